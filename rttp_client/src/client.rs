@@ -1,21 +1,254 @@
-use crate::{Config, error};
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::{Config, Download, SegmentedDownload, error};
 #[cfg(feature = "async")]
 use crate::connection::AsyncConnection;
-use crate::connection::BlockConnection;
-use crate::request::{RawRequest, Request};
-use crate::response::Response;
-use crate::types::{Header, IntoHeader, IntoPara, Proxy, ToFormData, ToRoUrl};
+use crate::connection::{BlockConnection, ConnectionPool};
+use crate::request::{RawRequest, ReaderBody, Request};
+use crate::response::{Response, StreamResponse};
+#[cfg(feature = "async")]
+use crate::response::AsyncStreamResponse;
+use crate::types::{Auth, CookieStore, DigestCache, Header, HeaderMap, IntoHeader, IntoPara, Proxy, ToFormData, ToRoUrl};
+
+/// A long-lived http client, hold the settings shared by every request send from it.
+///
+/// `Client` is cheap to clone and can be shared across threads, each call of `request`
+/// (or `get`, `post` ...) hands out an independent `HttpClient` to build and emit one request.
+///
+/// # Examples
+/// ```rust
+/// # use rttp_client::{Client, Config};
+/// let client = Client::builder()
+///   .header(("Accept", "application/json"))
+///   .config(Config::builder().read_timeout(3000))
+///   .build();
+/// client.get().url("http://httpbin.org/get").emit();
+/// client.post().url("http://httpbin.org/post").emit();
+/// ```
+#[derive(Clone, Debug)]
+pub struct Client {
+  inner: Arc<ClientInner>,
+}
 
 #[derive(Debug)]
+struct ClientInner {
+  headers: HeaderMap,
+  user_agent: Option<String>,
+  bodyless_content_type: bool,
+  config: Config,
+  proxy: Option<Proxy>,
+  cookie_store: Option<CookieStore>,
+  pool: ConnectionPool,
+  digest: DigestCache,
+}
+
+impl Default for Client {
+  fn default() -> Self {
+    ClientBuilder::default().build()
+  }
+}
+
+impl Client {
+  /// Create a `Client` with default settings.
+  pub fn new() -> Self {
+    Default::default()
+  }
+
+  pub fn builder() -> ClientBuilder {
+    ClientBuilder::new()
+  }
+
+  pub fn headers(&self) -> &HeaderMap { &self.inner.headers }
+  pub fn user_agent(&self) -> &Option<String> { &self.inner.user_agent }
+  pub fn bodyless_content_type(&self) -> bool { self.inner.bodyless_content_type }
+  pub fn config(&self) -> &Config { &self.inner.config }
+  pub fn proxy(&self) -> &Option<Proxy> { &self.inner.proxy }
+  pub fn cookie_store(&self) -> &Option<CookieStore> { &self.inner.cookie_store }
+
+  pub(crate) fn pool(&self) -> &ConnectionPool { &self.inner.pool }
+  pub(crate) fn digest_cache(&self) -> &DigestCache { &self.inner.digest }
+
+  /// Count of idle keep-alive connections hold by this client.
+  pub fn idle_connections(&self) -> usize {
+    self.inner.pool.idle_count()
+  }
+
+  /// Create a new request use this client settings.
+  pub fn request(&self) -> HttpClient {
+    HttpClient::with_request(Request::with_client(self.clone()))
+  }
+
+  /// Create a get request
+  pub fn get(&self) -> HttpClient {
+    self.method("GET")
+  }
+
+  /// Create a post request
+  pub fn post(&self) -> HttpClient {
+    self.method("POST")
+  }
+
+  /// Create a put request
+  pub fn put(&self) -> HttpClient {
+    self.method("PUT")
+  }
+
+  /// Create a delete request
+  pub fn delete(&self) -> HttpClient {
+    self.method("DELETE")
+  }
+
+  /// Create a options request
+  pub fn options(&self) -> HttpClient {
+    self.method("OPTIONS")
+  }
+
+  /// Create a head request
+  pub fn head(&self) -> HttpClient {
+    self.method("HEAD")
+  }
+
+  /// Create a request by method
+  pub fn method<S: AsRef<str>>(&self, method: S) -> HttpClient {
+    let mut client = self.request();
+    client.method(method);
+    client
+  }
+}
+
+
+#[derive(Clone, Debug)]
+pub struct ClientBuilder {
+  headers: HeaderMap,
+  user_agent: Option<String>,
+  bodyless_content_type: bool,
+  config: Config,
+  proxy: Option<Proxy>,
+  cookie_store: Option<CookieStore>,
+  pool_idle_timeout: u64,
+  pool_max_idle_per_host: usize,
+  pool_max_total: usize,
+}
+
+impl Default for ClientBuilder {
+  fn default() -> Self {
+    let pool = ConnectionPool::default();
+    Self {
+      headers: HeaderMap::new(),
+      user_agent: Some(format!("Mozilla/5.0 rttp/{}", env!("CARGO_PKG_VERSION"))),
+      bodyless_content_type: true,
+      config: Default::default(),
+      proxy: None,
+      cookie_store: None,
+      pool_idle_timeout: pool.idle_timeout(),
+      pool_max_idle_per_host: pool.max_idle_per_host(),
+      pool_max_total: pool.max_total(),
+    }
+  }
+}
+
+impl ClientBuilder {
+  pub fn new() -> Self {
+    Default::default()
+  }
+
+  pub fn build(&self) -> Client {
+    Client {
+      inner: Arc::new(ClientInner {
+        headers: self.headers.clone(),
+        user_agent: self.user_agent.clone(),
+        bodyless_content_type: self.bodyless_content_type,
+        config: self.config.clone(),
+        proxy: self.proxy.clone(),
+        cookie_store: self.cookie_store.clone(),
+        pool: ConnectionPool::new(self.pool_idle_timeout, self.pool_max_idle_per_host, self.pool_max_total),
+        digest: Default::default(),
+      })
+    }
+  }
+
+  /// Add default header, all request send from this client will have this header.
+  /// The default header of the same name is replaced.
+  pub fn header<P: IntoHeader>(&mut self, header: P) -> &mut Self {
+    self.headers.insert_all(header.into_headers());
+    self
+  }
+
+  /// Add default header, keep the default headers of the same name.
+  pub fn header_append<P: IntoHeader>(&mut self, header: P) -> &mut Self {
+    self.headers.extend(header.into_headers());
+    self
+  }
+
+  /// Set `User-Agent` of all requests, default is `Mozilla/5.0 rttp/<version>`.
+  /// A `User-Agent` header set by `header` take precedence.
+  pub fn user_agent<S: AsRef<str>>(&mut self, user_agent: S) -> &mut Self {
+    self.user_agent = Some(user_agent.as_ref().to_string());
+    self
+  }
+
+  /// Do not send `User-Agent` unless the request set it by `header`.
+  pub fn no_user_agent(&mut self) -> &mut Self {
+    self.user_agent = None;
+    self
+  }
+
+  /// Whether send the automatic `Content-Type` for the request have no body, default is true.
+  /// A `Content-Type` header set by `header` is always sent.
+  pub fn bodyless_content_type(&mut self, bodyless_content_type: bool) -> &mut Self {
+    self.bodyless_content_type = bodyless_content_type;
+    self
+  }
+
+  /// Set default config
+  pub fn config<C: AsRef<Config>>(&mut self, config: C) -> &mut Self {
+    self.config = config.as_ref().clone();
+    self
+  }
+
+  /// Set default proxy
+  pub fn proxy<P: AsRef<Proxy>>(&mut self, proxy: P) -> &mut Self {
+    self.proxy = Some(proxy.as_ref().clone());
+    self
+  }
+
+  /// Set cookie store, the cookies of responses are stored and sent back by following requests.
+  /// The store is shared with the given one.
+  pub fn cookie_store<S: AsRef<CookieStore>>(&mut self, cookie_store: S) -> &mut Self {
+    self.cookie_store = Some(cookie_store.as_ref().clone());
+    self
+  }
+
+  /// Idle keep-alive connection will be closed after this time (millisecond)
+  pub fn pool_idle_timeout(&mut self, idle_timeout: u64) -> &mut Self {
+    self.pool_idle_timeout = idle_timeout;
+    self
+  }
+
+  /// Max count of idle keep-alive connections for each host, set 0 to disable keep-alive
+  pub fn pool_max_idle_per_host(&mut self, max_idle_per_host: usize) -> &mut Self {
+    self.pool_max_idle_per_host = max_idle_per_host;
+    self
+  }
+
+  /// Max count of idle keep-alive connections for all hosts
+  pub fn pool_max_total(&mut self, max_total: usize) -> &mut Self {
+    self.pool_max_total = max_total;
+    self
+  }
+}
+
+
+#[derive(Clone, Debug)]
 pub struct HttpClient {
   request: Request,
 }
 
 impl Default for HttpClient {
   fn default() -> Self {
-    Self {
-      request: Request::new()
-    }
+    Client::new().request()
   }
 }
 
@@ -40,18 +273,18 @@ impl HttpClient {
 
 impl HttpClient {
 
-  /// Set count of this request auto redirect times.
-  pub(crate) fn count(&mut self, count: u32) -> &mut Self {
-    self.request.count_set(count);
-    self
-  }
-
   /// Reset this request, The request only use once, This function can reset request.
+  /// The settings of the `Client` this request created from will be kept.
   pub fn reset(&mut self) -> &mut Self {
-    self.request = Request::new();
+    self.request = Request::with_client(self.request.client().clone());
     self
   }
 
+  /// The `Client` this request created from.
+  pub fn client(&self) -> &Client {
+    self.request.client()
+  }
+
   /// Set get request
   pub fn get(&mut self) -> &mut Self {
     self.method("GET")
@@ -125,30 +358,34 @@ impl HttpClient {
     self
   }
 
-  /// Not support now
-  pub fn auth(&mut self) -> &mut Self {
-    unimplemented!()
+  /// Set request authentication, a `Authorization` header set by `header` take precedence.
+  /// If not set, the username and password of url will be used as basic auth.
+  pub fn auth<A: AsRef<Auth>>(&mut self, auth: A) -> &mut Self {
+    self.request.auth_set(auth.as_ref().clone());
+    self
   }
 
- ///  Add request header
+  /// Add request header, the header of the same name is replaced, except `Cookie` is joined.
+  /// To send multiple headers of the same name, use `header_append`.
+  /// `Content-Length` is computed from body, the value set must be the body length.
   pub fn header<P: IntoHeader>(&mut self, header: P) -> &mut Self {
-    let mut headers = self.request.headers_mut();
-    for h in header.into_headers() {
-      let mut exi = headers.iter_mut()
-        .find(|d| d.name().eq_ignore_ascii_case(h.name()));
-
-      if let Some(eh) = exi {
-        if h.name().eq_ignore_ascii_case("cookie") {
-          let new_cookie_value = format!("{};{}", eh.value(), h.value());
-          eh.replace(Header::new("Cookie", new_cookie_value));
-          continue;
-        }
-
-        eh.replace(h);
-        continue;
-      }
-      headers.push(h);
+    let (cookies, headers): (Vec<Header>, Vec<Header>) = header.into_headers().into_iter()
+      .partition(|h| h.name().eq_ignore_ascii_case("cookie"));
+    let map = self.request.headers_mut();
+    for cookie in cookies {
+      let value = match map.get("cookie") {
+        Some(exists) => format!("{};{}", exists, cookie.value()),
+        None => cookie.value().clone(),
+      };
+      map.insert("Cookie", value);
     }
+    map.insert_all(headers);
+    self
+  }
+
+  /// Add request header, keep the headers of the same name.
+  pub fn header_append<P: IntoHeader>(&mut self, header: P) -> &mut Self {
+    self.request.headers_mut().extend(header.into_headers());
     self
   }
 
@@ -190,6 +427,76 @@ impl HttpClient {
     self
   }
 
+  /// Set request body read from the reader while sending, it's not hold in memory.
+  /// The body is sent with `Content-Length` if the length is known, otherwise by chunked transfer encoding.
+  /// The reader is read only once, the request can't be sent again, or redirected with the body.
+  /// When sent by `rasync`, the reader is read by a blocking task, not the thread run the future.
+  ///
+  /// # Examples
+  /// ```rust
+  /// # use rttp_client::HttpClient;
+  /// # fn run() -> Result<(), Box<dyn std::error::Error>> {
+  /// let file = std::fs::File::open("data.bin")?;
+  /// let length = file.metadata()?.len();
+  /// HttpClient::new()
+  ///   .post()
+  ///   .url("http://httpbin.org/post")
+  ///   .reader(file, Some(length))
+  ///   .emit()?;
+  /// # Ok(())
+  /// # }
+  /// ```
+  pub fn reader<R: io::Read + Send + 'static>(&mut self, reader: R, length: Option<u64>) -> &mut Self {
+    self.request.reader_set(ReaderBody::new(reader, length));
+    self
+  }
+
+  /// Same as `reader`, the body is read from `async_std::io::Read`.
+  #[cfg(feature = "async")]
+  pub fn async_reader<R>(&mut self, reader: R, length: Option<u64>) -> &mut Self
+    where
+      R: async_std::io::Read + Unpin + Send + 'static,
+  {
+    self.request.reader_set(ReaderBody::with_async(reader, length));
+    self
+  }
+
+  /// Send the body by chunked transfer encoding even if the length is known, each read of
+  /// the reader is written as a chunk. `Content-Length` header can't be set with it.
+  ///
+  /// # Examples
+  /// ```rust
+  /// # use rttp_client::HttpClient;
+  /// # fn run() -> Result<(), Box<dyn std::error::Error>> {
+  /// let archive = std::process::Command::new("tar")
+  ///   .args(&["-cz", "/path/to/dir"])
+  ///   .stdout(std::process::Stdio::piped())
+  ///   .spawn()?
+  ///   .stdout
+  ///   .take()
+  ///   .unwrap();
+  /// HttpClient::new()
+  ///   .put()
+  ///   .url("http://httpbin.org/put")
+  ///   .chunked(true)
+  ///   .reader(archive, None)
+  ///   .trailer(("X-Checksum", "sha256"))
+  ///   .emit()?;
+  /// # Ok(())
+  /// # }
+  /// ```
+  pub fn chunked(&mut self, chunked: bool) -> &mut Self {
+    self.request.chunked_set(chunked);
+    self
+  }
+
+  /// Add request trailer sent after the last chunk, the header of the same name is replaced.
+  /// Trailers are only sent with chunked body, the `Trailer` header is set by names of them.
+  pub fn trailer<P: IntoHeader>(&mut self, trailer: P) -> &mut Self {
+    self.request.trailers_mut().insert_all(trailer.into_headers());
+    self
+  }
+
   /// emit a request
   ///
   /// # Examples
@@ -229,4 +536,71 @@ impl HttpClient {
     let request = RawRequest::async_new(&mut self.request).await?;
     AsyncConnection::new(request).async_call().await
   }
+
+  /// Emit a request, returns once the status line and headers are read,
+  /// the body is not hold in memory, but read from the returned `StreamResponse` as it arrives.
+  ///
+  /// # Examples
+  /// ```rust
+  /// # use rttp_client::HttpClient;
+  /// # fn run() -> Result<(), Box<dyn std::error::Error>> {
+  /// let mut response = HttpClient::new()
+  ///   .url("http://httpbin.org/get")
+  ///   .emit_stream()?;
+  /// std::io::copy(&mut response, &mut std::io::stdout())?;
+  /// # Ok(())
+  /// # }
+  /// ```
+  pub fn emit_stream(&mut self) -> error::Result<StreamResponse> {
+    if self.request.closed() {
+      return Err(error::connection_closed());
+    }
+    let request = RawRequest::block_new(&mut self.request)?;
+    BlockConnection::new(request).block_stream()
+  }
+
+  /// Async version of `emit_stream`.
+  #[cfg(feature = "async")]
+  pub async fn rasync_stream(&mut self) -> error::Result<AsyncStreamResponse> {
+    if self.request.closed() {
+      return Err(error::connection_closed());
+    }
+    let request = RawRequest::async_new(&mut self.request).await?;
+    AsyncConnection::new(request).async_stream().await
+  }
+
+  /// Download the response body to a file, the download is continued if it's failed before.
+  ///
+  /// # Examples
+  /// ```rust
+  /// # use rttp_client::HttpClient;
+  /// # fn run() -> Result<(), Box<dyn std::error::Error>> {
+  /// HttpClient::new()
+  ///   .url("http://httpbin.org/bytes/1024")
+  ///   .download("bytes.bin")
+  ///   .emit()?;
+  /// # Ok(())
+  /// # }
+  /// ```
+  pub fn download<'a, P: AsRef<Path>>(&mut self, path: P) -> Download<'a> {
+    Download::new(self.request.clone(), path.as_ref().to_path_buf())
+  }
+
+  /// Download the file by concurrent range requests, the server must support `Range`.
+  ///
+  /// # Examples
+  /// ```rust
+  /// # use rttp_client::HttpClient;
+  /// # fn run() -> Result<(), Box<dyn std::error::Error>> {
+  /// HttpClient::new()
+  ///   .url("http://httpbin.org/range/102400")
+  ///   .segmented_download("range.bin")
+  ///   .segments(4)
+  ///   .emit()?;
+  /// # Ok(())
+  /// # }
+  /// ```
+  pub fn segmented_download<'a, P: AsRef<Path>>(&mut self, path: P) -> SegmentedDownload<'a> {
+    SegmentedDownload::new(self.request.clone(), path.as_ref().to_path_buf())
+  }
 }
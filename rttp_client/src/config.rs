@@ -1,3 +1,5 @@
+use crate::types::RedirectPolicy;
+
 //use std::{collections::HashMap, sync::Mutex};
 //
 //use once_cell::sync::Lazy;
@@ -7,13 +9,13 @@
 //  Mutex::new(config)
 //});
 
-
 #[derive(Clone, Debug)]
 pub struct Config {
   read_timeout: u64,
   write_timeout: u64,
   auto_redirect: bool,
   max_redirect: u32,
+  redirect: Option<RedirectPolicy>,
 }
 
 impl Default for Config {
@@ -38,6 +40,15 @@ impl Config {
   pub fn write_timeout(&self) -> u64 { self.write_timeout }
   pub fn auto_redirect(&self) -> bool { self.auto_redirect }
   pub fn max_redirect(&self) -> u32 { self.max_redirect }
+
+  /// The redirect policy, if not set, it's follow `max_redirect` redirects when `auto_redirect` is true.
+  pub fn redirect(&self) -> RedirectPolicy {
+    match &self.redirect {
+      Some(policy) => policy.clone(),
+      None if self.auto_redirect => RedirectPolicy::limited(self.max_redirect),
+      None => RedirectPolicy::none(),
+    }
+  }
 }
 
 
@@ -54,6 +65,7 @@ impl ConfigBuilder {
         write_timeout: 5000,
         auto_redirect: false,
         max_redirect: 3,
+        redirect: None,
       }
     }
   }
@@ -78,6 +90,11 @@ impl ConfigBuilder {
     self.config.max_redirect = max_redirect;
     self
   }
+  /// Set redirect policy, it's take precedence over `auto_redirect` and `max_redirect`.
+  pub fn redirect<P: AsRef<RedirectPolicy>>(&mut self, policy: P) -> &mut Self {
+    self.config.redirect = Some(policy.as_ref().clone());
+    self
+  }
 }
 
 impl AsRef<Config> for Config {
@@ -1,16 +1,24 @@
+#[cfg(feature = "tls-rustls")]
 use std::sync::Arc;
 
+use async_std::net::TcpStream;
 use async_std::prelude::*;
-use socks::{Socks4Stream, Socks5Stream};
 use url::Url;
 
+use crate::connection::async_proxy;
+#[cfg(any(feature = "tls-native", feature = "tls-rustls"))]
+use crate::connection::async_tls;
 use crate::connection::connection::Connection;
-use crate::connection::connection_reader::ConnectionReader;
-use crate::error;
+use crate::connection::connection_reader::{AsyncBodyReader, AsyncConnectionReader};
+use crate::{error, HttpClient};
 use crate::request::RawRequest;
-use crate::response::Response;
-use crate::types::{Proxy, ProxyType, ToUrl};
-use crate::connection::async_std_io_block::AsyncToBlockStream;
+use crate::response::{AsyncStreamResponse, Response};
+use crate::types::{Proxy, ProxyType};
+
+/// An async stream can be read and written, plain tcp stream or tls stream.
+pub trait AsyncStream: async_std::io::Read + async_std::io::Write + Unpin + Send {}
+
+impl<T: async_std::io::Read + async_std::io::Write + Unpin + Send> AsyncStream for T {}
 
 pub struct AsyncConnection<'a> {
   conn: Connection<'a>
@@ -23,26 +31,46 @@ impl<'a> AsyncConnection<'a> {
 
   pub async fn async_call(mut self) -> error::Result<Response> {
     let url = self.conn.url().map_err(error::builder)?;
-    let proxy = self.conn.proxy();
-    let binary = if let Some(proxy) = proxy {
-      self.call_with_proxy(&url, proxy).await?
-    } else {
-      self.async_send(&url).await?
-    };
-//    let binary = self.async_send(&url).await?;
-
-    let response = Response::new(self.conn.rourl().clone(), binary)?;
+    let stream = self.async_connect(&url).await?;
+    let mut response = self.async_send_with_stream(&url, stream).await?;
+
+    response.history_set(self.conn.history(&url));
+    self.conn.store_cookies(&url, &response);
+
+    if let Some(request) = self.conn.next_request(&url, &response)? {
+      self.conn.closed_set(true);
+      // the next request call `async_call` again, box it to break the recursive future
+      return Box::pin(HttpClient::with_request(request).rasync()).await;
+    }
+
     self.conn.closed_set(true);
     Ok(response)
   }
+
+  /// Same as `async_call`, but returns once the head of response is read.
+  pub async fn async_stream(mut self) -> error::Result<AsyncStreamResponse> {
+    let url = self.conn.url().map_err(error::builder)?;
+    let stream = self.async_connect(&url).await?;
+    let (mut response, body) = self.async_open_with_stream(&url, stream).await?;
+
+    response.history_set(self.conn.history(&url));
+    self.conn.store_cookies(&url, &response);
+
+    if let Some(request) = self.conn.next_request(&url, &response)? {
+      // the body of redirect response is not needed, the connection is closed
+      drop(body);
+      self.conn.closed_set(true);
+      return Box::pin(HttpClient::with_request(request).rasync_stream()).await;
+    }
+
+    self.conn.closed_set(true);
+    AsyncStreamResponse::new(response, body)
+  }
 }
 
 impl<'a> AsyncConnection<'a> {
-  async fn async_tcp_stream(&self, addr: &String) -> error::Result<async_std::net::TcpStream> {
-//    let async_stream = self.async_tcp_stream(addr)?;
-//    Ok(async_std::net::TcpStream::from(async_stream))
-
-    let stream = async_std::net::TcpStream::connect(addr).await.map_err(error::request)?;
+  async fn async_tcp_stream(&self, addr: &String) -> error::Result<TcpStream> {
+    let stream = TcpStream::connect(addr).await.map_err(error::request)?;
     // todo: async_std tcp stream set timeout?
     Ok(stream)
   }
@@ -54,171 +82,130 @@ impl<'a> AsyncConnection<'a> {
     let header = self.conn.header();
     let body = self.conn.body();
 
-    stream.write(header.as_bytes()).await.map_err(error::request)?;
+    stream.write_all(header.as_bytes()).await.map_err(error::request)?;
     if let Some(body) = body {
-      stream.write(body.bytes()).await.map_err(error::request)?;
+      body.async_write(stream).await.map_err(error::request)?;
     }
     stream.flush().await.map_err(error::request)?;
 
     Ok(())
   }
 
-  async fn async_read_stream<S>(&self, url: &Url, stream: &mut S) -> error::Result<Vec<u8>>
+  async fn async_read_stream<S>(&self, stream: &mut S) -> error::Result<Response>
     where
       S: async_std::io::Read + std::marker::Unpin,
   {
-//    let mut reader = ConnectionReader::new(url, stream);
-//    reader.binary()
-
-    let mut buffer = vec![0u8; 1024];
-    let _ = stream.read(&mut buffer).await.map_err(error::request)?;
-    Ok(buffer)
+    let mut reader = AsyncConnectionReader::new(self.conn.rourl(), self.conn.method(), stream);
+    match reader.response().await? {
+      Some((response, _)) => Ok(response),
+      None => Err(error::bad_response("Connection closed before response")),
+    }
   }
 }
 
 // connection send
 impl<'a> AsyncConnection<'a> {
-  async fn async_send(&self, url: &Url) -> error::Result<Vec<u8>> {
-    let addr = self.conn.addr(url)?;
-    let mut stream = self.async_tcp_stream(&addr).await?;
-
-    self.async_send_with_stream(url, stream).await
+  /// Connect to the url host, through the proxy if have.
+  async fn async_connect(&self, url: &Url) -> error::Result<TcpStream> {
+    match self.conn.proxy() {
+      Some(proxy) => self.proxy_stream(url, proxy).await,
+      None => self.async_tcp_stream(&self.conn.addr(url)?).await,
+    }
   }
 
-  async fn async_send_with_stream(&self, url: &Url, mut stream: async_std::net::TcpStream)
-                                  -> error::Result<Vec<u8>> {
-    match url.scheme() {
-      "http" => self.async_send_http(url, stream).await,
-      "https" => self.async_send_https(url, stream).await,
-      _ => return Err(error::url_bad_scheme(url.clone()))
-    }
+  async fn async_send_with_stream(&self, url: &Url, stream: TcpStream) -> error::Result<Response> {
+    let mut stream = self.async_secure(url, stream).await?;
+    self.async_write_stream(&mut stream).await?;
+    self.async_read_stream(&mut stream).await
   }
 
-  async fn async_send_http(&self, url: &Url, mut stream: async_std::net::TcpStream)
-                           -> error::Result<Vec<u8>> {
+  async fn async_open_with_stream(&self, url: &Url, stream: TcpStream)
+                                  -> error::Result<(Response, AsyncBodyReader<Box<dyn AsyncStream>>)> {
+    let mut stream = self.async_secure(url, stream).await?;
     self.async_write_stream(&mut stream).await?;
-    self.async_read_stream(url, &mut stream).await
+    AsyncConnectionReader::new(self.conn.rourl(), self.conn.method(), stream)
+      .response_head().await?
+      .ok_or_else(|| error::bad_response("Connection closed before response"))
+  }
+
+  /// Wrap the stream with tls if it's https url.
+  async fn async_secure(&self, url: &Url, stream: TcpStream) -> error::Result<Box<dyn AsyncStream>> {
+    match url.scheme() {
+      "http" => Ok(Box::new(stream)),
+      "https" => self.async_tls_stream(url, stream).await,
+      _ => Err(error::url_bad_scheme(url.clone()))
+    }
   }
 
   #[cfg(not(any(feature = "tls-native", feature = "tls-rustls")))]
-  async fn async_send_https(&self, url: &Url, mut stream: async_std::net::TcpStream)
-                            -> error::Result<Vec<u8>> {
-    return Err(error::no_request_features("Not have any tls features, Can't request a https url"));
+  async fn async_tls_stream(&self, _url: &Url, _stream: TcpStream) -> error::Result<Box<dyn AsyncStream>> {
+    Err(error::no_request_features("Not have any tls features, Can't request a https url"))
   }
 
   #[cfg(feature = "tls-native")]
-  async fn async_send_https(&self, url: &Url, mut stream: async_std::net::TcpStream) -> error::Result<Vec<u8>> {
-    let mut stream = AsyncToBlockStream::new(stream);
+  async fn async_tls_stream(&self, url: &Url, stream: TcpStream) -> error::Result<Box<dyn AsyncStream>> {
     let connector = native_tls::TlsConnector::builder().build().map_err(error::request)?;
-    let mut ssl_stream;
-//  if self.verify {
-    ssl_stream = connector.connect(&self.conn.host(url)?[..], stream).map_err(error::request)?;
-//  } else {
-//    ssl_stream = connector.danger_connect_without_providing_domain_for_certificate_verification_and_server_name_indication(stream).map_err(error::request)?;
-//  }
-
-
-    // fixme: block to async
-//    self.async_write_stream(&mut ssl_stream).await?;
-//    self.async_read_stream(url, &mut ssl_stream).await
-    self.conn.block_write_stream(&mut ssl_stream)?;
-    self.conn.block_read_stream(url, &mut ssl_stream)
+    let tls = async_tls::native_tls_connect(connector, &self.conn.host(url)?[..], stream).await?;
+    Ok(Box::new(tls))
   }
 
   #[cfg(feature = "tls-rustls")]
-  async fn async_send_https(&self, url: &Url, mut stream: async_std::net::TcpStream)
-                            -> error::Result<Vec<u8>> {
-    let mut stream = AsyncToBlockStream::new(stream);
+  async fn async_tls_stream(&self, url: &Url, stream: TcpStream) -> error::Result<Box<dyn AsyncStream>> {
     let mut config = rustls::ClientConfig::new();
     config
       .root_store
       .add_server_trust_anchors(&webpki_roots::TLS_SERVER_ROOTS);
     let rc_config = Arc::new(config);
     let host = self.conn.host(url)?;
-    let dns_name = webpki::DNSNameRef::try_from_ascii_str(&host[..]).unwrap();
-    let mut client = rustls::ClientSession::new(&rc_config, dns_name);
-    let mut tls = rustls::Stream::new(&mut client, &mut stream);
-
-    // fixme: block to async
-//    self.async_write_stream(&mut tls).await?;
-//    self.async_read_stream(url, &mut tls).await
-    self.conn.block_write_stream(&mut tls)?;
-    self.conn.block_read_stream(url, &mut tls)
+    let dns_name = webpki::DNSNameRef::try_from_ascii_str(&host[..])
+      .map_err(|_| error::bad_ssl("Bad dns name."))?;
+    let client = rustls::ClientSession::new(&rc_config, dns_name);
+    let stream = rustls::StreamOwned::new(client, async_tls::StdAdapter::new(stream));
+    Ok(Box::new(async_tls::AsyncTlsStream::new(stream)))
   }
 }
 
 // proxy connection
 impl<'a> AsyncConnection<'a> {
-  async fn call_with_proxy(&self, url: &Url, proxy: &Proxy) -> error::Result<Vec<u8>> {
+  /// Connect to the url host through the proxy.
+  async fn proxy_stream(&self, url: &Url, proxy: &Proxy) -> error::Result<TcpStream> {
     match proxy.type_() {
-      ProxyType::HTTP => self.call_with_proxy_https(url, proxy).await,
-      ProxyType::HTTPS => self.call_with_proxy_https(url, proxy).await,
-      ProxyType::SOCKS4 => self.call_with_proxy_socks4(url, proxy).await,
-      ProxyType::SOCKS5 => self.call_with_proxy_socks5(url, proxy).await,
+      ProxyType::HTTP => self.proxy_stream_https(url, proxy).await,
+      ProxyType::HTTPS => self.proxy_stream_https(url, proxy).await,
+      ProxyType::SOCKS4 => self.proxy_stream_socks4(url, proxy).await,
+      ProxyType::SOCKS5 => self.proxy_stream_socks5(url, proxy).await,
     }
   }
 
-//  fn call_with_proxy_http(&self, url: &Url, proxy: &Proxy) -> error::Result<Vec<u8>> {
-//    let header = self.request.header();
-//    let body = self.request.body();
-//
-//    let addr = format!("{}:{}", proxy.host(), proxy.port());
-//    let mut stream = self.tcp_stream(&addr)?;
-//    self.call_tcp_stream_http(stream)
-//  }
-
-  async fn call_with_proxy_https(&self, url: &Url, proxy: &Proxy) -> error::Result<Vec<u8>> {
+  async fn proxy_stream_https(&self, url: &Url, proxy: &Proxy) -> error::Result<TcpStream> {
     let connect_header = self.conn.proxy_header(url, proxy)?;
 
     let addr = format!("{}:{}", proxy.host(), proxy.port());
     let mut stream = self.async_tcp_stream(&addr).await?;
-
-    stream.write(connect_header.as_bytes()).await.map_err(error::request)?;
-    stream.flush().await.map_err(error::request)?;
-
-    //HTTP/1.1 200 Connection Established
-    let mut res = [0u8; 1024];
-    stream.read(&mut res).await.map_err(error::request)?;
-
-    let res_s = match String::from_utf8(res.to_vec()) {
-      Ok(r) => r,
-      Err(_) => return Err(error::bad_proxy("parse proxy server response error."))
-    };
-    if !res_s.to_ascii_lowercase().contains("connection established") {
-      return Err(error::bad_proxy("Proxy server response error."));
-    }
-
-    self.async_send_with_stream(url, stream).await
+    async_proxy::connect(&mut stream, &connect_header).await?;
+    Ok(stream)
   }
 
-  async fn call_with_proxy_socks4(&self, url: &Url, proxy: &Proxy) -> error::Result<Vec<u8>> {
+  async fn proxy_stream_socks4(&self, url: &Url, proxy: &Proxy) -> error::Result<TcpStream> {
     let addr_proxy = format!("{}:{}", proxy.host(), proxy.port());
-    let addr_target = self.conn.addr(url)?;
-    let user = if let Some(u) = proxy.username() { u.to_string() } else { "".to_string() };
-    let mut stream = Socks4Stream::connect(&addr_proxy[..], &addr_target[..], &user[..])
-      .map_err(error::request)?;
-    // fixme: block to async
-//    let mut stream = BlockToAsyncStream::new(&mut stream);
-//    self.async_send_with_stream(url, &mut stream).await
-    self.conn.block_send_with_stream(url, &mut stream)
+    let host = self.conn.host(url)?;
+    let port = self.conn.port(url)?;
+    let user = proxy.username().as_deref().unwrap_or_default();
+
+    let mut stream = self.async_tcp_stream(&addr_proxy).await?;
+    async_proxy::socks4(&mut stream, &host, port, user).await?;
+    Ok(stream)
   }
 
-  async fn call_with_proxy_socks5(&self, url: &Url, proxy: &Proxy) -> error::Result<Vec<u8>> {
+  async fn proxy_stream_socks5(&self, url: &Url, proxy: &Proxy) -> error::Result<TcpStream> {
     let addr_proxy = format!("{}:{}", proxy.host(), proxy.port());
-    let addr_target = self.conn.addr(url)?;
-    let mut stream = if let Some(u) = proxy.username() {
-      if let Some(p) = proxy.password() {
-        Socks5Stream::connect_with_password(&addr_proxy[..], &addr_target[..], &u[..], &p[..])
-      } else {
-        Socks5Stream::connect_with_password(&addr_proxy[..], &addr_target[..], &u[..], "")
-      }
-    } else {
-      Socks5Stream::connect(&addr_proxy[..], &addr_target[..])
-    }.map_err(error::request)?;
-    // fixme: block to async
-//    let mut stream = BlockToAsyncStream::new(&mut stream);
-//    self.async_send_with_stream(url, &mut stream).await
-    self.conn.block_send_with_stream(url, &mut stream)
+    let host = self.conn.host(url)?;
+    let port = self.conn.port(url)?;
+    let username = proxy.username().as_deref();
+    let password = proxy.password().as_deref();
+
+    let mut stream = self.async_tcp_stream(&addr_proxy).await?;
+    async_proxy::socks5(&mut stream, &host, port, username, password).await?;
+    Ok(stream)
   }
 }
-
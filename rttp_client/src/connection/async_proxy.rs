@@ -0,0 +1,146 @@
+use std::net::IpAddr;
+
+use async_std::net::TcpStream;
+use async_std::prelude::*;
+
+use crate::error;
+
+/// Socks4 handshake, the domain target use socks4a.
+pub async fn socks4(stream: &mut TcpStream, host: &str, port: u16, user: &str) -> error::Result<()> {
+  let mut packet = vec![4u8, 1];
+  packet.extend_from_slice(&port.to_be_bytes());
+  match host.parse::<IpAddr>() {
+    Ok(IpAddr::V4(ip)) => {
+      packet.extend_from_slice(&ip.octets());
+      packet.extend_from_slice(user.as_bytes());
+      packet.push(0);
+    }
+    Ok(IpAddr::V6(_)) => return Err(error::bad_proxy("Socks4 not support ipv6 address.")),
+    Err(_) => {
+      packet.extend_from_slice(&[0, 0, 0, 1]);
+      packet.extend_from_slice(user.as_bytes());
+      packet.push(0);
+      packet.extend_from_slice(host.as_bytes());
+      packet.push(0);
+    }
+  }
+  stream.write_all(&packet).await.map_err(error::request)?;
+
+  let mut reply = [0u8; 8];
+  stream.read_exact(&mut reply).await.map_err(error::request)?;
+  if reply[0] != 0 {
+    return Err(error::bad_proxy("Bad socks4 proxy response."));
+  }
+  match reply[1] {
+    90 => Ok(()),
+    91 => Err(error::bad_proxy("Socks4 request rejected or failed.")),
+    92 | 93 => Err(error::bad_proxy("Socks4 request rejected because of identd.")),
+    _ => Err(error::bad_proxy("Bad socks4 proxy response.")),
+  }
+}
+
+/// Socks5 handshake, use username/password authentication if the username is provided.
+pub async fn socks5(stream: &mut TcpStream, host: &str, port: u16, username: Option<&str>, password: Option<&str>)
+                    -> error::Result<()> {
+  let greeting: &[u8] = if username.is_some() { &[5, 2, 0, 2] } else { &[5, 1, 0] };
+  stream.write_all(greeting).await.map_err(error::request)?;
+
+  let mut reply = [0u8; 2];
+  stream.read_exact(&mut reply).await.map_err(error::request)?;
+  if reply[0] != 5 {
+    return Err(error::bad_proxy("Bad socks5 proxy response."));
+  }
+  match reply[1] {
+    0 => {}
+    2 => {
+      let username = username.unwrap_or_default();
+      let password = password.unwrap_or_default();
+      if username.len() > 255 || password.len() > 255 {
+        return Err(error::bad_proxy("Socks5 username or password too long."));
+      }
+      let mut packet = vec![1u8, username.len() as u8];
+      packet.extend_from_slice(username.as_bytes());
+      packet.push(password.len() as u8);
+      packet.extend_from_slice(password.as_bytes());
+      stream.write_all(&packet).await.map_err(error::request)?;
+
+      let mut reply = [0u8; 2];
+      stream.read_exact(&mut reply).await.map_err(error::request)?;
+      if reply[1] != 0 {
+        return Err(error::bad_proxy("Socks5 authentication failed."));
+      }
+    }
+    _ => return Err(error::bad_proxy("No acceptable socks5 authentication method.")),
+  }
+
+  let mut packet = vec![5u8, 1, 0];
+  match host.parse::<IpAddr>() {
+    Ok(IpAddr::V4(ip)) => {
+      packet.push(1);
+      packet.extend_from_slice(&ip.octets());
+    }
+    Ok(IpAddr::V6(ip)) => {
+      packet.push(4);
+      packet.extend_from_slice(&ip.octets());
+    }
+    Err(_) => {
+      if host.len() > 255 {
+        return Err(error::bad_proxy("Socks5 target domain too long."));
+      }
+      packet.push(3);
+      packet.push(host.len() as u8);
+      packet.extend_from_slice(host.as_bytes());
+    }
+  }
+  packet.extend_from_slice(&port.to_be_bytes());
+  stream.write_all(&packet).await.map_err(error::request)?;
+
+  let mut reply = [0u8; 4];
+  stream.read_exact(&mut reply).await.map_err(error::request)?;
+  if reply[0] != 5 {
+    return Err(error::bad_proxy("Bad socks5 proxy response."));
+  }
+  if reply[1] != 0 {
+    return Err(error::bad_proxy(format!("Socks5 connect failed, reply code {}.", reply[1])));
+  }
+  // skip the bound address
+  let len = match reply[3] {
+    1 => 4,
+    4 => 16,
+    3 => {
+      let mut len = [0u8; 1];
+      stream.read_exact(&mut len).await.map_err(error::request)?;
+      len[0] as usize
+    }
+    _ => return Err(error::bad_proxy("Bad socks5 proxy response.")),
+  };
+  let mut bound = vec![0u8; len + 2];
+  stream.read_exact(&mut bound).await.map_err(error::request)?;
+  Ok(())
+}
+
+/// Http proxy tunnel, send `CONNECT` and wait the 2xx response head.
+pub async fn connect(stream: &mut TcpStream, connect_header: &str) -> error::Result<()> {
+  stream.write_all(connect_header.as_bytes()).await.map_err(error::request)?;
+  stream.flush().await.map_err(error::request)?;
+
+  // read byte by byte, the tunnel data after response head must be keep in stream
+  let mut head = Vec::new();
+  let mut byte = [0u8; 1];
+  while !head.ends_with(b"\r\n\r\n") {
+    let size = stream.read(&mut byte).await.map_err(error::request)?;
+    if size == 0 {
+      return Err(error::bad_proxy("Proxy server closed connection."));
+    }
+    head.push(byte[0]);
+  }
+
+  let head = String::from_utf8_lossy(&head);
+  let code = head.split_whitespace().nth(1)
+    .and_then(|code| code.parse::<u32>().ok())
+    .ok_or_else(|| error::bad_proxy("parse proxy server response error."))?;
+  if !(200..300).contains(&code) {
+    return Err(error::bad_proxy(format!("Proxy server response error, status code {}.", code)));
+  }
+  Ok(())
+}
@@ -0,0 +1,145 @@
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll, Waker};
+
+use async_std::net::TcpStream;
+
+#[cfg(feature = "tls-native")]
+use crate::error;
+
+/// Blocking io view of an async tcp stream, the pending io is reported as `WouldBlock`,
+/// so the tls library can be driven by async poll without block the executor thread.
+pub struct StdAdapter {
+  stream: TcpStream,
+  waker: Option<Waker>,
+}
+
+impl StdAdapter {
+  pub fn new(stream: TcpStream) -> Self {
+    Self { stream, waker: None }
+  }
+
+  fn poll<R, F>(&mut self, f: F) -> io::Result<R>
+    where
+      F: FnOnce(Pin<&mut TcpStream>, &mut Context) -> Poll<io::Result<R>>,
+  {
+    let waker = match &self.waker {
+      Some(waker) => waker.clone(),
+      None => return Err(io::ErrorKind::WouldBlock.into()),
+    };
+    let mut cx = Context::from_waker(&waker);
+    match f(Pin::new(&mut self.stream), &mut cx) {
+      Poll::Ready(result) => result,
+      Poll::Pending => Err(io::ErrorKind::WouldBlock.into()),
+    }
+  }
+}
+
+impl io::Read for StdAdapter {
+  fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+    self.poll(|stream, cx| async_std::io::Read::poll_read(stream, cx, buf))
+  }
+}
+
+impl io::Write for StdAdapter {
+  fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    self.poll(|stream, cx| async_std::io::Write::poll_write(stream, cx, buf))
+  }
+
+  fn flush(&mut self) -> io::Result<()> {
+    self.poll(async_std::io::Write::poll_flush)
+  }
+}
+
+/// A tls stream work on `StdAdapter`.
+pub trait TlsIo: io::Read + io::Write + Unpin {
+  fn adapter(&mut self) -> &mut StdAdapter;
+}
+
+#[cfg(feature = "tls-native")]
+impl TlsIo for native_tls::TlsStream<StdAdapter> {
+  fn adapter(&mut self) -> &mut StdAdapter {
+    self.get_mut()
+  }
+}
+
+#[cfg(feature = "tls-rustls")]
+impl TlsIo for rustls::StreamOwned<rustls::ClientSession, StdAdapter> {
+  fn adapter(&mut self) -> &mut StdAdapter {
+    &mut self.sock
+  }
+}
+
+/// Async tls stream, `async_std::io::Read` and `async_std::io::Write` on top of a tls stream.
+pub struct AsyncTlsStream<T> {
+  tls: T,
+}
+
+impl<T: TlsIo> AsyncTlsStream<T> {
+  pub fn new(tls: T) -> Self {
+    Self { tls }
+  }
+
+  fn with_context<R, F>(&mut self, cx: &mut Context, f: F) -> Poll<io::Result<R>>
+    where
+      F: FnOnce(&mut T) -> io::Result<R>,
+  {
+    self.tls.adapter().waker = Some(cx.waker().clone());
+    let result = f(&mut self.tls);
+    self.tls.adapter().waker = None;
+    match result {
+      Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => Poll::Pending,
+      result => Poll::Ready(result),
+    }
+  }
+}
+
+impl<T: TlsIo> async_std::io::Read for AsyncTlsStream<T> {
+  fn poll_read(self: Pin<&mut Self>, cx: &mut Context, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+    self.get_mut().with_context(cx, |tls| tls.read(buf))
+  }
+}
+
+impl<T: TlsIo> async_std::io::Write for AsyncTlsStream<T> {
+  fn poll_write(self: Pin<&mut Self>, cx: &mut Context, buf: &[u8]) -> Poll<io::Result<usize>> {
+    self.get_mut().with_context(cx, |tls| tls.write(buf))
+  }
+
+  fn poll_flush(self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+    self.get_mut().with_context(cx, |tls| tls.flush())
+  }
+
+  fn poll_close(self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+    self.poll_flush(cx)
+  }
+}
+
+/// Native tls handshake without block, retry the handshake when the stream is ready.
+#[cfg(feature = "tls-native")]
+pub async fn native_tls_connect(connector: native_tls::TlsConnector, domain: &str, stream: TcpStream)
+                                -> error::Result<AsyncTlsStream<native_tls::TlsStream<StdAdapter>>> {
+  let mut connector = Some((connector, StdAdapter::new(stream)));
+  let mut mid: Option<native_tls::MidHandshakeTlsStream<StdAdapter>> = None;
+  let tls = async_std::future::poll_fn(|cx| {
+    let result = if let Some((connector, mut adapter)) = connector.take() {
+      adapter.waker = Some(cx.waker().clone());
+      connector.connect(domain, adapter)
+    } else if let Some(mut handshake) = mid.take() {
+      handshake.get_mut().waker = Some(cx.waker().clone());
+      handshake.handshake()
+    } else {
+      return Poll::Ready(Err(error::bad_ssl("Native tls handshake already finished.")));
+    };
+    match result {
+      Ok(tls) => Poll::Ready(Ok(tls)),
+      Err(native_tls::HandshakeError::WouldBlock(handshake)) => {
+        mid = Some(handshake);
+        Poll::Pending
+      }
+      Err(native_tls::HandshakeError::Failure(_)) => Poll::Ready(Err(error::bad_ssl("Native tls error."))),
+    }
+  }).await?;
+  let mut stream = AsyncTlsStream::new(tls);
+  stream.tls.adapter().waker = None;
+  Ok(stream)
+}
@@ -12,8 +12,10 @@ use url::Url;
 
 use crate::{error, HttpClient};
 use crate::connection::connection::Connection;
+use crate::connection::connection_reader::BodyReader;
+use crate::connection::pool::PoolStream;
 use crate::request::RawRequest;
-use crate::response::Response;
+use crate::response::{Response, StreamResponse};
 use crate::types::{Proxy, ProxyType};
 
 pub struct BlockConnection<'a> {
@@ -37,51 +39,72 @@ impl<'a> BlockConnection<'a> {
 
     let proxy = self.conn.proxy();
 
-    let binary = if let Some(proxy) = proxy {
-      self.call_with_proxy(&url, proxy)?
+    let mut response = if let Some(proxy) = proxy {
+      let stream = self.proxy_stream(&url, proxy)?;
+      self.conn.block_send_with_stream(&url, stream)?
     } else {
       self.conn.block_send(&url)?
     };
 
-    let config = self.conn.config();
-    let response = Response::new(self.conn.rourl().clone(), binary)?;
+    response.history_set(self.conn.history(&url));
+    self.conn.store_cookies(&url, &response);
 
-    if let Some(location) = response.location() {
-      let req_url = url.as_str();
-      if req_url == location {
-        return Err(error::loop_detected(url));
-      }
-      if !config.auto_redirect() {
-        return Ok(response);
+    if let Some(request) = self.conn.next_request(&url, &response)? {
+      self.conn.closed_set(true);
+      return HttpClient::with_request(request).emit();
+    }
+
+    self.conn.closed_set(true);
+    Ok(response)
+  }
+
+  /// Same as `block_call`, but returns once the head of response is read.
+  pub fn block_stream(mut self) -> error::Result<StreamResponse> {
+    let url = self.conn.url().map_err(error::builder)?;
+    match self.conn.proxy() {
+      Some(proxy) => {
+        let stream = self.proxy_stream(&url, proxy)?;
+        let (response, body) = self.conn.block_open_with_stream(&url, stream)?;
+        self.stream_response(&url, response, body)
       }
-      let count = self.conn.count();
-      if count > config.max_redirect() {
-        return Err(error::too_many_redirects(url));
+      None => {
+        let (response, body) = self.conn.block_open(&url)?;
+        self.stream_response(&url, response, body)
       }
+    }
+  }
 
-      return HttpClient::with_request(self.conn.request().origin().clone())
-        .url(location)
-        .count(count + 1)
-        .emit();
+  fn stream_response<S>(&mut self, url: &Url, mut response: Response, body: BodyReader<S>) -> error::Result<StreamResponse>
+    where
+      S: io::Read + Send + 'static,
+  {
+    response.history_set(self.conn.history(url));
+    self.conn.store_cookies(url, &response);
+
+    if let Some(request) = self.conn.next_request(url, &response)? {
+      // the body of redirect response is not needed, the connection is closed
+      drop(body);
+      self.conn.closed_set(true);
+      return HttpClient::with_request(request).emit_stream();
     }
 
     self.conn.closed_set(true);
-    Ok(response)
+    StreamResponse::new(response, body)
   }
 }
 
 // proxy connection
 impl<'a> BlockConnection<'a> {
-  fn call_with_proxy(&self, url: &Url, proxy: &Proxy) -> error::Result<Vec<u8>> {
+  /// Connect to the url host through the proxy.
+  fn proxy_stream(&self, url: &Url, proxy: &Proxy) -> error::Result<Box<dyn PoolStream>> {
     match proxy.type_() {
-      ProxyType::HTTP => self.call_with_proxy_https(url, proxy),
-      ProxyType::HTTPS => self.call_with_proxy_https(url, proxy),
-      ProxyType::SOCKS4 => self.call_with_proxy_socks4(url, proxy),
-      ProxyType::SOCKS5 => self.call_with_proxy_socks5(url, proxy),
+      ProxyType::HTTP => self.proxy_stream_https(url, proxy),
+      ProxyType::HTTPS => self.proxy_stream_https(url, proxy),
+      ProxyType::SOCKS4 => self.proxy_stream_socks4(url, proxy),
+      ProxyType::SOCKS5 => self.proxy_stream_socks5(url, proxy),
     }
   }
-
-//  fn call_with_proxy_http(&self, url: &Url, proxy: &Proxy) -> error::Result<Vec<u8>> {
+//  fn call_with_proxy_http(&self, url: &Url, proxy: &Proxy) -> error::Result<Response> {
 //    let header = self.request.header();
 //    let body = self.request.body();
 //
@@ -90,7 +113,7 @@ impl<'a> BlockConnection<'a> {
 //    self.call_tcp_stream_http(stream)
 //  }
 
-  fn call_with_proxy_https(&self, url: &Url, proxy: &Proxy) -> error::Result<Vec<u8>> {
+  fn proxy_stream_https(&self, url: &Url, proxy: &Proxy) -> error::Result<Box<dyn PoolStream>> {
     let host = self.conn.host(url)?;
     let port = self.conn.port(url)?;
 
@@ -117,22 +140,22 @@ impl<'a> BlockConnection<'a> {
       return Err(error::bad_proxy("Proxy server response error."));
     }
 
-    self.conn.block_send_with_stream(url, &mut stream)
+    Ok(Box::new(stream))
   }
 
-  fn call_with_proxy_socks4(&self, url: &Url, proxy: &Proxy) -> error::Result<Vec<u8>> {
+  fn proxy_stream_socks4(&self, url: &Url, proxy: &Proxy) -> error::Result<Box<dyn PoolStream>> {
     let addr_proxy = format!("{}:{}", proxy.host(), proxy.port());
     let addr_target = self.conn.addr(url)?;
     let user = if let Some(u) = proxy.username() { u.to_string() } else { "".to_string() };
-    let mut stream = Socks4Stream::connect(&addr_proxy[..], &addr_target[..], &user[..])
+    let stream = Socks4Stream::connect(&addr_proxy[..], &addr_target[..], &user[..])
       .map_err(error::request)?;
-    self.conn.block_send_with_stream(url, &mut stream)
+    Ok(Box::new(stream))
   }
 
-  fn call_with_proxy_socks5(&self, url: &Url, proxy: &Proxy) -> error::Result<Vec<u8>> {
+  fn proxy_stream_socks5(&self, url: &Url, proxy: &Proxy) -> error::Result<Box<dyn PoolStream>> {
     let addr_proxy = format!("{}:{}", proxy.host(), proxy.port());
     let addr_target = self.conn.addr(url)?;
-    let mut stream = if let Some(u) = proxy.username() {
+    let stream = if let Some(u) = proxy.username() {
       if let Some(p) = proxy.password() {
         Socks5Stream::connect_with_password(&addr_proxy[..], &addr_target[..], &u[..], &p[..])
       } else {
@@ -141,6 +164,6 @@ impl<'a> BlockConnection<'a> {
     } else {
       Socks5Stream::connect(&addr_proxy[..], &addr_target[..])
     }.map_err(error::request)?;
-    self.conn.block_send_with_stream(url, &mut stream)
+    Ok(Box::new(stream))
   }
 }
@@ -4,9 +4,11 @@ use std::sync::Arc;
 use url::Url;
 
 use crate::{Config, error};
-use crate::connection::connection_reader::ConnectionReader;
-use crate::request::{RawRequest, RequestBody};
-use crate::types::{Proxy, RoUrl, ToUrl};
+use crate::connection::connection_reader::{BodyReader, ConnectionReader, response_head};
+use crate::connection::pool::{ConnectionPool, PoolStream, PooledStream};
+use crate::request::{RawRequest, Request, RequestBody};
+use crate::response::Response;
+use crate::types::{Action, Attempt, Auth, DigestCache, DigestChallenge, Proxy, RoUrl, ToUrl};
 
 pub struct Connection<'a> {
   request: RawRequest<'a>
@@ -19,9 +21,6 @@ impl<'a> Connection<'a> {
 }
 
 impl<'a> Connection<'a> {
-  pub fn request(&self) -> &RawRequest {
-    &self.request
-  }
   pub fn rourl(&self) -> &RoUrl {
     self.request.url()
   }
@@ -40,8 +39,11 @@ impl<'a> Connection<'a> {
   pub fn config(&self) -> &Config {
     self.request.origin().config()
   }
-  pub fn count(&self) -> u32 {
-    self.request.origin().count()
+  pub fn method(&self) -> &String {
+    self.request.origin().method()
+  }
+  pub fn pool(&self) -> &ConnectionPool {
+    self.request.origin().client().pool()
   }
 
   pub fn closed_set(&mut self, closed: bool) {
@@ -90,6 +92,146 @@ impl<'a> Connection<'a> {
   }
 }
 
+// redirect
+impl<'a> Connection<'a> {
+  /// Urls visited to get the response of this request, include the current url.
+  pub fn history(&self, url: &Url) -> Vec<Url> {
+    let mut history = self.request.origin().history().clone();
+    history.push(url.clone());
+    history
+  }
+
+  /// Build the next request if the response is a redirect should be followed by redirect policy.
+  pub fn redirect(&self, url: &Url, response: &Response) -> error::Result<Option<Request>> {
+    let code = response.code();
+    if ![301, 302, 303, 307, 308].contains(&code) {
+      return Ok(None);
+    }
+    let location = match response.location() {
+      Some(location) => location,
+      None => return Ok(None),
+    };
+    let policy = self.config().redirect();
+    if policy.max_redirect() == 0 {
+      return Ok(None);
+    }
+
+    // location maybe relative, like `/login`, `../next` or `//host/path`
+    let next = url.join(location.trim()).map_err(|_| error::bad_url(url.clone(), "Bad redirect location"))?;
+    let origin = self.request.origin();
+    // RFC 7231, 303 change method to GET, and 301/302 change POST to GET
+    let method = origin.method();
+    let to_get = (code == 303 && !method.eq_ignore_ascii_case("head")) ||
+      ((code == 301 || code == 302) && method.eq_ignore_ascii_case("post"));
+    let next_method = if to_get { "GET" } else { method.as_str() };
+
+    // the same url with other method is not a loop, like POST /form -> 303 -> GET /form
+    let history = self.history(url);
+    let mut visited = origin.visited().clone();
+    visited.push((method.clone(), url.clone()));
+    if visited.iter().any(|(m, u)| m.eq_ignore_ascii_case(next_method) && *u == next) {
+      return Err(error::loop_detected(next));
+    }
+    if history.len() > policy.max_redirect() as usize {
+      return Err(error::too_many_redirects(url.clone()));
+    }
+    if policy.check(&Attempt::new(code, &next, &history)) == Action::Stop {
+      return Ok(None);
+    }
+
+    let mut request = origin.clone();
+    request.url_set(RoUrl::with(next.as_str()))
+      .paths_set(vec![])
+      .count_set(origin.count() + 1)
+      .auth_retries_set(0);
+    *request.history_mut() = history;
+    *request.visited_mut() = visited;
+
+    if to_get {
+      request.method_set("GET")
+        .paras_set(vec![])
+        .formdatas_set(vec![])
+        .binary_set(vec![]);
+      *request.raw_mut() = None;
+      *request.reader_mut() = None;
+      request.chunked_set(false).trailers_mut().clear();
+      request.headers_mut().retain(|h| {
+        !h.name().eq_ignore_ascii_case("content-type") &&
+          !h.name().eq_ignore_ascii_case("content-length") &&
+          !h.name().eq_ignore_ascii_case("transfer-encoding")
+      });
+    } else {
+      // the paras of url already in location
+      let paras_in_body = !method.eq_ignore_ascii_case("get") && origin.raw().is_none() && origin.binary().is_empty() &&
+        origin.reader().is_none();
+      if !paras_in_body {
+        request.paras_set(vec![]);
+      }
+    }
+
+    // do not leak credentials to other origin
+    if url.origin() != next.origin() {
+      request.headers_mut().retain(|h| {
+        !h.name().eq_ignore_ascii_case("authorization") &&
+          !h.name().eq_ignore_ascii_case("cookie")
+      });
+      *request.auth_mut() = None;
+    }
+    Ok(Some(request))
+  }
+}
+
+// cookie
+impl<'a> Connection<'a> {
+  /// Save the cookies of response to cookie store of client, if have.
+  pub fn store_cookies(&self, url: &Url, response: &Response) {
+    if let Some(store) = self.request.origin().client().cookie_store() {
+      store.store(response.cookies(), url);
+    }
+  }
+}
+
+// authentication
+impl<'a> Connection<'a> {
+  /// Build the request answer the digest challenge, if the response is `401` and request use digest auth.
+  /// The challenge is cached by client, the next request to the same origin answer it directly.
+  pub fn digest_retry(&self, url: &Url, response: &Response) -> Option<Request> {
+    let origin = self.request.origin();
+    if response.code() != 401 || !matches!(origin.auth(), Some(Auth::Digest { .. })) {
+      return None;
+    }
+    if origin.headers().contains("authorization") {
+      return None;
+    }
+    let challenge = DigestChallenge::from_headers(&response.header_values("www-authenticate"))?;
+    // the credentials is rejected, retry again only if the nonce is stale
+    let retries = origin.auth_retries();
+    if retries > 0 && !challenge.stale() {
+      return None;
+    }
+    if retries > 1 || (challenge.stale() && self.digest_cache().nonce(url).as_ref() == Some(challenge.nonce())) {
+      return None;
+    }
+    self.digest_cache().save(url, challenge);
+
+    let mut request = origin.clone();
+    request.auth_retries_set(retries + 1);
+    Some(request)
+  }
+
+  fn digest_cache(&self) -> &DigestCache {
+    self.request.origin().client().digest_cache()
+  }
+
+  /// The request should be sent after the response, answer the digest challenge or follow the redirect.
+  pub fn next_request(&self, url: &Url, response: &Response) -> error::Result<Option<Request>> {
+    if let Some(request) = self.digest_retry(url, response) {
+      return Ok(Some(request));
+    }
+    self.redirect(url, response)
+  }
+}
+
 impl<'a> Connection<'a> {
   pub fn block_tcp_stream(&self, addr: &String) -> error::Result<std::net::TcpStream> {
     let config = self.config();
@@ -102,92 +244,168 @@ impl<'a> Connection<'a> {
   pub fn block_write_stream<S>(&self, stream: &mut S) -> error::Result<()> where S: io::Write, {
     let header = self.header();
     let body = self.body();
-    stream.write(header.as_bytes()).map_err(error::request)?;
+    stream.write_all(header.as_bytes()).map_err(error::request)?;
     if let Some(body) = body {
-      stream.write(body.bytes()).map_err(error::request)?;
+      body.block_write(stream).map_err(error::request)?;
     }
     stream.flush().map_err(error::request)?;
 
     Ok(())
   }
 
-  pub fn block_read_stream<S>(&self, url: &Url, stream: &mut S) -> error::Result<Vec<u8>> where S: io::Read, {
-    let mut reader = ConnectionReader::new(url, stream);
-    reader.binary()
+  pub fn block_read_response<S>(&self, stream: &mut S) -> error::Result<Option<(Response, bool)>> where S: io::Read, {
+    let mut reader = ConnectionReader::new(self.rourl(), self.method(), stream);
+    reader.response()
   }
 
-  pub fn block_send(&self, url: &Url) -> error::Result<Vec<u8>> {
-    let addr = self.addr(url)?;
-    let mut stream = self.block_tcp_stream(&addr)?;
-//    self.call_tcp_stream_http(stream)
-    self.block_send_with_stream(url, &mut stream)
+  pub fn block_send(&self, url: &Url) -> error::Result<Response> {
+    let pool = self.pool();
+    let key = self.pool_key(url)?;
+
+    while let Some(mut stream) = self.idle_stream(&key) {
+      // the idle connection maybe closed by server, discard it and try next one
+      if !self.block_reuse(&mut stream) {
+        continue;
+      }
+      let (response, keep_alive) = match self.block_read_response(&mut stream) {
+        Ok(Some(v)) => v,
+        Ok(None) | Err(_) if self.idempotent() => continue,
+        Ok(None) => return Err(error::bad_response("Connection closed before response")),
+        Err(e) => return Err(e),
+      };
+      if keep_alive {
+        pool.put(&key, stream);
+      }
+      return Ok(response);
+    }
+
+    let mut stream = self.block_connect(url)?;
+    self.block_write_stream(&mut stream)?;
+    let (response, keep_alive) = self.block_read_response(&mut stream)?
+      .ok_or_else(|| error::bad_response("Connection closed before response"))?;
+    if keep_alive {
+      pool.put(&key, stream);
+    }
+    Ok(response)
   }
 
-  pub fn block_send_with_stream<S>(&self, url: &Url, stream: &mut S) -> error::Result<Vec<u8>>
-    where
-      S: io::Read + io::Write,
-  {
-    match url.scheme() {
-      "http" => self.block_send_http(url, stream),
-      "https" => self.block_send_https(url, stream),
-      _ => return Err(error::url_bad_scheme(url.clone()))
+  /// Same as `block_send`, but only the head of response is read, the body is left in connection.
+  /// The connection is put back to pool when the body is read to end.
+  pub fn block_open(&self, url: &Url) -> error::Result<(Response, BodyReader<PooledStream>)> {
+    let key = self.pool_key(url)?;
+
+    while let Some(mut stream) = self.idle_stream(&key) {
+      if !self.block_reuse(&mut stream) {
+        continue;
+      }
+      match self.block_read_head(stream, &key) {
+        Ok(Some(opened)) => return Ok(opened),
+        Ok(None) | Err(_) if self.idempotent() => continue,
+        Ok(None) => return Err(error::bad_response("Connection closed before response")),
+        Err(e) => return Err(e),
+      }
+    }
+
+    let mut stream = self.block_connect(url)?;
+    self.block_write_stream(&mut stream)?;
+    self.block_read_head(stream, &key)?
+      .ok_or_else(|| error::bad_response("Connection closed before response"))
+  }
+
+  fn block_read_head(&self, stream: PooledStream, key: &str) -> error::Result<Option<(Response, BodyReader<PooledStream>)>> {
+    let (response, mut body) = match response_head(self.rourl(), self.method(), stream)? {
+      Some(v) => v,
+      None => return Ok(None),
+    };
+    let client = self.request.origin().client().clone();
+    let key = key.to_string();
+    body.on_release(move |stream| client.pool().put(&key, stream));
+    Ok(Some((response, body)))
+  }
+
+  /// Write the request to an idle connection, `false` if the connection is closed and the request is not sent.
+  fn block_reuse(&self, stream: &mut PooledStream) -> bool {
+    stream.timeout(self.config()).is_ok() && !stream.is_closed() && self.block_write_stream(stream).is_ok()
+  }
+
+  /// Whether the request can be sent again when the idle connection closed before response,
+  /// the server maybe already handled the request not idempotent, like `POST`.
+  fn idempotent(&self) -> bool {
+    ["GET", "HEAD", "PUT", "DELETE", "OPTIONS", "TRACE"].iter().any(|method| self.method().eq_ignore_ascii_case(method))
+  }
+
+  /// Take an idle connection to retry on, the body read from reader can't be sent again,
+  /// so it's always sent by a new connection.
+  fn idle_stream(&self, key: &str) -> Option<PooledStream> {
+    if self.body().as_ref().is_some_and(|body| !body.replayable()) {
+      return None;
     }
+    self.pool().take(key)
+  }
+
+  fn pool_key(&self, url: &Url) -> error::Result<String> {
+    Ok(format!("{}://{}", url.scheme(), self.addr(url)?))
   }
 
-  pub fn block_send_http<S>(&self, url: &Url, stream: &mut S) -> error::Result<Vec<u8>>
-    where
-      S: io::Read + io::Write,
-  {
-    self.block_write_stream(stream)?;
-    self.block_read_stream(url, stream)
+  /// Connect to the url host, if it's https url, the tls handshake will be done.
+  pub fn block_connect(&self, url: &Url) -> error::Result<PooledStream> {
+    let addr = self.addr(url)?;
+    let stream = self.block_tcp_stream(&addr)?;
+    let socket = stream.try_clone().map_err(error::request)?;
+    Ok(PooledStream::new(self.block_secure(url, stream)?, socket))
+  }
+
+  /// Wrap the stream with tls if it's https url.
+  pub fn block_secure<S: PoolStream + 'static>(&self, url: &Url, stream: S) -> error::Result<Box<dyn PoolStream>> {
+    match url.scheme() {
+      "http" => Ok(Box::new(stream)),
+      "https" => self.block_tls_stream(url, stream),
+      _ => Err(error::url_bad_scheme(url.clone()))
+    }
   }
 
   #[cfg(not(any(feature = "tls-native", feature = "tls-rustls")))]
-  pub fn block_send_https<S>(&self, url: &Url, stream: &mut S) -> error::Result<Vec<u8>>
-    where
-      S: io::Read + io::Write,
-  {
-    return Err(error::no_request_features("Not have any tls features, Can't request a https url"));
+  fn block_tls_stream<S: PoolStream + 'static>(&self, _url: &Url, _stream: S) -> error::Result<Box<dyn PoolStream>> {
+    Err(error::no_request_features("Not have any tls features, Can't request a https url"))
   }
 
   #[cfg(feature = "tls-native")]
-  pub fn block_send_https<S>(&self, url: &Url, stream: &mut S) -> error::Result<Vec<u8>>
-    where
-      S: io::Read + io::Write,
-  {
+  fn block_tls_stream<S: PoolStream + 'static>(&self, url: &Url, stream: S) -> error::Result<Box<dyn PoolStream>> {
     let connector = native_tls::TlsConnector::builder().build().map_err(error::request)?;
-    let mut ssl_stream;
-//  if self.verify {
-    ssl_stream = connector.connect(&self.host(url)?[..], stream)
+    let ssl_stream = connector.connect(&self.host(url)?[..], stream)
       .map_err(|_| error::bad_ssl("Native tls error."))?;
-//    ssl_stream = connector.connect(&self.host(url)?[..], stream).map_err(error::request)?;
-//  } else {
-//    ssl_stream = connector.danger_connect_without_providing_domain_for_certificate_verification_and_server_name_indication(stream).map_err(error::request)?;
-//  }
-
-    self.block_write_stream(&mut ssl_stream)?;
-    self.block_read_stream(url, &mut ssl_stream)
+    Ok(Box::new(ssl_stream))
   }
 
   #[cfg(feature = "tls-rustls")]
-  pub fn block_send_https<S>(&self, url: &Url, stream: &mut S) -> error::Result<Vec<u8>>
-    where
-      S: io::Read + io::Write,
-  {
+  fn block_tls_stream<S: PoolStream + 'static>(&self, url: &Url, stream: S) -> error::Result<Box<dyn PoolStream>> {
     let mut config = rustls::ClientConfig::new();
     config
       .root_store
       .add_server_trust_anchors(&webpki_roots::TLS_SERVER_ROOTS);
     let rc_config = Arc::new(config);
     let host = self.host(url)?;
-    let dns_name = webpki::DNSNameRef::try_from_ascii_str(&host[..]).unwrap();
-    let mut client = rustls::ClientSession::new(&rc_config, dns_name);
-    let mut tls = rustls::Stream::new(&mut client, stream);
-
-    self.block_write_stream(&mut tls)?;
-    self.block_read_stream(url, &mut tls)
+    let dns_name = webpki::DNSNameRef::try_from_ascii_str(&host[..])
+      .map_err(|_| error::bad_ssl("Bad dns name."))?;
+    let client = rustls::ClientSession::new(&rc_config, dns_name);
+    Ok(Box::new(rustls::StreamOwned::new(client, stream)))
   }
-}
-
 
+  /// Send the request by the stream connected to the url host, like a proxy tunnel.
+  pub fn block_send_with_stream<S: PoolStream + 'static>(&self, url: &Url, stream: S) -> error::Result<Response> {
+    let mut stream = self.block_secure(url, stream)?;
+    self.block_write_stream(&mut stream)?;
+    match self.block_read_response(&mut stream)? {
+      Some((response, _)) => Ok(response),
+      None => Err(error::bad_response("Connection closed before response")),
+    }
+  }
 
+  /// Same as `block_send_with_stream`, but only the head of response is read.
+  pub fn block_open_with_stream<S: PoolStream + 'static>(&self, url: &Url, stream: S) -> error::Result<(Response, BodyReader<Box<dyn PoolStream>>)> {
+    let mut stream = self.block_secure(url, stream)?;
+    self.block_write_stream(&mut stream)?;
+    response_head(self.rourl(), self.method(), stream)?
+      .ok_or_else(|| error::bad_response("Connection closed before response"))
+  }
+}
@@ -1,33 +1,594 @@
 use std::io;
-
-use url::Url;
+use std::io::{BufRead, Read};
+use std::sync::{Arc, Mutex};
 
 use crate::error;
-use crate::response::Response;
-use crate::types::RoUrl;
+use crate::response::{ChunkedDecoder, Next, Response};
+use crate::types::{HeaderMap, IntoHeader, RoUrl};
+
+const LF: u8 = b'\n';
+/// Max size of a part read from async streaming body.
+#[cfg(feature = "async")]
+const PART_SIZE: usize = 8 * 1024;
 
 pub struct ConnectionReader<'a> {
-  url: &'a Url,
+  url: &'a RoUrl,
+  method: &'a str,
   reader: Box<&'a mut dyn io::Read>,
 }
 
 impl<'a> ConnectionReader<'a> {
-  pub fn new(url: &'a Url, reader: &'a mut dyn io::Read) -> ConnectionReader<'a> {
+  pub fn new(url: &'a RoUrl, method: &'a str, reader: &'a mut dyn io::Read) -> ConnectionReader<'a> {
     Self {
       url,
+      method,
       reader: Box::new(reader),
     }
   }
 
-  pub fn binary(&mut self) -> error::Result<Vec<u8>> {
-    let mut binary: Vec<u8> = Vec::new();
-    let _ = self.reader.read_to_end(&mut binary).map_err(error::request)?;
-    Ok(binary)
+  /// Read one response from stream, the status line and headers are parsed first,
+  /// then the body is framed by `Content-Length`, `Transfer-Encoding: chunked` or connection close.
+  /// Returns the response and whether the connection can be reused,
+  /// `None` if the connection closed before any response.
+  pub fn response(&mut self) -> error::Result<Option<(Response, bool)>> {
+    let mut reader = io::BufReader::new(&mut **self.reader);
+    let mut binary = Vec::new();
+
+    let head = match read_final_head(&mut reader, &mut binary)? {
+      Some(head) => head,
+      None => return Ok(None),
+    };
+
+    let mut body = Vec::new();
+    let keep_alive = match head.framing(self.method)? {
+      Framing::Empty => head.keep_alive(),
+      Framing::Chunked => {
+        read_chunked(&mut reader, &mut body)?;
+        head.keep_alive()
+      }
+      Framing::Length(len) => {
+        // read as the body arrives, the length sent by server may be far larger than the body
+        (&mut reader).take(len).read_to_end(&mut body).map_err(error::request)?;
+        check_body_length(body.len(), len)?;
+        head.keep_alive()
+      }
+      Framing::Close => {
+        reader.read_to_end(&mut body).map_err(error::request)?;
+        false
+      }
+    };
+
+    binary.extend_from_slice(&body);
+    let response = Response::with_parts(self.url.clone(), binary, &head.status_line, head.headers, body)?;
+    Ok(Some((response, keep_alive)))
+  }
+}
+
+/// Read the status line and headers of response from stream, the body is left in stream
+/// and read by the returned `BodyReader` as it arrives.
+/// `None` if the connection closed before any response.
+pub fn response_head<S: io::Read>(url: &RoUrl, method: &str, stream: S) -> error::Result<Option<(Response, BodyReader<S>)>> {
+  let mut reader = io::BufReader::new(stream);
+  let mut binary = Vec::new();
+  let head = match read_final_head(&mut reader, &mut binary)? {
+    Some(head) => head,
+    None => return Ok(None),
+  };
+  let framing = head.framing(method)?;
+  let keep_alive = head.keep_alive();
+  let response = Response::with_parts(url.clone(), binary, &head.status_line, head.headers, vec![])?;
+  Ok(Some((response, BodyReader::new(reader, framing, keep_alive))))
+}
+
+/// Body of a streaming response, the chunked body is decoded while reading.
+/// When the body is read to end, the connection is released if it can be reused.
+pub struct BodyReader<S> {
+  reader: Option<io::BufReader<S>>,
+  state: BodyState,
+  keep_alive: bool,
+  trailers: Arc<Mutex<HeaderMap>>,
+  release: Option<Box<dyn FnOnce(S) + Send>>,
+}
+
+impl<S: io::Read> BodyReader<S> {
+  fn new(reader: io::BufReader<S>, framing: Framing, keep_alive: bool) -> Self {
+    let (state, keep_alive) = BodyState::new(framing, keep_alive);
+    Self { reader: Some(reader), state, keep_alive, trailers: Default::default(), release: None }
+  }
+
+  /// Trailer headers of chunked body, filled when the body is read to end.
+  pub fn trailers(&self) -> Arc<Mutex<HeaderMap>> {
+    self.trailers.clone()
+  }
+
+  pub fn is_end(&self) -> bool {
+    matches!(self.state, BodyState::Done)
+  }
+
+  /// Set the function to take the connection back when the body is read to end, like put it to pool.
+  pub fn on_release<F: FnOnce(S) + Send + 'static>(&mut self, release: F) {
+    self.release = Some(Box::new(release));
+    if let BodyState::Done = self.state {
+      self.finish();
+    }
+  }
+
+  fn finish(&mut self) {
+    self.state = BodyState::Done;
+    if !self.keep_alive || self.release.is_none() {
+      return;
+    }
+    if let (Some(reader), Some(release)) = (self.reader.take(), self.release.take()) {
+      // the server sent more data than the body, the connection can't be reused
+      if reader.buffer().is_empty() {
+        release(reader.into_inner());
+      }
+    }
+  }
+}
+
+impl<S: io::Read> io::Read for BodyReader<S> {
+  fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+    loop {
+      let reader = match self.reader.as_mut() {
+        Some(reader) => reader,
+        None => return Ok(0),
+      };
+      match self.state {
+        BodyState::Done => return Ok(0),
+        BodyState::Close => return reader.read(buf),
+        BodyState::Length(rest) => {
+          let size = read_part(reader, buf, rest)?;
+          self.state = BodyState::Length(rest - size as u64);
+          if rest == size as u64 {
+            self.finish();
+          }
+          return Ok(size);
+        }
+        BodyState::Chunked(ref mut decoder) => match decoder.next() {
+          Next::Line | Next::Trailer => decoder.line(&next_line(reader)?).map_err(invalid_data)?,
+          Next::Data(rest) => {
+            let size = read_part(reader, buf, rest)?;
+            decoder.data(size as u64);
+            return Ok(size);
+          }
+          Next::End => {
+            let trailers = decoder.take_trailers();
+            if let Ok(mut lock) = self.trailers.lock() {
+              *lock = trailers;
+            }
+            self.finish();
+            return Ok(0);
+          }
+        },
+      }
+    }
+  }
+}
+
+/// Same as `ConnectionReader`, read response from an async stream.
+#[cfg(feature = "async")]
+pub struct AsyncConnectionReader<'a, R> {
+  url: &'a RoUrl,
+  method: &'a str,
+  reader: async_std::io::BufReader<R>,
+}
+
+#[cfg(feature = "async")]
+impl<'a, R> AsyncConnectionReader<'a, R> where R: async_std::io::Read + Unpin, {
+  pub fn new(url: &'a RoUrl, method: &'a str, reader: R) -> AsyncConnectionReader<'a, R> {
+    Self {
+      url,
+      method,
+      reader: async_std::io::BufReader::new(reader),
+    }
+  }
+
+  pub async fn response(&mut self) -> error::Result<Option<(Response, bool)>> {
+    use async_std::io::ReadExt;
+
+    let mut binary = Vec::new();
+    let head = match self.read_final_head(&mut binary).await? {
+      Some(head) => head,
+      None => return Ok(None),
+    };
+
+    let mut body = Vec::new();
+    let keep_alive = match head.framing(self.method)? {
+      Framing::Empty => head.keep_alive(),
+      Framing::Chunked => {
+        self.read_chunked(&mut body).await?;
+        head.keep_alive()
+      }
+      Framing::Length(len) => {
+        (&mut self.reader).take(len).read_to_end(&mut body).await.map_err(error::request)?;
+        check_body_length(body.len(), len)?;
+        head.keep_alive()
+      }
+      Framing::Close => {
+        self.reader.read_to_end(&mut body).await.map_err(error::request)?;
+        false
+      }
+    };
+
+    binary.extend_from_slice(&body);
+    let response = Response::with_parts(self.url.clone(), binary, &head.status_line, head.headers, body)?;
+    Ok(Some((response, keep_alive)))
+  }
+
+  /// Same as `response_head`, the body is read by the returned `AsyncBodyReader`.
+  pub async fn response_head(mut self) -> error::Result<Option<(Response, AsyncBodyReader<R>)>> {
+    let mut binary = Vec::new();
+    let head = match self.read_final_head(&mut binary).await? {
+      Some(head) => head,
+      None => return Ok(None),
+    };
+    let framing = head.framing(self.method)?;
+    let (state, _) = BodyState::new(framing, false);
+    let response = Response::with_parts(self.url.clone(), binary, &head.status_line, head.headers, vec![])?;
+    Ok(Some((response, AsyncBodyReader { reader: self.reader, state, trailers: Default::default() })))
   }
 
-  pub fn response(&mut self) -> error::Result<Response> {
-    Response::new(RoUrl::from(self.url.clone()), self.binary()?)
+  async fn read_final_head(&mut self, binary: &mut Vec<u8>) -> error::Result<Option<Head>> {
+    loop {
+      let head = match self.read_head(binary).await? {
+        Some(head) => head,
+        None => return Ok(None),
+      };
+      if head.interim() {
+        binary.clear();
+        continue;
+      }
+      return Ok(Some(head));
+    }
+  }
+
+  async fn read_line(&mut self, binary: &mut Vec<u8>) -> error::Result<Option<String>> {
+    use async_std::io::BufReadExt;
+
+    let mut line = Vec::new();
+    let size = self.reader.read_until(LF, &mut line).await.map_err(error::request)?;
+    if size == 0 {
+      return Ok(None);
+    }
+    binary.extend_from_slice(&line);
+    Ok(Some(String::from_utf8_lossy(&line).trim_end().to_string()))
+  }
+
+  async fn read_head(&mut self, binary: &mut Vec<u8>) -> error::Result<Option<Head>> {
+    let status_line = match self.read_line(binary).await? {
+      Some(line) => line,
+      None if binary.is_empty() => return Ok(None),
+      None => return Err(error::bad_response("Connection closed before response header")),
+    };
+    let mut head = Head::new(status_line)?;
+    loop {
+      let line = self.read_line(binary).await?
+        .ok_or_else(|| error::bad_response("Connection closed before response header"))?;
+      if line.is_empty() {
+        return Ok(Some(head));
+      }
+      head.push_header(line);
+    }
   }
 
-  // todo Connection reader will read more type from io::Reader, like Chunk data, and Stream data.
+  async fn read_chunked(&mut self, binary: &mut Vec<u8>) -> error::Result<()> {
+    use async_std::io::ReadExt;
+
+    let mut decoder = ChunkedDecoder::new();
+    loop {
+      match decoder.next() {
+        Next::Line | Next::Trailer => {
+          let line = self.read_line(binary).await?
+            .ok_or_else(|| error::bad_response("Connection closed before chunked body end"))?;
+          decoder.line(&line)?;
+        }
+        Next::Data(len) => {
+          let start = binary.len();
+          (&mut self.reader).take(len).read_to_end(binary).await.map_err(error::request)?;
+          check_body_length(binary.len() - start, len)?;
+          decoder.data(len);
+        }
+        Next::End => return Ok(()),
+      }
+    }
+  }
+}
+
+/// Same as `BodyReader`, read the body from an async stream part by part.
+#[cfg(feature = "async")]
+pub struct AsyncBodyReader<R> {
+  reader: async_std::io::BufReader<R>,
+  state: BodyState,
+  trailers: Arc<Mutex<HeaderMap>>,
+}
+
+#[cfg(feature = "async")]
+impl<R> AsyncBodyReader<R> where R: async_std::io::Read + Unpin, {
+  /// Trailer headers of chunked body, filled when the body is read to end.
+  pub fn trailers(&self) -> Arc<Mutex<HeaderMap>> {
+    self.trailers.clone()
+  }
+
+  pub fn is_end(&self) -> bool {
+    matches!(self.state, BodyState::Done)
+  }
+
+  /// Read the next part of body, `None` if the body is read to end.
+  /// The reader is returned back, so the future not borrow it.
+  pub async fn next_part(mut self) -> (Self, io::Result<Option<Vec<u8>>>) {
+    let part = self.read_part().await;
+    (self, part)
+  }
+
+  async fn read_part(&mut self) -> io::Result<Option<Vec<u8>>> {
+    use async_std::io::ReadExt;
+
+    let mut buf = vec![0u8; PART_SIZE];
+    loop {
+      match self.state {
+        BodyState::Done => return Ok(None),
+        BodyState::Close => {
+          let size = self.reader.read(&mut buf).await?;
+          if size == 0 {
+            self.state = BodyState::Done;
+            return Ok(None);
+          }
+          buf.truncate(size);
+          return Ok(Some(buf));
+        }
+        BodyState::Length(rest) => {
+          if rest == 0 {
+            self.state = BodyState::Done;
+            continue;
+          }
+          let size = Self::read_max(&mut self.reader, &mut buf, rest).await?;
+          self.state = BodyState::Length(rest - size as u64);
+          buf.truncate(size);
+          return Ok(Some(buf));
+        }
+        BodyState::Chunked(ref mut decoder) => match decoder.next() {
+          Next::Line | Next::Trailer => {
+            let line = Self::next_line(&mut self.reader).await?;
+            decoder.line(&line).map_err(invalid_data)?;
+          }
+          Next::Data(rest) => {
+            let size = Self::read_max(&mut self.reader, &mut buf, rest).await?;
+            decoder.data(size as u64);
+            buf.truncate(size);
+            return Ok(Some(buf));
+          }
+          Next::End => {
+            let trailers = decoder.take_trailers();
+            if let Ok(mut lock) = self.trailers.lock() {
+              *lock = trailers;
+            }
+            self.state = BodyState::Done;
+          }
+        },
+      }
+    }
+  }
+
+  async fn read_max(reader: &mut async_std::io::BufReader<R>, buf: &mut [u8], rest: u64) -> io::Result<usize> {
+    use async_std::io::ReadExt;
+
+    let max = rest.min(buf.len() as u64) as usize;
+    let size = reader.read(&mut buf[..max]).await?;
+    if size == 0 {
+      return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Connection closed before body end"));
+    }
+    Ok(size)
+  }
+
+  async fn next_line(reader: &mut async_std::io::BufReader<R>) -> io::Result<String> {
+    use async_std::io::BufReadExt;
+
+    let mut line = Vec::new();
+    if reader.read_until(LF, &mut line).await? == 0 {
+      return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Connection closed before chunked body end"));
+    }
+    Ok(String::from_utf8_lossy(&line).trim_end().to_string())
+  }
+}
+
+/// Progress of reading a streaming body.
+enum BodyState {
+  Length(u64),
+  Chunked(ChunkedDecoder),
+  Close,
+  Done,
+}
+
+impl BodyState {
+  /// The state to start with, and whether the connection can be reused after the body.
+  fn new(framing: Framing, keep_alive: bool) -> (Self, bool) {
+    match framing {
+      Framing::Empty | Framing::Length(0) => (BodyState::Done, keep_alive),
+      Framing::Chunked => (BodyState::Chunked(ChunkedDecoder::new()), keep_alive),
+      Framing::Length(len) => (BodyState::Length(len), keep_alive),
+      Framing::Close => (BodyState::Close, false),
+    }
+  }
+}
+
+/// How the body of a response is delimited.
+enum Framing {
+  Empty,
+  Chunked,
+  Length(u64),
+  Close,
+}
+
+struct Head {
+  status_line: String,
+  version: String,
+  code: u32,
+  headers: HeaderMap,
+}
+
+impl Head {
+  fn new(status_line: String) -> error::Result<Self> {
+    let status_parts: Vec<&str> = status_line.splitn(3, ' ').collect();
+    let version = status_parts.first().map_or("".to_string(), |v| v.to_string());
+    let code: u32 = status_parts.get(1)
+      .ok_or_else(|| error::bad_response("Response status not have code"))?
+      .parse()
+      .map_err(|_| error::bad_response("Response status code is not a number"))?;
+    Ok(Self { status_line, version, code, headers: HeaderMap::new() })
+  }
+
+  fn push_header(&mut self, line: String) {
+    self.headers.extend(line.into_headers().into_iter().take(1));
+  }
+
+  fn values(&self, name: &str) -> Vec<&String> {
+    self.headers.get_all(name)
+  }
+
+  fn tokens(&self, name: &str) -> Vec<String> {
+    self.values(name).iter()
+      .flat_map(|v| v.split(','))
+      .map(|v| v.trim().to_ascii_lowercase())
+      .filter(|v| !v.is_empty())
+      .collect()
+  }
+
+  /// Interim response, like `100 Continue`, the final response will follow.
+  fn interim(&self) -> bool {
+    self.code >= 100 && self.code < 200 && self.code != 101
+  }
+
+  fn framing(&self, method: &str) -> error::Result<Framing> {
+    let no_body = method.eq_ignore_ascii_case("head") ||
+      (self.code >= 100 && self.code < 200) ||
+      self.code == 204 ||
+      self.code == 304;
+    if no_body {
+      return Ok(Framing::Empty);
+    }
+    if self.tokens("transfer-encoding").last().is_some_and(|v| v == "chunked") {
+      return Ok(Framing::Chunked);
+    }
+    match self.values("content-length").first() {
+      Some(v) => v.trim().parse().map(Framing::Length)
+        .map_err(|_| error::bad_response("Response content length is not a number")),
+      None => Ok(Framing::Close),
+    }
+  }
+
+  fn keep_alive(&self) -> bool {
+    let connection = self.tokens("connection");
+    if connection.iter().any(|v| v == "close") {
+      return false;
+    }
+    if self.version.eq_ignore_ascii_case("HTTP/1.0") {
+      return connection.iter().any(|v| v == "keep-alive");
+    }
+    true
+  }
+}
+
+/// The connection closed before the body is read to end.
+fn check_body_length(read: usize, len: u64) -> error::Result<()> {
+  if read as u64 != len {
+    return Err(error::bad_response("Connection closed before body end"));
+  }
+  Ok(())
+}
+
+fn read_line<R: BufRead>(reader: &mut R, binary: &mut Vec<u8>) -> error::Result<Option<String>> {
+  let mut line = Vec::new();
+  let size = match reader.read_until(LF, &mut line) {
+    Ok(size) => size,
+    Err(e) => {
+      // the idle connection reset by server
+      let reset = e.kind() == io::ErrorKind::ConnectionReset ||
+        e.kind() == io::ErrorKind::ConnectionAborted;
+      if reset && binary.is_empty() {
+        return Ok(None);
+      }
+      return Err(error::request(e));
+    }
+  };
+  if size == 0 {
+    return Ok(None);
+  }
+  binary.extend_from_slice(&line);
+  Ok(Some(String::from_utf8_lossy(&line).trim_end().to_string()))
+}
+
+/// Read the head of final response, the interim responses like `100 Continue` are skipped.
+fn read_final_head<R: BufRead>(reader: &mut R, binary: &mut Vec<u8>) -> error::Result<Option<Head>> {
+  loop {
+    let head = match read_head(reader, binary)? {
+      Some(head) => head,
+      None => return Ok(None),
+    };
+    if head.interim() {
+      binary.clear();
+      continue;
+    }
+    return Ok(Some(head));
+  }
+}
+
+fn read_head<R: BufRead>(reader: &mut R, binary: &mut Vec<u8>) -> error::Result<Option<Head>> {
+  let status_line = match read_line(reader, binary)? {
+    Some(line) => line,
+    None if binary.is_empty() => return Ok(None),
+    None => return Err(error::bad_response("Connection closed before response header")),
+  };
+  let mut head = Head::new(status_line)?;
+  loop {
+    let line = read_line(reader, binary)?
+      .ok_or_else(|| error::bad_response("Connection closed before response header"))?;
+    if line.is_empty() {
+      return Ok(Some(head));
+    }
+    head.push_header(line);
+  }
+}
+
+/// Read the raw chunked body to `binary`, it's decoded when the response is built.
+fn read_chunked<R: BufRead>(reader: &mut R, binary: &mut Vec<u8>) -> error::Result<()> {
+  let mut decoder = ChunkedDecoder::new();
+  loop {
+    match decoder.next() {
+      Next::Line | Next::Trailer => {
+        let line = read_line(reader, binary)?
+          .ok_or_else(|| error::bad_response("Connection closed before chunked body end"))?;
+        decoder.line(&line)?;
+      }
+      Next::Data(len) => {
+        let start = binary.len();
+        reader.take(len).read_to_end(binary).map_err(error::request)?;
+        check_body_length(binary.len() - start, len)?;
+        decoder.data(len);
+      }
+      Next::End => return Ok(()),
+    }
+  }
+}
+
+/// Read at most `rest` bytes of body, the connection closed before that is an error.
+fn read_part<R: Read>(reader: &mut R, buf: &mut [u8], rest: u64) -> io::Result<usize> {
+  let max = rest.min(buf.len() as u64) as usize;
+  let size = reader.read(&mut buf[..max])?;
+  if size == 0 && max > 0 {
+    return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Connection closed before body end"));
+  }
+  Ok(size)
+}
+
+fn next_line<R: BufRead>(reader: &mut R) -> io::Result<String> {
+  let mut line = Vec::new();
+  if reader.read_until(LF, &mut line)? == 0 {
+    return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Connection closed before chunked body end"));
+  }
+  Ok(String::from_utf8_lossy(&line).trim_end().to_string())
+}
+
+fn invalid_data(err: error::Error) -> io::Error {
+  io::Error::new(io::ErrorKind::InvalidData, err)
 }
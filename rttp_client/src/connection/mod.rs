@@ -1,11 +1,18 @@
 #[cfg(feature = "async")]
 pub use self::async_connection::*;
 pub use self::block_connection::*;
+pub use self::pool::ConnectionPool;
+pub(crate) use self::connection_reader::BodyReader;
+#[cfg(feature = "async")]
+pub(crate) use self::connection_reader::AsyncBodyReader;
 
 mod block_connection;
 mod connection_reader;
 #[cfg(feature = "async")]
 mod async_connection;
 mod connection;
+mod pool;
 #[cfg(feature = "async")]
-mod async_std_io_block;
+mod async_proxy;
+#[cfg(all(feature = "async", any(feature = "tls-native", feature = "tls-rustls")))]
+mod async_tls;
@@ -0,0 +1,156 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+use std::net::TcpStream;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::Config;
+
+/// A stream can be keep in connection pool, plain tcp stream or tls stream.
+pub trait PoolStream: io::Read + io::Write + Send {}
+
+impl<T: io::Read + io::Write + Send> PoolStream for T {}
+
+/// A connection hold by pool, `socket` is the underlying tcp stream of `stream`,
+/// use to reset timeout when the connection is reused.
+pub struct PooledStream {
+  stream: Box<dyn PoolStream>,
+  socket: TcpStream,
+}
+
+impl PooledStream {
+  pub fn new(stream: Box<dyn PoolStream>, socket: TcpStream) -> Self {
+    Self { stream, socket }
+  }
+
+  pub fn timeout(&self, config: &Config) -> io::Result<()> {
+    self.socket.set_read_timeout(Some(Duration::from_millis(config.read_timeout())))?;
+    self.socket.set_write_timeout(Some(Duration::from_millis(config.write_timeout())))
+  }
+
+  /// Whether the idle connection is closed by server, or the server sent data not asked for,
+  /// it can't be reused both.
+  pub fn is_closed(&self) -> bool {
+    if self.socket.set_nonblocking(true).is_err() {
+      return true;
+    }
+    let mut buf = [0u8; 1];
+    let closed = match self.socket.peek(&mut buf) {
+      Ok(_) => true,
+      Err(e) => e.kind() != io::ErrorKind::WouldBlock,
+    };
+    self.socket.set_nonblocking(false).is_err() || closed
+  }
+}
+
+impl io::Read for PooledStream {
+  fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+    self.stream.read(buf)
+  }
+}
+
+impl io::Write for PooledStream {
+  fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    self.stream.write(buf)
+  }
+
+  fn flush(&mut self) -> io::Result<()> {
+    self.stream.flush()
+  }
+}
+
+struct Idle {
+  stream: PooledStream,
+  since: Instant,
+}
+
+/// Keep-alive connection pool, idle connections are grouped by host.
+pub struct ConnectionPool {
+  idle_timeout: u64,
+  max_idle_per_host: usize,
+  max_total: usize,
+  idles: Mutex<HashMap<String, Vec<Idle>>>,
+}
+
+impl Default for ConnectionPool {
+  fn default() -> Self {
+    Self::new(90000, 8, 64)
+  }
+}
+
+impl ConnectionPool {
+  pub fn new(idle_timeout: u64, max_idle_per_host: usize, max_total: usize) -> Self {
+    Self {
+      idle_timeout,
+      max_idle_per_host,
+      max_total,
+      idles: Mutex::new(HashMap::new()),
+    }
+  }
+
+  pub fn idle_timeout(&self) -> u64 { self.idle_timeout }
+  pub fn max_idle_per_host(&self) -> usize { self.max_idle_per_host }
+  pub fn max_total(&self) -> usize { self.max_total }
+
+  /// Whether the pool keep any connection.
+  pub fn enabled(&self) -> bool {
+    self.max_idle_per_host > 0 && self.max_total > 0
+  }
+
+  /// Count of idle connections in pool.
+  pub fn idle_count(&self) -> usize {
+    match self.idles.lock() {
+      Ok(idles) => idles.values().map(|v| v.len()).sum(),
+      Err(_) => 0,
+    }
+  }
+
+  /// Take an idle connection of the host, expired connections will be dropped.
+  pub fn take(&self, key: &str) -> Option<PooledStream> {
+    let mut idles = self.idles.lock().ok()?;
+    let timeout = Duration::from_millis(self.idle_timeout);
+    let streams = idles.get_mut(key)?;
+    streams.retain(|idle| idle.since.elapsed() < timeout);
+    let idle = streams.pop();
+    if streams.is_empty() {
+      idles.remove(key);
+    }
+    idle.map(|v| v.stream)
+  }
+
+  /// Put a connection back to pool, if the pool is full, the connection will be closed.
+  pub fn put(&self, key: &str, stream: PooledStream) {
+    if !self.enabled() {
+      return;
+    }
+    let mut idles = match self.idles.lock() {
+      Ok(v) => v,
+      Err(_) => return,
+    };
+    let timeout = Duration::from_millis(self.idle_timeout);
+    idles.values_mut().for_each(|streams| streams.retain(|idle| idle.since.elapsed() < timeout));
+    idles.retain(|_, streams| !streams.is_empty());
+
+    let total: usize = idles.values().map(|v| v.len()).sum();
+    if total >= self.max_total {
+      return;
+    }
+    let streams = idles.entry(key.to_string()).or_default();
+    if streams.len() >= self.max_idle_per_host {
+      return;
+    }
+    streams.push(Idle { stream, since: Instant::now() });
+  }
+}
+
+impl fmt::Debug for ConnectionPool {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.debug_struct("ConnectionPool")
+      .field("idle_timeout", &self.idle_timeout)
+      .field("max_idle_per_host", &self.max_idle_per_host)
+      .field("max_total", &self.max_total)
+      .field("idle_count", &self.idle_count())
+      .finish()
+  }
+}
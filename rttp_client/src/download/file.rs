@@ -0,0 +1,210 @@
+use std::ffi::OsString;
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+use sha2::{Digest, Sha256};
+
+use crate::{error, HttpClient};
+use crate::request::Request;
+use crate::response::StreamResponse;
+use crate::types::Header;
+
+pub(crate) type Progress<'a> = Box<dyn FnMut(u64, Option<u64>) + 'a>;
+
+/// Download the response body to a file, created by `HttpClient::download`.
+///
+/// The body is written to `<path>.part` and renamed to `path` when it's complete,
+/// a failed download is continued by next call with `Range` and `If-Range` headers,
+/// the server without range support or the changed file is downloaded from start.
+///
+/// # Examples
+///
+/// ```rust
+/// # use rttp_client::HttpClient;
+/// # fn run() -> Result<(), Box<dyn std::error::Error>> {
+/// let size = HttpClient::new()
+///   .get()
+///   .url("http://httpbin.org/bytes/1024")
+///   .download("bytes.bin")
+///   .progress(|done, total| println!("{} of {:?}", done, total))
+///   .emit()?;
+/// assert_eq!(1024, size);
+/// # Ok(())
+/// # }
+/// ```
+pub struct Download<'a> {
+  request: Request,
+  path: PathBuf,
+  resume: bool,
+  sha256: Option<String>,
+  progress: Option<Progress<'a>>,
+}
+
+impl<'a> Download<'a> {
+  pub(crate) fn new(request: Request, path: PathBuf) -> Self {
+    Self { request, path, resume: true, sha256: None, progress: None }
+  }
+
+  /// Whether continue the partial download left by last call, default is true.
+  pub fn resume(&mut self, resume: bool) -> &mut Self {
+    self.resume = resume;
+    self
+  }
+
+  /// Verify the SHA-256 digest of downloaded file, hex encoded.
+  /// If not match, the downloaded data is removed.
+  pub fn sha256<S: AsRef<str>>(&mut self, sha256: S) -> &mut Self {
+    self.sha256 = Some(sha256.as_ref().trim().to_ascii_lowercase());
+    self
+  }
+
+  /// Called after each write with downloaded bytes and total bytes, the total is `None` if unknown.
+  pub fn progress<F: FnMut(u64, Option<u64>) + 'a>(&mut self, progress: F) -> &mut Self {
+    self.progress = Some(Box::new(progress));
+    self
+  }
+
+  /// Download the file, returns size of it.
+  pub fn emit(&mut self) -> error::Result<u64> {
+    let part = suffixed(&self.path, ".part");
+    let validator_path = suffixed(&self.path, ".part.if-range");
+    if !self.resume {
+      remove(&part);
+      remove(&validator_path);
+    }
+    // the part file is resumable only if its validator is saved
+    let validator = fs::read_to_string(&validator_path).ok();
+    let offset = match (&validator, fs::metadata(&part)) {
+      (Some(_), Ok(metadata)) if metadata.is_file() => metadata.len(),
+      _ => 0,
+    };
+
+    let mut response = self.send(offset, validator.as_deref())?;
+    let mut offset = offset;
+    if response.code() == 416 && offset > 0 {
+      response = self.send(0, None)?;
+      offset = 0;
+    }
+    response.response().error_for_status_ref()?;
+    let url = response.url().clone();
+
+    // a body still encoded is not the bytes of range
+    let encoded = response.header_value("content-encoding").is_some_and(|v| !v.trim().eq_ignore_ascii_case("identity"));
+    let total = if response.code() == 206 {
+      let content_range = response.headers().content_range()
+        .ok_or_else(|| error::bad_response("Invalid Content-Range of partial content").with_url(url.clone()))?;
+      if content_range.range().map(|(first, _)| first) != Some(offset) {
+        return Err(error::bad_response(format!("Unexpected Content-Range: {}", content_range)).with_url(url));
+      }
+      content_range.complete_length()
+    } else {
+      offset = 0;
+      response.headers().content_length().filter(|_| !encoded)
+    };
+
+    let validator = response.headers().etag()
+      .filter(|etag| !etag.is_weak())
+      .map(|etag| etag.to_string())
+      .or_else(|| response.header_value("last-modified").cloned());
+    match validator {
+      Some(validator) if !encoded => fs::write(&validator_path, validator).map_err(error::body)?,
+      _ => remove(&validator_path),
+    }
+
+    let mut file = if offset > 0 {
+      OpenOptions::new().append(true).open(&part)
+    } else {
+      File::create(&part)
+    }.map_err(error::body)?;
+    let mut hasher = self.sha256.as_ref().map(|_| Sha256::new());
+    if let (Some(hasher), true) = (hasher.as_mut(), offset > 0) {
+      io::copy(&mut File::open(&part).map_err(error::body)?.take(offset), hasher).map_err(error::body)?;
+    }
+
+    let done = self.write(&mut response, &mut file, hasher.as_mut(), offset, total)
+      .map_err(|e| error::body(e).with_url(url.clone()))?;
+    drop(file);
+    if let Some(total) = total {
+      if done != total {
+        return Err(error::bad_response(format!("Incomplete body, {} of {} bytes downloaded", done, total)).with_url(url));
+      }
+    }
+
+    if let (Some(expected), Some(hasher)) = (&self.sha256, hasher) {
+      let actual: String = hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect();
+      if &actual != expected {
+        remove(&part);
+        remove(&validator_path);
+        return Err(error::digest_mismatch(url, expected, &actual));
+      }
+    }
+    fs::rename(&part, &self.path).map_err(error::body)?;
+    remove(&validator_path);
+    Ok(done)
+  }
+
+  fn send(&self, offset: u64, validator: Option<&str>) -> error::Result<StreamResponse> {
+    let mut request = self.request.clone();
+    let headers = request.headers_mut();
+    // ranges are of the raw file, not of a compressed body
+    headers.insert("Accept-Encoding", "identity");
+    if offset > 0 {
+      let range = Header::range(offset, None);
+      headers.insert(range.name(), range.value());
+      if let Some(validator) = validator {
+        headers.insert("If-Range", validator);
+      }
+    }
+    HttpClient::with_request(request).emit_stream()
+  }
+
+  fn write(&mut self, response: &mut StreamResponse, file: &mut File, mut hasher: Option<&mut Sha256>,
+           offset: u64, total: Option<u64>) -> io::Result<u64> {
+    let mut done = offset;
+    if let Some(progress) = self.progress.as_mut() {
+      progress(done, total);
+    }
+    let mut buf = vec![0u8; 64 * 1024];
+    loop {
+      let size = match response.read(&mut buf) {
+        Ok(0) => break,
+        Ok(size) => size,
+        Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+        Err(e) => return Err(e),
+      };
+      file.write_all(&buf[..size])?;
+      if let Some(hasher) = hasher.as_mut() {
+        hasher.update(&buf[..size]);
+      }
+      done += size as u64;
+      if let Some(progress) = self.progress.as_mut() {
+        progress(done, total);
+      }
+    }
+    file.sync_all()?;
+    Ok(done)
+  }
+}
+
+impl fmt::Debug for Download<'_> {
+  fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+    formatter.debug_struct("Download")
+      .field("path", &self.path)
+      .field("resume", &self.resume)
+      .field("sha256", &self.sha256)
+      .finish()
+  }
+}
+
+/// The path with suffix appended to file name, `file.zip` to `file.zip.part`.
+pub(crate) fn suffixed(path: &Path, suffix: &str) -> PathBuf {
+  let mut name = OsString::from(path.as_os_str());
+  name.push(suffix);
+  PathBuf::from(name)
+}
+
+pub(crate) fn remove(path: &Path) {
+  let _ = fs::remove_file(path);
+}
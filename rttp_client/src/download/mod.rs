@@ -0,0 +1,5 @@
+pub use self::file::*;
+pub use self::segmented::*;
+
+mod file;
+mod segmented;
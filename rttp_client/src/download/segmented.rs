@@ -0,0 +1,222 @@
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::thread;
+
+use crate::{error, HttpClient};
+use crate::download::file::{self, Download, Progress};
+use crate::request::Request;
+use crate::types::Header;
+
+/// Download a file by concurrent range requests, created by `HttpClient::segmented_download`.
+///
+/// The size of file is probed by `HEAD`, if the server send `Accept-Ranges: bytes`, the file
+/// is split into segments, each segment is fetched by a `Range` request in its own thread and
+/// written to its position of `<path>.part`. A failed segment is retried from where it stopped.
+/// The server not support ranges is downloaded by `Download`.
+///
+/// # Examples
+///
+/// ```rust
+/// # use rttp_client::HttpClient;
+/// # fn run() -> Result<(), Box<dyn std::error::Error>> {
+/// let size = HttpClient::new()
+///   .get()
+///   .url("http://httpbin.org/range/102400")
+///   .segmented_download("range.bin")
+///   .segments(8)
+///   .retries(2)
+///   .emit()?;
+/// assert_eq!(102400, size);
+/// # Ok(())
+/// # }
+/// ```
+pub struct SegmentedDownload<'a> {
+  request: Request,
+  path: PathBuf,
+  segments: u64,
+  retries: u32,
+  progress: Option<Progress<'a>>,
+}
+
+impl<'a> SegmentedDownload<'a> {
+  pub(crate) fn new(request: Request, path: PathBuf) -> Self {
+    Self { request, path, segments: 4, retries: 3, progress: None }
+  }
+
+  /// Count of concurrent range requests, default is 4.
+  pub fn segments(&mut self, segments: usize) -> &mut Self {
+    self.segments = segments.max(1) as u64;
+    self
+  }
+
+  /// Times to retry a failed segment, default is 3.
+  pub fn retries(&mut self, retries: u32) -> &mut Self {
+    self.retries = retries;
+    self
+  }
+
+  /// Called with downloaded bytes of all segments and total bytes, the total is `None` if unknown.
+  pub fn progress<F: FnMut(u64, Option<u64>) + 'a>(&mut self, progress: F) -> &mut Self {
+    self.progress = Some(Box::new(progress));
+    self
+  }
+
+  /// Download the file, returns size of it.
+  pub fn emit(&mut self) -> error::Result<u64> {
+    let mut request = self.request.clone();
+    request.method_set("HEAD");
+    request.headers_mut().insert("Accept-Encoding", "identity");
+    let head = HttpClient::with_request(request).emit()?;
+    let encoded = head.header_value("content-encoding").is_some_and(|v| !v.trim().eq_ignore_ascii_case("identity"));
+    let total = head.headers().content_length().filter(|_| head.is_success() && !encoded);
+    let total = match total {
+      Some(total) if total > 0 && head.headers().accept_ranges() && self.segments > 1 => total,
+      _ => return self.single(),
+    };
+    let validator = head.headers().etag()
+      .filter(|etag| !etag.is_weak())
+      .map(|etag| etag.to_string())
+      .or_else(|| head.header_value("last-modified").cloned());
+
+    let part = file::suffixed(&self.path, ".part");
+    let result = self.fetch(&part, total, validator.as_deref())
+      .and_then(|_| File::open(&part).and_then(|file| file.sync_all()).map_err(error::body))
+      .and_then(|_| fs::rename(&part, &self.path).map_err(error::body));
+    if result.is_err() {
+      file::remove(&part);
+    }
+    result.map(|_| total)
+  }
+
+  /// Download by a single request.
+  fn single(&mut self) -> error::Result<u64> {
+    let mut download = Download::new(self.request.clone(), self.path.clone());
+    if let Some(progress) = self.progress.as_mut() {
+      download.progress(progress);
+    }
+    download.emit()
+  }
+
+  /// Fetch all segments to the part file, the progress is reported in this thread.
+  fn fetch(&mut self, part: &Path, total: u64, validator: Option<&str>) -> error::Result<()> {
+    File::create(part).and_then(|file| file.set_len(total)).map_err(error::body)?;
+    let size = total.div_ceil(self.segments.min(total));
+    let ranges: Vec<(u64, u64)> = (0..total).step_by(size as usize)
+      .map(|first| (first, (first + size).min(total) - 1))
+      .collect();
+
+    let (sender, receiver) = mpsc::channel::<u64>();
+    let progress = &mut self.progress;
+    let (request, retries) = (&self.request, self.retries);
+    thread::scope(|scope| {
+      let handles: Vec<_> = ranges.into_iter()
+        .map(|range| {
+          let segment = Segment { request: request.clone(), part, range, validator, total };
+          let sender = sender.clone();
+          scope.spawn(move || segment.fetch(retries, &sender))
+        })
+        .collect();
+      drop(sender);
+
+      let mut done = 0;
+      if let Some(progress) = progress.as_mut() {
+        progress(done, Some(total));
+      }
+      for size in receiver {
+        done += size;
+        if let Some(progress) = progress.as_mut() {
+          progress(done, Some(total));
+        }
+      }
+      handles.into_iter()
+        .map(|handle| handle.join().unwrap_or_else(|_| Err(error::body("Segment download panicked"))))
+        .collect::<error::Result<Vec<()>>>()
+        .map(|_| ())
+    })
+  }
+}
+
+impl fmt::Debug for SegmentedDownload<'_> {
+  fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+    formatter.debug_struct("SegmentedDownload")
+      .field("path", &self.path)
+      .field("segments", &self.segments)
+      .field("retries", &self.retries)
+      .finish()
+  }
+}
+
+/// A range of file, first and last byte position are inclusive.
+struct Segment<'s> {
+  request: Request,
+  part: &'s Path,
+  range: (u64, u64),
+  validator: Option<&'s str>,
+  total: u64,
+}
+
+impl Segment<'_> {
+  /// Fetch the segment, the written bytes are sent to progress.
+  fn fetch(&self, retries: u32, progress: &mpsc::Sender<u64>) -> error::Result<()> {
+    let (mut offset, last) = self.range;
+    let mut attempts = 0;
+    loop {
+      match self.fetch_from(&mut offset, progress) {
+        Ok(()) => return Ok(()),
+        Err(e) if attempts >= retries => return Err(e),
+        Err(_) => attempts += 1,
+      }
+      if offset > last {
+        return Ok(());
+      }
+    }
+  }
+
+  /// Fetch the rest of segment from offset, the offset is moved forward as written.
+  fn fetch_from(&self, offset: &mut u64, progress: &mpsc::Sender<u64>) -> error::Result<()> {
+    let last = self.range.1;
+    let mut request = self.request.clone();
+    let headers = request.headers_mut();
+    headers.insert("Accept-Encoding", "identity");
+    let range = Header::range(*offset, Some(last));
+    headers.insert(range.name(), range.value());
+    if let Some(validator) = self.validator {
+      headers.insert("If-Range", validator);
+    }
+
+    let mut response = HttpClient::with_request(request).emit_stream()?;
+    response.response().error_for_status_ref()?;
+    let url = response.url().clone();
+    // `200` is the whole file, the server not support ranges any more or the file is changed
+    let content_range = response.headers().content_range().filter(|_| response.code() == 206);
+    let expected = content_range.is_some_and(|content_range| {
+      content_range.range() == Some((*offset, last)) && content_range.complete_length().is_none_or(|length| length == self.total)
+    });
+    if !expected {
+      return Err(error::bad_response(format!("Unexpected response of range {}-{}", *offset, last)).with_url(url));
+    }
+
+    let mut file = OpenOptions::new().write(true).open(self.part).map_err(error::body)?;
+    file.seek(SeekFrom::Start(*offset)).map_err(error::body)?;
+    let mut buf = vec![0u8; 64 * 1024];
+    while *offset <= last {
+      let size = match response.read(&mut buf) {
+        Ok(0) => break,
+        Ok(size) => size,
+        Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+        Err(e) => return Err(error::body(e).with_url(url)),
+      };
+      let size = size.min((last + 1 - *offset) as usize);
+      file.write_all(&buf[..size]).map_err(error::body)?;
+      *offset += size as u64;
+      let _ = progress.send(size as u64);
+    }
+    if *offset <= last {
+      return Err(error::bad_response(format!("Incomplete range {}-{}, stopped at {}", self.range.0, last, *offset)).with_url(url));
+    }
+    Ok(())
+  }
+}
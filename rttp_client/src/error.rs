@@ -66,6 +66,11 @@ impl Error {
     }
   }
 
+  /// Returns true if the error is from decoding the response, like a corrupt compressed body.
+  pub fn is_decode(&self) -> bool {
+    matches!(self.inner.kind, Kind::Decode)
+  }
+
   /// Returns true if the error is related to a timeout.
   pub fn is_timeout(&self) -> bool {
     self.source().map(|e| e.is::<TimedOut>()).unwrap_or(false)
@@ -244,6 +249,11 @@ pub(crate) fn bad_response<S: AsRef<str>>(message: S) -> Error {
   Error::new(Kind::Response, Some(message.as_ref()))
 }
 
+pub(crate) fn digest_mismatch(url: Url, expected: &str, actual: &str) -> Error {
+  let message = format!("SHA-256 digest mismatch, expected {} but got {}", expected, actual);
+  Error::new(Kind::Body, Some(message)).with_url(url)
+}
+
 pub(crate) fn bad_cookie<S: AsRef<str>>(message: S) -> Error {
   Error::new(Kind::Decode, Some(message.as_ref()))
 }
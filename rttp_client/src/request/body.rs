@@ -0,0 +1,367 @@
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use crate::error;
+use crate::types::HeaderMap;
+
+/// Body of request, the parts are written in order.
+/// The file and reader parts are not hold in memory, but read while writing.
+#[derive(Clone)]
+pub struct RequestBody {
+  parts: Vec<BodyPart>,
+  chunked: bool,
+  trailers: HeaderMap,
+}
+
+#[derive(Clone)]
+enum BodyPart {
+  Binary(Vec<u8>),
+  /// A file and the length of it when the body is built.
+  File(PathBuf, u64),
+  Reader(ReaderBody),
+}
+
+impl RequestBody {
+  pub fn with_vec(vec: Vec<u8>) -> Self {
+    Self { parts: vec![BodyPart::Binary(vec)], chunked: false, trailers: HeaderMap::new() }
+  }
+
+  pub fn with_text<S: AsRef<str>>(text: S) -> Self {
+    Self::with_slice(text.as_ref().to_owned().as_bytes())
+  }
+
+  pub fn with_slice(slice: &[u8]) -> Self {
+    Self::with_vec(slice.to_vec())
+  }
+
+  pub(crate) fn with_reader(reader: ReaderBody) -> Self {
+    Self { parts: vec![BodyPart::Reader(reader)], chunked: false, trailers: HeaderMap::new() }
+  }
+
+  pub(crate) fn new() -> Self {
+    Self { parts: vec![], chunked: false, trailers: HeaderMap::new() }
+  }
+
+  pub(crate) fn push_binary(&mut self, binary: &[u8]) {
+    match self.parts.last_mut() {
+      Some(BodyPart::Binary(last)) => last.extend_from_slice(binary),
+      _ => self.parts.push(BodyPart::Binary(binary.to_vec())),
+    }
+  }
+
+  pub(crate) fn push_file(&mut self, path: PathBuf, length: u64) {
+    self.parts.push(BodyPart::File(path, length));
+  }
+
+  /// Send the body by chunked transfer encoding, the trailers are sent after the last chunk.
+  pub(crate) fn chunked_set(&mut self, trailers: HeaderMap) {
+    self.chunked = true;
+    self.trailers = trailers;
+  }
+
+  /// The body in memory, `None` if some parts are read while writing.
+  pub fn bytes(&self) -> Option<&[u8]> {
+    match &self.parts[..] {
+      [] => Some(&[]),
+      [BodyPart::Binary(binary)] => Some(binary),
+      _ => None,
+    }
+  }
+
+  pub fn string(&self) -> error::Result<String> {
+    String::from_utf8(self.bytes().unwrap_or_default().to_vec()).map_err(error::request)
+  }
+
+  /// Length of body, `None` if the body is sent by chunked transfer-encoding,
+  /// it's chunked if asked or the length of reader is unknown.
+  pub fn content_length(&self) -> Option<u64> {
+    if self.chunked {
+      return None;
+    }
+    self.parts.iter().map(|part| match part {
+      BodyPart::Binary(binary) => Some(binary.len() as u64),
+      BodyPart::File(_, length) => Some(*length),
+      BodyPart::Reader(reader) => reader.length,
+    }).sum()
+  }
+
+  pub fn trailers(&self) -> &HeaderMap {
+    &self.trailers
+  }
+
+  /// Whether the body can be written again, the reader can be read only once.
+  pub(crate) fn replayable(&self) -> bool {
+    !self.parts.iter().any(|part| matches!(part, BodyPart::Reader(_)))
+  }
+
+  /// The last chunk and trailers end the chunked body.
+  fn last_chunk(&self) -> String {
+    let mut last = String::from("0\r\n");
+    for trailer in &self.trailers {
+      last.push_str(&format!("{}: {}\r\n", trailer.name(), trailer.value()));
+    }
+    last.push_str("\r\n");
+    last
+  }
+
+  pub(crate) fn block_write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+    let chunked = self.content_length().is_none();
+    for part in &self.parts {
+      match part {
+        BodyPart::Binary(binary) => write_data(writer, binary, chunked)?,
+        BodyPart::File(path, length) => {
+          let copied = copy(&mut File::open(path)?.take(*length), writer, chunked)?;
+          check_length(copied, *length)?;
+        }
+        BodyPart::Reader(reader) => reader.block_write(writer, chunked)?,
+      }
+    }
+    if chunked {
+      writer.write_all(self.last_chunk().as_bytes())?;
+    }
+    Ok(())
+  }
+
+  #[cfg(feature = "async")]
+  pub(crate) async fn async_write<W: async_std::io::Write + Unpin>(&self, writer: &mut W) -> io::Result<()> {
+    use async_std::io::{ReadExt, WriteExt};
+
+    let chunked = self.content_length().is_none();
+    for part in &self.parts {
+      match part {
+        BodyPart::Binary(binary) => async_write_data(writer, binary, chunked).await?,
+        BodyPart::File(path, length) => {
+          let file = async_std::fs::File::open(path).await?;
+          let copied = async_copy(&mut file.take(*length), writer, chunked).await?;
+          check_length(copied, *length)?;
+        }
+        BodyPart::Reader(reader) => reader.async_write(writer, chunked).await?,
+      }
+    }
+    if chunked {
+      writer.write_all(self.last_chunk().as_bytes()).await?;
+    }
+    Ok(())
+  }
+}
+
+impl fmt::Display for RequestBody {
+  #[inline]
+  fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+    let text = self.string().unwrap_or_default();
+    fmt::Display::fmt(&text, formatter)
+  }
+}
+
+impl fmt::Debug for RequestBody {
+  #[inline]
+  fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+    match self.bytes() {
+      Some(_) => fmt::Debug::fmt(&self.string().unwrap_or_default(), formatter),
+      None => formatter.debug_struct("RequestBody").field("content_length", &self.content_length()).finish(),
+    }
+  }
+}
+
+
+/// Body read from a reader while sending. The reader is taken by the first sending,
+/// the request of it can be sent only once.
+#[derive(Clone)]
+pub(crate) struct ReaderBody {
+  source: Arc<Mutex<Option<Source>>>,
+  length: Option<u64>,
+}
+
+enum Source {
+  Block(Box<dyn Read + Send>),
+  #[cfg(feature = "async")]
+  Async(Box<dyn async_std::io::Read + Unpin + Send>),
+}
+
+impl ReaderBody {
+  pub(crate) fn new<R: Read + Send + 'static>(reader: R, length: Option<u64>) -> Self {
+    Self { source: Arc::new(Mutex::new(Some(Source::Block(Box::new(reader))))), length }
+  }
+
+  #[cfg(feature = "async")]
+  pub(crate) fn with_async<R: async_std::io::Read + Unpin + Send + 'static>(reader: R, length: Option<u64>) -> Self {
+    Self { source: Arc::new(Mutex::new(Some(Source::Async(Box::new(reader))))), length }
+  }
+
+  /// Whether the reader is taken by a sent request.
+  pub(crate) fn is_sent(&self) -> bool {
+    self.source.lock().map_or(true, |source| source.is_none())
+  }
+
+  fn take(&self) -> io::Result<Source> {
+    self.source.lock().ok()
+      .and_then(|mut source| source.take())
+      .ok_or_else(|| io::Error::other("The reader of body is already sent"))
+  }
+
+  fn block_write<W: Write>(&self, writer: &mut W, chunked: bool) -> io::Result<()> {
+    let limit = self.length.unwrap_or(u64::MAX);
+    let copied = match self.take()? {
+      Source::Block(reader) => copy(&mut reader.take(limit), writer, chunked)?,
+      #[cfg(feature = "async")]
+      Source::Async(reader) => {
+        use async_std::io::ReadExt;
+        async_std::task::block_on(async_copy(&mut reader.take(limit), &mut BlockWriter(writer), chunked))?
+      }
+    };
+    self.length.map_or(Ok(()), |length| check_length(copied, length))
+  }
+
+  #[cfg(feature = "async")]
+  async fn async_write<W: async_std::io::Write + Unpin>(&self, writer: &mut W, chunked: bool) -> io::Result<()> {
+    use async_std::io::ReadExt;
+
+    let limit = self.length.unwrap_or(u64::MAX);
+    let copied = match self.take()? {
+      Source::Async(reader) => async_copy(&mut reader.take(limit), writer, chunked).await?,
+      // the blocking reader is read by a blocking task, not the executor, the data is handed over by channel
+      Source::Block(reader) => {
+        let (sender, receiver) = async_std::channel::bounded(1);
+        let mut reader = reader.take(limit);
+        let read = async_std::task::spawn_blocking(move || copy(&mut reader, &mut ChannelWriter(sender), false));
+        while let Ok(data) = receiver.recv().await {
+          async_write_data(writer, &data, chunked).await?;
+        }
+        read.await?
+      }
+    };
+    self.length.map_or(Ok(()), |length| check_length(copied, length))
+  }
+}
+
+impl fmt::Debug for ReaderBody {
+  fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+    formatter.debug_struct("ReaderBody")
+      .field("length", &self.length)
+      .field("sent", &self.is_sent())
+      .finish()
+  }
+}
+
+
+const BUFFER_SIZE: usize = 64 * 1024;
+
+/// Copy the reader to writer, returns the bytes copied.
+fn copy<R: Read, W: Write>(reader: &mut R, writer: &mut W, chunked: bool) -> io::Result<u64> {
+  let mut buf = vec![0u8; BUFFER_SIZE];
+  let mut copied = 0;
+  loop {
+    let size = match reader.read(&mut buf) {
+      Ok(size) => size,
+      Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+      Err(e) => return Err(e),
+    };
+    if size == 0 {
+      return Ok(copied);
+    }
+    write_data(writer, &buf[..size], chunked)?;
+    copied += size as u64;
+  }
+}
+
+/// Write the data, as a chunk if chunked. The empty data is skipped, it's the last chunk.
+fn write_data<W: Write>(writer: &mut W, data: &[u8], chunked: bool) -> io::Result<()> {
+  if data.is_empty() {
+    return Ok(());
+  }
+  if chunked {
+    writer.write_all(format!("{:x}\r\n", data.len()).as_bytes())?;
+    writer.write_all(data)?;
+    writer.write_all(b"\r\n")?;
+    // the chunk is sent as it's read, not held in buffer of the stream
+    return writer.flush();
+  }
+  writer.write_all(data)
+}
+
+#[cfg(feature = "async")]
+async fn async_copy<R, W>(reader: &mut R, writer: &mut W, chunked: bool) -> io::Result<u64>
+  where
+    R: async_std::io::Read + Unpin,
+    W: async_std::io::Write + Unpin,
+{
+  use async_std::io::ReadExt;
+
+  let mut buf = vec![0u8; BUFFER_SIZE];
+  let mut copied = 0;
+  loop {
+    let size = match reader.read(&mut buf).await {
+      Ok(size) => size,
+      Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+      Err(e) => return Err(e),
+    };
+    if size == 0 {
+      return Ok(copied);
+    }
+    async_write_data(writer, &buf[..size], chunked).await?;
+    copied += size as u64;
+  }
+}
+
+/// Blocking writer used as async writer, the async reader is copied to it in `block_on`.
+#[cfg(feature = "async")]
+struct BlockWriter<'a, W>(&'a mut W);
+
+#[cfg(feature = "async")]
+impl<W: Write> async_std::io::Write for BlockWriter<'_, W> {
+  fn poll_write(self: std::pin::Pin<&mut Self>, _: &mut std::task::Context<'_>, buf: &[u8]) -> std::task::Poll<io::Result<usize>> {
+    std::task::Poll::Ready(self.get_mut().0.write(buf))
+  }
+
+  fn poll_flush(self: std::pin::Pin<&mut Self>, _: &mut std::task::Context<'_>) -> std::task::Poll<io::Result<()>> {
+    std::task::Poll::Ready(self.get_mut().0.flush())
+  }
+
+  fn poll_close(self: std::pin::Pin<&mut Self>, cx: &mut std::task::Context<'_>) -> std::task::Poll<io::Result<()>> {
+    self.poll_flush(cx)
+  }
+}
+
+/// Send the data written to the async writer, the writing fails if the receiver is dropped.
+#[cfg(feature = "async")]
+struct ChannelWriter(async_std::channel::Sender<Vec<u8>>);
+
+#[cfg(feature = "async")]
+impl Write for ChannelWriter {
+  fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    self.0.send_blocking(buf.to_vec())
+      .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "The request body is not sent anymore"))?;
+    Ok(buf.len())
+  }
+
+  fn flush(&mut self) -> io::Result<()> {
+    Ok(())
+  }
+}
+
+#[cfg(feature = "async")]
+async fn async_write_data<W: async_std::io::Write + Unpin>(writer: &mut W, data: &[u8], chunked: bool) -> io::Result<()> {
+  use async_std::io::WriteExt;
+
+  if data.is_empty() {
+    return Ok(());
+  }
+  if chunked {
+    writer.write_all(format!("{:x}\r\n", data.len()).as_bytes()).await?;
+    writer.write_all(data).await?;
+    writer.write_all(b"\r\n").await?;
+    return writer.flush().await;
+  }
+  writer.write_all(data).await
+}
+
+/// The body sent must be as long as the `Content-Length`.
+fn check_length(copied: u64, length: u64) -> io::Result<()> {
+  if copied == length {
+    return Ok(());
+  }
+  Err(io::Error::new(io::ErrorKind::UnexpectedEof, format!("Body is {} bytes, but {} bytes are read", length, copied)))
+}
@@ -1,6 +1,9 @@
 pub use self::raw_request::RawRequest;
 pub use self::request::*;
+pub use self::body::RequestBody;
+pub(crate) use self::body::ReaderBody;
 
 mod request;
 mod raw_request;
 mod raw_builder;
+mod body;
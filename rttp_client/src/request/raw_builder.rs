@@ -6,7 +6,8 @@ use url::Url;
 
 use crate::error;
 use crate::request::{RawRequest, Request, RequestBody};
-use crate::types::{FormDataType, Header, RoUrl, ToUrl};
+use crate::response;
+use crate::types::{self, Auth, FormDataType, RoUrl, ToUrl};
 
 pub const HYPHENS: &'static str = "---------------------------";
 pub const DISPOSITION_PREFIX: &'static str = "--";
@@ -30,6 +31,7 @@ impl<'a> RawBuilder<'a> {
   }
 
   pub fn block_raw_request(mut self) -> error::Result<RawRequest<'a>> {
+    self.validate()?;
     let mut rourl = self.request.url()
       .clone()
       .ok_or(error::none_url())?;
@@ -37,6 +39,7 @@ impl<'a> RawBuilder<'a> {
     self.rebuild_paras(&mut rourl);
     self.rebuild_url(&mut rourl);
     let body = self.build_body_block(&mut rourl)?;
+    let body = self.chunk_body(body)?;
     let header = self.build_header(&rourl, &body)?;
     Ok(RawRequest {
       origin: self.request,
@@ -48,6 +51,7 @@ impl<'a> RawBuilder<'a> {
 
   #[cfg(feature = "async")]
   pub async fn async_raw_request(mut self) -> error::Result<RawRequest<'a>> {
+    self.validate()?;
     let mut rourl = self.request.url()
       .clone()
       .ok_or(error::none_url())?;
@@ -55,6 +59,7 @@ impl<'a> RawBuilder<'a> {
     self.rebuild_paras(&mut rourl);
     self.rebuild_url(&mut rourl);
     let body = self.build_body_async(&mut rourl).await?;
+    let body = self.chunk_body(body)?;
     let header = self.build_header(&rourl, &body)?;
     Ok(RawRequest {
       origin: self.request,
@@ -66,10 +71,48 @@ impl<'a> RawBuilder<'a> {
 }
 
 
+// validate
+impl<'a> RawBuilder<'a> {
+  /// Check the parts written to request head as is, a line break in them would inject
+  /// other headers or requests.
+  fn validate(&self) -> error::Result<()> {
+    let method = self.request.method();
+    if !types::is_token(method) {
+      return Err(error::builder_with_message(format!("Invalid request method: {:?}", method)));
+    }
+    for path in self.request.paths() {
+      if !types::is_field_value(path) {
+        return Err(error::builder_with_message(format!("Invalid url path: {:?}", path)));
+      }
+    }
+    for header in self.request.headers() {
+      if !types::is_token(header.name()) {
+        return Err(error::builder_with_message(format!("Invalid header name: {:?}", header.name())));
+      }
+      if !types::is_field_value(header.value()) {
+        return Err(error::builder_with_message(format!("Invalid value of header {}", header.name())));
+      }
+    }
+    for trailer in self.request.trailers() {
+      if !types::is_token(trailer.name()) {
+        return Err(error::builder_with_message(format!("Invalid trailer name: {:?}", trailer.name())));
+      }
+      if !types::is_field_value(trailer.value()) {
+        return Err(error::builder_with_message(format!("Invalid value of trailer {}", trailer.name())));
+      }
+    }
+    Ok(())
+  }
+}
+
 // build header
 impl<'a> RawBuilder<'a> {
   fn request_url(&self, url: &Url, full: bool) -> String {
     if full {
+      // userinfo is sent by authorization header, never in request line
+      let mut url = url.clone();
+      let _ = url.set_username("");
+      let _ = url.set_password(None);
       return url.as_str().to_owned();
     }
 
@@ -95,16 +138,28 @@ impl<'a> RawBuilder<'a> {
     let mut found_host = false;
     let mut found_connection = false;
     let mut found_ua = false;
+    let mut found_authorization = false;
     let mut found_content_type = false;
-    let mut found_content_length = false;
+    let mut found_accept_encoding = false;
+    let mut store_cookies = self.request.client().cookie_store().as_ref()
+      .and_then(|store| store.header_value(&url));
 
     for header in self.request.headers() {
       let name = header.name();
-      let value = header.value().replace(DISPOSITION_END, "");
+      let mut value = header.value().clone();
+
+      // cookies of store follow the cookies set by request
+      if name.eq_ignore_ascii_case("cookie") {
+        if let Some(cookies) = store_cookies.take() {
+          value = format!("{}; {}", value, cookies);
+        }
+      }
 
       if name.eq_ignore_ascii_case("host") { found_host = true; }
       if name.eq_ignore_ascii_case("connection") { found_connection = true; }
       if name.eq_ignore_ascii_case("user-agent") { found_ua = true; }
+      if name.eq_ignore_ascii_case("authorization") { found_authorization = true; }
+      if name.eq_ignore_ascii_case("accept-encoding") { found_accept_encoding = true; }
 
       if name.eq_ignore_ascii_case("content-type") {
         found_content_type = true;
@@ -113,7 +168,17 @@ impl<'a> RawBuilder<'a> {
         }
       }
       if name.eq_ignore_ascii_case("content-length") {
-        found_content_length = true;
+        // the header set must agree with the body, the body of unknown length is chunked
+        match body.as_ref().map_or(Some(0), |b| b.content_length()) {
+          None => return Err(error::builder_with_message("Content-Length can't be set with chunked transfer-encoding")),
+          Some(len) if value.trim().parse::<u64>().ok() != Some(len) =>
+            return Err(error::builder_with_message(format!("Content-Length {} is not the body length {}", value.trim(), len))),
+          _ => {}
+        }
+      }
+      // content length, transfer encoding and trailer are always computed from body
+      if name.eq_ignore_ascii_case("content-length") || name.eq_ignore_ascii_case("transfer-encoding") ||
+        name.eq_ignore_ascii_case("trailer") {
         continue;
       }
 
@@ -131,66 +196,89 @@ impl<'a> RawBuilder<'a> {
         }
       }, |v| Ok(v))?;
       builder.push_str(&format!("Host: {}:{}{}", host, port, DISPOSITION_END));
-      self.request.headers_mut().push(Header::new("Host", format!("{}:{}", host, port)));
     }
 
-    // auto add connection header
+    // auto add connection header, keep alive if the client have connection pool
     if !found_connection {
-      let conn = format!("Connection: Close{}", DISPOSITION_END);
-      builder.push_str(&conn);
-      self.request.headers_mut().push(Header::new("Connection", "Close"));
+      let conn = if self.request.client().pool().enabled() { "keep-alive" } else { "Close" };
+      builder.push_str(&format!("Connection: {}{}", conn, DISPOSITION_END));
+    }
+
+    // auto add cookie header from cookie store
+    if let Some(cookies) = store_cookies {
+      builder.push_str(&format!("Cookie: {}{}", cookies, DISPOSITION_END));
+    }
+
+    // auto add authorization header, from auth or url userinfo
+    if !found_authorization {
+      let auth = self.request.auth().clone().or_else(|| Auth::from_url(&url));
+      let authorization = match auth {
+        // answer the digest challenge cached by client, if have
+        Some(Auth::Digest { username, password }) => {
+          let method = self.request.method().to_uppercase();
+          let body = match &body {
+            Some(body) => body.bytes(),
+            None => Some(&[][..]),
+          };
+          self.request.client().digest_cache()
+            .authorization(&url, &username, &password, &method, &request_url, body)?
+        }
+        Some(auth) => auth.header_value(),
+        None => None,
+      };
+      if let Some(authorization) = authorization {
+        if !types::is_field_value(&authorization) {
+          return Err(error::builder_with_message("Invalid value of header Authorization"));
+        }
+        builder.push_str(&format!("Authorization: {}{}", authorization, DISPOSITION_END));
+      }
     }
 
     // auto add user agent header
     if !found_ua {
-      let ua = format!("Mozilla/5.0 rttp/{}", env!("CARGO_PKG_VERSION"));
-      builder.push_str(&format!("User-Agent: {}{}", ua, DISPOSITION_END));
-      self.request.headers_mut().push(Header::new("User-Agent", ua));
+      if let Some(ua) = self.request.client().user_agent() {
+        if !types::is_field_value(ua) {
+          return Err(error::builder_with_message("Invalid value of header User-Agent"));
+        }
+        builder.push_str(&format!("User-Agent: {}{}", ua, DISPOSITION_END));
+      }
+    }
+
+    // auto add accept encoding header, the encodings can be decoded
+    if !found_accept_encoding {
+      if let Some(encodings) = response::accept_encoding() {
+        builder.push_str(&format!("Accept-Encoding: {}{}", encodings, DISPOSITION_END));
+      }
     }
 
     // auto add content type header
     // if it's form data request, replace header use this class generate header
     if self.request.formdatas().is_empty() {
-      if !found_content_type {
+      let auto_content_type = body.is_some() || self.request.client().bodyless_content_type();
+      if !found_content_type && auto_content_type {
         match &self.content_type {
           Some(ct) => {
-            let ctstr = ct.to_string();
-            builder.push_str(&format!("Content-Type: {}{}", ctstr, DISPOSITION_END));
-            self.request.headers_mut().push(Header::new("Content-Type", ctstr));
+            builder.push_str(&format!("Content-Type: {}{}", ct, DISPOSITION_END));
           }
           None => {
             builder.push_str(&format!("Content-Type: {}{}", mime::APPLICATION_OCTET_STREAM.to_string(), DISPOSITION_END));
-            self.request.headers_mut().push(Header::new("Content-Type", mime::APPLICATION_OCTET_STREAM.to_string()));
           }
         }
       }
-    } else {
-      let mut headers = self.request.headers().iter()
-        .filter(|h| !h.name().eq_ignore_ascii_case("content-type"))
-        .map(|v| v.clone())
-        .collect::<Vec<Header>>();
-      if let Some(ct) = &self.content_type {
-        let cts = ct.to_string();
-        builder.push_str(&format!("Content-Type: {}{}", cts, DISPOSITION_END));
-        headers.push(Header::new("Content-Type", cts));
-        self.request.headers_set(headers);
-      }
-    }
-
-    // auto add content length header
-    let len = if let Some(body) = body {
-      body.len()
-    } else {
-      0
-    };
-    builder.push_str(&format!("Content-Length: {}{}", len, DISPOSITION_END));
-    if found_content_length {
-      let mut headers = self.request.headers().iter()
-        .filter(|h| h.name().eq_ignore_ascii_case("content-length"))
-        .map(|v| v.clone())
-        .collect::<Vec<Header>>();
-      headers.push(Header::new("Content-Length", len.to_string()));
-      self.request.headers_set(headers);
+    } else if let Some(ct) = &self.content_type {
+      builder.push_str(&format!("Content-Type: {}{}", ct, DISPOSITION_END));
+    }
+
+    // auto add content length header, the body of unknown length is sent by chunked transfer encoding
+    match body.as_ref().map_or(Some(0), |b| b.content_length()) {
+      Some(len) => builder.push_str(&format!("Content-Length: {}{}", len, DISPOSITION_END)),
+      None => builder.push_str(&format!("Transfer-Encoding: chunked{}", DISPOSITION_END)),
+    }
+
+    // announce the trailers sent after the body
+    if let Some(body) = body.as_ref().filter(|b| !b.trailers().is_empty()) {
+      let names: Vec<&str> = body.trailers().iter().map(|t| t.name().as_str()).collect();
+      builder.push_str(&format!("Trailer: {}{}", names.join(", "), DISPOSITION_END));
     }
 
     builder.push_str(DISPOSITION_END);
@@ -198,6 +286,22 @@ impl<'a> RawBuilder<'a> {
   }
 }
 
+// chunked body
+impl<'a> RawBuilder<'a> {
+  /// Send the body by chunked transfer encoding if asked or the length is unknown, the trailers need it.
+  fn chunk_body(&self, body: Option<RequestBody>) -> error::Result<Option<RequestBody>> {
+    let trailers = self.request.trailers();
+    match body {
+      Some(mut body) if self.request.chunked() || body.content_length().is_none() => {
+        body.chunked_set(trailers.clone());
+        Ok(Some(body))
+      }
+      _ if !trailers.is_empty() => Err(error::builder_with_message("Trailers are only sent with chunked body")),
+      body => Ok(body),
+    }
+  }
+}
+
 // rebuild para/url
 impl<'a> RawBuilder<'a> {
   fn rebuild_paras(&mut self, rourl: &mut RoUrl) {
@@ -306,14 +410,15 @@ impl<'a> RawBuilder<'a> {
     let paras = self.request.paras();
     let binary = self.request.binary();
     let formdatas = self.request.formdatas();
+    let reader = self.request.reader();
 
-    let has_multi_body_type = vec![raw.is_some(), !binary.is_empty(), !formdatas.is_empty()]
+    let has_multi_body_type = [raw.is_some(), !binary.is_empty(), !formdatas.is_empty(), reader.is_some()]
       .iter()
       .filter(|v| **v)
       .collect::<Vec<&bool>>()
       .len() > 1;
     if has_multi_body_type {
-      return Err(error::builder_with_message("Bad request body, raw binary and form-data only support choose one"));
+      return Err(error::builder_with_message("Bad request body, raw binary form-data and reader only support choose one"));
     }
 
     self.content_type = Some(mime::APPLICATION_WWW_FORM_URLENCODED);
@@ -325,7 +430,7 @@ impl<'a> RawBuilder<'a> {
     }
 
     // paras
-    if !paras.is_empty() && raw.is_none() && binary.is_empty() && formdatas.is_empty() && !is_get {
+    if !paras.is_empty() && raw.is_none() && binary.is_empty() && formdatas.is_empty() && reader.is_none() && !is_get {
       return self.build_body_with_form_urlencoded(rourl);
     }
 
@@ -358,6 +463,21 @@ impl<'a> RawBuilder<'a> {
       return Ok(body);
     }
 
+    // reader
+    if let Some(reader) = reader {
+      if reader.is_sent() {
+        return Err(error::builder_with_message("The reader of body is already sent, the request can't be sent again"));
+      }
+      self.content_type = Some(Mime::from_str(&self.request.header("content-type").map_or(mime::APPLICATION_OCTET_STREAM.to_string(), |v| v)[..])
+        .map_err(error::builder)?);
+
+      let body = Some(RequestBody::with_reader(reader.clone()));
+      if !is_get && !paras.is_empty() {
+        for para in paras { rourl.para(para); }
+      }
+      return Ok(body);
+    }
+
     // no body
     Ok(None)
   }
@@ -365,7 +485,8 @@ impl<'a> RawBuilder<'a> {
   fn build_body_with_form_data_block(&mut self, rourl: &mut RoUrl) -> error::Result<Option<RequestBody>> {
     let fdw = self.build_body_with_form_data_sync_common(rourl)?;
     let mut disposition = fdw.disposition;
-    let mut buffer = fdw.buffer;
+    let mut body = RequestBody::new();
+    body.push_binary(&fdw.buffer);
 
     let traditional = self.request.traditional();
     let formdatas = self.request.formdatas();
@@ -376,15 +497,15 @@ impl<'a> RawBuilder<'a> {
         let guess = mime_guess::from_path(&file);
         let filename = if let Some(fname) = formdata.filename() { fname.to_string() } else { "".to_string() };
         let item = disposition.create_with_filename_and_content_type(&field_name, &filename, guess.first_or_octet_stream());
-        buffer.extend_from_slice(item.as_bytes());
-        let file_content = std::fs::read(&file).map_err(error::builder)?;
-        buffer.extend(file_content);
+        body.push_binary(item.as_bytes());
+        // the file is read while sending
+        let len = std::fs::metadata(&file).map_err(error::builder)?.len();
+        body.push_file(file, len);
+        body.push_binary(DISPOSITION_END.as_bytes());
       }
     }
     let end = disposition.end();
-    buffer.extend_from_slice(end.as_bytes());
-
-    let body = RequestBody::with_vec(buffer);
+    body.push_binary(end.as_bytes());
     Ok(Some(body))
   }
 
@@ -445,14 +566,15 @@ impl<'a> RawBuilder<'a> {
     let paras = self.request.paras();
     let binary = self.request.binary();
     let formdatas = self.request.formdatas();
+    let reader = self.request.reader();
 
-    let has_multi_body_type = vec![raw.is_some(), !binary.is_empty(), !formdatas.is_empty()]
+    let has_multi_body_type = [raw.is_some(), !binary.is_empty(), !formdatas.is_empty(), reader.is_some()]
       .iter()
       .filter(|v| **v)
       .collect::<Vec<&bool>>()
       .len() > 1;
     if has_multi_body_type {
-      return Err(error::builder_with_message("Bad request body, raw binary and form-data only support choose one"));
+      return Err(error::builder_with_message("Bad request body, raw binary form-data and reader only support choose one"));
     }
 
     self.content_type = Some(mime::APPLICATION_WWW_FORM_URLENCODED);
@@ -464,7 +586,7 @@ impl<'a> RawBuilder<'a> {
     }
 
     // paras
-    if !paras.is_empty() && raw.is_none() && binary.is_empty() && formdatas.is_empty() && !is_get {
+    if !paras.is_empty() && raw.is_none() && binary.is_empty() && formdatas.is_empty() && reader.is_none() && !is_get {
       return self.build_body_with_form_urlencoded(rourl);
     }
 
@@ -497,6 +619,21 @@ impl<'a> RawBuilder<'a> {
       return Ok(body);
     }
 
+    // reader
+    if let Some(reader) = reader {
+      if reader.is_sent() {
+        return Err(error::builder_with_message("The reader of body is already sent, the request can't be sent again"));
+      }
+      self.content_type = Some(Mime::from_str(&self.request.header("content-type").map_or(mime::APPLICATION_OCTET_STREAM.to_string(), |v| v)[..])
+        .map_err(error::builder)?);
+
+      let body = Some(RequestBody::with_reader(reader.clone()));
+      if !is_get && !paras.is_empty() {
+        for para in paras { rourl.para(para); }
+      }
+      return Ok(body);
+    }
+
     // no body
     Ok(None)
   }
@@ -505,7 +642,8 @@ impl<'a> RawBuilder<'a> {
   async fn build_body_with_form_data_async(&mut self, rourl: &mut RoUrl) -> error::Result<Option<RequestBody>> {
     let fdw = self.build_body_with_form_data_sync_common(rourl)?;
     let mut disposition = fdw.disposition;
-    let mut buffer = fdw.buffer;
+    let mut body = RequestBody::new();
+    body.push_binary(&fdw.buffer);
 
     let traditional = self.request.traditional();
     let formdatas = self.request.formdatas();
@@ -516,15 +654,15 @@ impl<'a> RawBuilder<'a> {
         let guess = mime_guess::from_path(&file);
         let filename = if let Some(fname) = formdata.filename() { fname.to_string() } else { "".to_string() };
         let item = disposition.create_with_filename_and_content_type(&field_name, &filename, guess.first_or_octet_stream());
-        buffer.extend_from_slice(item.as_bytes());
-        let file_content = async_std::fs::read(&file).await.map_err(error::builder)?;
-        buffer.extend(file_content);
+        body.push_binary(item.as_bytes());
+        // the file is read while sending
+        let len = async_std::fs::metadata(&file).await.map_err(error::builder)?.len();
+        body.push_file(file, len);
+        body.push_binary(DISPOSITION_END.as_bytes());
       }
     }
     let end = disposition.end();
-    buffer.extend_from_slice(end.as_bytes());
-
-    let body = RequestBody::with_vec(buffer);
+    body.push_binary(end.as_bytes());
     Ok(Some(body))
   }
 }
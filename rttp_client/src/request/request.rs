@@ -1,10 +1,12 @@
-use std::fmt;
+use url::Url;
 
-use crate::{error, Config};
-use crate::types::{FormData, Header, Para, Proxy, RoUrl, ToRoUrl};
+use crate::{Client, Config};
+use crate::request::ReaderBody;
+use crate::types::{Auth, FormData, HeaderMap, Para, Proxy, RoUrl, ToRoUrl};
 
 #[derive(Clone, Debug)]
 pub struct Request {
+  client: Client,
   closed: bool,
   count: u32,
   config: Config,
@@ -13,34 +15,51 @@ pub struct Request {
   paths: Vec<String>,
   paras: Vec<Para>,
   formdatas: Vec<FormData>,
-  headers: Vec<Header>,
+  headers: HeaderMap,
   traditional: bool,
   encode: bool,
   raw: Option<String>,
   binary: Vec<u8>,
+  reader: Option<ReaderBody>,
+  chunked: bool,
+  trailers: HeaderMap,
   proxy: Option<Proxy>,
+  auth: Option<Auth>,
+  auth_retries: u32,
+  history: Vec<Url>,
+  /// Method and url of the requests in history, a redirect back to one of them is a loop.
+  visited: Vec<(String, Url)>,
 }
 
 impl Request {
-  pub fn new() -> Self {
+  pub fn with_client(client: Client) -> Self {
     Self {
+      config: client.config().clone(),
+      headers: client.headers().clone(),
+      proxy: client.proxy().clone(),
+      client,
       closed: false,
       count: 1,
-      config: Default::default(),
       url: None,
       method: "GET".to_string(),
       paths: vec![],
       paras: vec![],
       formdatas: vec![],
-      headers: vec![],
       traditional: true,
       encode: true,
       raw: None,
       binary: vec![],
-      proxy: None,
+      reader: None,
+      chunked: false,
+      trailers: HeaderMap::new(),
+      auth: None,
+      auth_retries: 0,
+      history: vec![],
+      visited: vec![],
     }
   }
 
+  pub fn client(&self) -> &Client { &self.client }
   pub fn closed(&self) -> bool { self.closed }
   pub fn config(&self) -> &Config { &self.config }
   pub fn count(&self) -> u32 { self.count }
@@ -49,12 +68,17 @@ impl Request {
   pub fn paths(&self) -> &Vec<String> { &self.paths }
   pub fn paras(&self) -> &Vec<Para> { &self.paras }
   pub fn formdatas(&self) -> &Vec<FormData> { &self.formdatas }
-  pub fn headers(&self) -> &Vec<Header> { &self.headers }
+  pub fn headers(&self) -> &HeaderMap { &self.headers }
   pub fn traditional(&self) -> bool { self.traditional }
   pub fn encode(&self) -> bool { self.encode }
   pub fn raw(&self) -> &Option<String> { &self.raw }
   pub fn binary(&self) -> &Vec<u8> { &self.binary }
+  pub(crate) fn reader(&self) -> &Option<ReaderBody> { &self.reader }
+  pub fn chunked(&self) -> bool { self.chunked }
+  pub fn trailers(&self) -> &HeaderMap { &self.trailers }
   pub fn proxy(&self) -> &Option<Proxy> { &self.proxy }
+  pub fn auth(&self) -> &Option<Auth> { &self.auth }
+  pub fn history(&self) -> &Vec<Url> { &self.history }
 
   pub(crate) fn closed_mut(&mut self) -> &mut bool { &mut self.closed }
   pub(crate) fn config_mut(&mut self) -> &mut Config { &mut self.config }
@@ -64,12 +88,19 @@ impl Request {
   pub(crate) fn paths_mut(&mut self) -> &mut Vec<String> { &mut self.paths }
   pub(crate) fn paras_mut(&mut self) -> &mut Vec<Para> { &mut self.paras }
   pub(crate) fn formdatas_mut(&mut self) -> &mut Vec<FormData> { &mut self.formdatas }
-  pub(crate) fn headers_mut(&mut self) -> &mut Vec<Header> { &mut self.headers }
+  pub(crate) fn headers_mut(&mut self) -> &mut HeaderMap { &mut self.headers }
   pub(crate) fn traditional_mut(&mut self) -> &mut bool { &mut self.traditional }
   pub(crate) fn encode_mut(&mut self) -> &mut bool { &mut self.encode }
   pub(crate) fn raw_mut(&mut self) -> &mut Option<String> { &mut self.raw }
   pub(crate) fn binary_mut(&mut self) -> &mut Vec<u8> { &mut self.binary }
+  pub(crate) fn reader_mut(&mut self) -> &mut Option<ReaderBody> { &mut self.reader }
+  pub(crate) fn trailers_mut(&mut self) -> &mut HeaderMap { &mut self.trailers }
   pub(crate) fn proxy_mut(&mut self) -> &mut Option<Proxy> { &mut self.proxy }
+  pub(crate) fn auth_retries(&self) -> u32 { self.auth_retries }
+  pub(crate) fn auth_mut(&mut self) -> &mut Option<Auth> { &mut self.auth }
+  pub(crate) fn history_mut(&mut self) -> &mut Vec<Url> { &mut self.history }
+  pub(crate) fn visited(&self) -> &Vec<(String, Url)> { &self.visited }
+  pub(crate) fn visited_mut(&mut self) -> &mut Vec<(String, Url)> { &mut self.visited }
 
 
   pub(crate) fn closed_set(&mut self, closed: bool) -> &mut Self {
@@ -104,7 +135,7 @@ impl Request {
     self.formdatas = formdatas;
     self
   }
-  pub(crate) fn headers_set(&mut self, headers: Vec<Header>) -> &mut Self {
+  pub(crate) fn headers_set(&mut self, headers: HeaderMap) -> &mut Self {
     self.headers = headers;
     self
   }
@@ -124,63 +155,29 @@ impl Request {
     self.binary = binary;
     self
   }
-  pub(crate) fn proxy_set(&mut self, proxy: Proxy) -> &mut Self {
-    self.proxy = Some(proxy);
+  pub(crate) fn reader_set(&mut self, reader: ReaderBody) -> &mut Self {
+    self.reader = Some(reader);
     self
   }
-
-  pub fn header<S: AsRef<str>>(&self, name: S) -> Option<String> {
-    self.headers.iter()
-      .find(|h| h.name().eq_ignore_ascii_case(name.as_ref()))
-      .map(|h| h.value().clone())
-  }
-}
-
-
-#[derive(Clone)]
-pub struct RequestBody {
-  binary: Vec<u8>
-}
-
-impl RequestBody {
-  pub fn with_vec(vec: Vec<u8>) -> Self {
-    Self { binary: vec }
-  }
-
-  pub fn with_text<S: AsRef<str>>(text: S) -> Self {
-    Self::with_slice(text.as_ref().to_owned().as_bytes())
-  }
-
-  pub fn with_slice(slice: &[u8]) -> Self {
-    Self::with_vec(slice.to_vec())
-  }
-
-  pub fn bytes(&self) -> &[u8] {
-    self.binary.as_slice()
+  pub(crate) fn chunked_set(&mut self, chunked: bool) -> &mut Self {
+    self.chunked = chunked;
+    self
   }
-
-  pub fn string(&self) -> error::Result<String> {
-    String::from_utf8(self.binary.clone()).map_err(error::request)
+  pub(crate) fn proxy_set(&mut self, proxy: Proxy) -> &mut Self {
+    self.proxy = Some(proxy);
+    self
   }
-
-  pub fn len(&self) -> usize {
-    self.binary.len()
+  pub(crate) fn auth_set(&mut self, auth: Auth) -> &mut Self {
+    self.auth = Some(auth);
+    self
   }
-}
-
-impl fmt::Display for RequestBody {
-  #[inline]
-  fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-    let text = self.string().unwrap_or_default();
-    fmt::Display::fmt(&text, formatter)
+  pub(crate) fn auth_retries_set(&mut self, retries: u32) -> &mut Self {
+    self.auth_retries = retries;
+    self
   }
-}
 
-impl fmt::Debug for RequestBody {
-  #[inline]
-  fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-    let text = self.string().unwrap_or_default();
-    fmt::Debug::fmt(&text, formatter)
+  pub fn header<S: AsRef<str>>(&self, name: S) -> Option<String> {
+    self.headers.get(name).cloned()
   }
 }
 
@@ -0,0 +1,83 @@
+use crate::error;
+use crate::types::{HeaderMap, IntoHeader};
+
+/// What the chunked decoder wants to read next.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum Next {
+  /// A line of chunk size or the line break after chunk data.
+  Line,
+  /// A trailer line, or the empty line end the body.
+  Trailer,
+  /// Chunk data, at most the bytes.
+  Data(u64),
+  End,
+}
+
+/// Decoder of `Transfer-Encoding: chunked` body, shared by the readers of whole and streaming body.
+/// The reader reads what `next` asks and feeds it back, the line break of line is not included.
+/// Chunk extensions are ignored.
+#[derive(Debug)]
+pub(crate) struct ChunkedDecoder {
+  state: State,
+  trailers: HeaderMap,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum State {
+  Size,
+  Data(u64),
+  DataEnd,
+  Trailer,
+  End,
+}
+
+impl ChunkedDecoder {
+  pub(crate) fn new() -> Self {
+    Self { state: State::Size, trailers: HeaderMap::new() }
+  }
+
+  pub(crate) fn next(&self) -> Next {
+    match self.state {
+      State::Size | State::DataEnd => Next::Line,
+      State::Data(rest) => Next::Data(rest),
+      State::Trailer => Next::Trailer,
+      State::End => Next::End,
+    }
+  }
+
+  /// Feed the line asked by `Next::Line` or `Next::Trailer`.
+  pub(crate) fn line(&mut self, line: &str) -> error::Result<()> {
+    self.state = match self.state {
+      State::Size => match chunk_size(line)? {
+        0 => State::Trailer,
+        size => State::Data(size),
+      },
+      // the line break after chunk data
+      State::DataEnd if line.is_empty() => State::Size,
+      State::DataEnd => return Err(error::bad_response("Bad chunk end")),
+      State::Trailer if line.is_empty() => State::End,
+      State::Trailer => {
+        self.trailers.extend(line.into_headers().into_iter().take(1));
+        State::Trailer
+      }
+      state => state,
+    };
+    Ok(())
+  }
+
+  /// Feed the size of chunk data read, not more than asked by `Next::Data`.
+  pub(crate) fn data(&mut self, size: u64) {
+    if let State::Data(rest) = self.state {
+      self.state = if size >= rest { State::DataEnd } else { State::Data(rest - size) };
+    }
+  }
+
+  pub(crate) fn take_trailers(&mut self) -> HeaderMap {
+    std::mem::take(&mut self.trailers)
+  }
+}
+
+fn chunk_size(line: &str) -> error::Result<u64> {
+  let size = line.split(';').next().unwrap_or_default().trim();
+  u64::from_str_radix(size, 16).map_err(|_| error::bad_response("Bad chunk size"))
+}
@@ -0,0 +1,258 @@
+use std::io::{self, Read};
+
+use crate::error;
+
+/// Content codings this build can decode, sent as `Accept-Encoding` if request not set it.
+pub(crate) fn accept_encoding() -> Option<String> {
+  let mut encodings: Vec<&str> = vec![];
+  if cfg!(feature = "gzip") { encodings.push("gzip"); }
+  if cfg!(feature = "deflate") { encodings.push("deflate"); }
+  if cfg!(feature = "brotli") { encodings.push("br"); }
+  if cfg!(feature = "zstd") { encodings.push("zstd"); }
+  if encodings.is_empty() { None } else { Some(encodings.join(", ")) }
+}
+
+/// Codings of `Content-Encoding` headers in order of applied, `identity` is skipped.
+pub(crate) fn content_encodings(values: &[&String]) -> Vec<String> {
+  values.iter()
+    .flat_map(|value| value.split(','))
+    .map(|coding| coding.trim().to_ascii_lowercase())
+    .filter(|coding| !coding.is_empty() && coding != "identity")
+    .collect()
+}
+
+/// Whether all the codings can be decoded.
+pub(crate) fn supported(encodings: &[String]) -> bool {
+  encodings.iter().all(|coding| {
+    let coding = &coding[..];
+    (cfg!(feature = "gzip") && (coding == "gzip" || coding == "x-gzip")) ||
+      (cfg!(feature = "deflate") && coding == "deflate") ||
+      (cfg!(feature = "brotli") && coding == "br") ||
+      (cfg!(feature = "zstd") && coding == "zstd")
+  })
+}
+
+/// Wrap the reader with decoders, the last applied coding is decoded first.
+/// The codings must be `supported`.
+pub(crate) fn decoder<'a, R: Read + Send + 'a>(encodings: &[String], reader: R) -> io::Result<Box<dyn Read + Send + 'a>> {
+  let mut reader: Box<dyn Read + Send + 'a> = Box::new(reader);
+  for coding in encodings.iter().rev() {
+    reader = wrap(coding, reader)?;
+  }
+  Ok(reader)
+}
+
+fn wrap<'a>(coding: &str, reader: Box<dyn Read + Send + 'a>) -> io::Result<Box<dyn Read + Send + 'a>> {
+  match coding {
+    #[cfg(feature = "gzip")]
+    "gzip" | "x-gzip" => Ok(Box::new(flate2::read::MultiGzDecoder::new(reader))),
+    #[cfg(feature = "deflate")]
+    "deflate" => Ok(Box::new(Deflate::Head(Some(io::BufReader::new(reader))))),
+    #[cfg(feature = "brotli")]
+    "br" => Ok(Box::new(brotli::Decompressor::new(reader, 8 * 1024))),
+    #[cfg(feature = "zstd")]
+    "zstd" => Ok(Box::new(zstd::stream::read::Decoder::new(reader)?)),
+    _ => {
+      drop(reader);
+      Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Unsupported content encoding: {}", coding)))
+    }
+  }
+}
+
+/// Decode the whole body.
+pub(crate) fn decode(encodings: &[String], binary: &[u8]) -> error::Result<Vec<u8>> {
+  let mut buffer = Vec::new();
+  decoder(encodings, binary)
+    .and_then(|mut reader| reader.read_to_end(&mut buffer))
+    .map_err(error::decode)?;
+  Ok(buffer)
+}
+
+/// `deflate` should be zlib format, but some servers send raw deflate data,
+/// the zlib header is checked to choose decoder when the body is first read,
+/// so the decoder can be made before the body arrives.
+#[cfg(feature = "deflate")]
+enum Deflate<R> {
+  Head(Option<io::BufReader<R>>),
+  Zlib(flate2::bufread::ZlibDecoder<io::BufReader<R>>),
+  Raw(flate2::bufread::DeflateDecoder<io::BufReader<R>>),
+}
+
+#[cfg(feature = "deflate")]
+impl<R: Read> Read for Deflate<R> {
+  fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+    use std::io::BufRead;
+
+    loop {
+      match self {
+        Deflate::Head(head) => {
+          let zlib = match head.as_mut() {
+            Some(reader) => zlib_header(reader.fill_buf()?),
+            None => return Ok(0),
+          };
+          if let Some(reader) = head.take() {
+            *self = if zlib {
+              Deflate::Zlib(flate2::bufread::ZlibDecoder::new(reader))
+            } else {
+              Deflate::Raw(flate2::bufread::DeflateDecoder::new(reader))
+            };
+          }
+        }
+        Deflate::Zlib(decoder) => return decoder.read(buf),
+        Deflate::Raw(decoder) => return decoder.read(buf),
+      }
+    }
+  }
+}
+
+/// Whether the data start with zlib header.
+#[cfg(feature = "deflate")]
+fn zlib_header(head: &[u8]) -> bool {
+  head.len() >= 2 && head[0] & 0x0f == 8 && (u16::from(head[0]) << 8 | u16::from(head[1])) % 31 == 0
+}
+
+/// Decode the body part by part, used when the body can't be read by a blocking reader.
+/// The codings must be `supported`.
+#[cfg(feature = "async")]
+pub(crate) struct PartDecoder {
+  stages: Vec<Stage>,
+}
+
+#[cfg(feature = "async")]
+impl PartDecoder {
+  pub(crate) fn new(encodings: &[String]) -> io::Result<Self> {
+    let stages = encodings.iter().rev().map(|coding| Stage::new(coding)).collect::<io::Result<Vec<Stage>>>()?;
+    Ok(Self { stages })
+  }
+
+  /// Decode a part of body, returns the decoded data can be output now.
+  pub(crate) fn decode(&mut self, part: &[u8]) -> io::Result<Vec<u8>> {
+    let mut data = part.to_vec();
+    for stage in &mut self.stages {
+      data = stage.decode(&data)?;
+    }
+    Ok(data)
+  }
+
+  /// The body is end, returns the rest decoded data, the incomplete body is an error.
+  pub(crate) fn finish(&mut self) -> io::Result<Vec<u8>> {
+    let mut data = vec![];
+    for stage in &mut self.stages {
+      let mut rest = stage.decode(&data)?;
+      rest.extend(stage.finish()?);
+      data = rest;
+    }
+    Ok(data)
+  }
+}
+
+#[cfg(feature = "async")]
+enum Stage {
+  #[cfg(feature = "gzip")]
+  Gzip(flate2::write::MultiGzDecoder<Vec<u8>>),
+  /// The deflate format is unknown before the first two bytes.
+  #[cfg(feature = "deflate")]
+  DeflateHead(Vec<u8>),
+  #[cfg(feature = "deflate")]
+  Inflate(Inflate),
+  #[cfg(feature = "brotli")]
+  Brotli(Box<brotli::DecompressorWriter<Vec<u8>>>),
+  /// The raw decoder is used, the writer of it tells whether the frame is complete when finish.
+  #[cfg(feature = "zstd")]
+  Zstd(zstd::stream::zio::Writer<Vec<u8>, zstd::stream::raw::Decoder<'static>>),
+}
+
+#[cfg(feature = "async")]
+impl Stage {
+  fn new(coding: &str) -> io::Result<Self> {
+    match coding {
+      #[cfg(feature = "gzip")]
+      "gzip" | "x-gzip" => Ok(Stage::Gzip(flate2::write::MultiGzDecoder::new(vec![]))),
+      #[cfg(feature = "deflate")]
+      "deflate" => Ok(Stage::DeflateHead(vec![])),
+      #[cfg(feature = "brotli")]
+      "br" => Ok(Stage::Brotli(Box::new(brotli::DecompressorWriter::new(vec![], 8 * 1024)))),
+      #[cfg(feature = "zstd")]
+      "zstd" => Ok(Stage::Zstd(zstd::stream::zio::Writer::new(vec![], zstd::stream::raw::Decoder::new()?))),
+      _ => Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Unsupported content encoding: {}", coding))),
+    }
+  }
+
+  // no stage without coding features, the data is not used
+  #[cfg_attr(not(any(feature = "gzip", feature = "deflate", feature = "brotli", feature = "zstd")), allow(unused_variables))]
+  fn decode(&mut self, data: &[u8]) -> io::Result<Vec<u8>> {
+    match *self {
+      #[cfg(feature = "gzip")]
+      Stage::Gzip(ref mut w) => { io::Write::write_all(w, data)?; Ok(std::mem::take(w.get_mut())) }
+      #[cfg(feature = "deflate")]
+      Stage::DeflateHead(ref mut head) => {
+        head.extend_from_slice(data);
+        if head.len() < 2 {
+          return Ok(vec![]);
+        }
+        let head = std::mem::take(head);
+        *self = Stage::Inflate(Inflate::new(zlib_header(&head)));
+        self.decode(&head)
+      }
+      #[cfg(feature = "deflate")]
+      Stage::Inflate(ref mut inflate) => inflate.decode(data),
+      #[cfg(feature = "brotli")]
+      Stage::Brotli(ref mut w) => { io::Write::write_all(w, data)?; Ok(std::mem::take(w.get_mut())) }
+      #[cfg(feature = "zstd")]
+      Stage::Zstd(ref mut w) => { io::Write::write_all(w, data)?; Ok(std::mem::take(w.writer_mut())) }
+    }
+  }
+
+  fn finish(&mut self) -> io::Result<Vec<u8>> {
+    match *self {
+      #[cfg(feature = "gzip")]
+      Stage::Gzip(ref mut w) => { w.try_finish()?; Ok(std::mem::take(w.get_mut())) }
+      #[cfg(feature = "deflate")]
+      Stage::DeflateHead(ref head) if head.is_empty() => Ok(vec![]),
+      #[cfg(feature = "deflate")]
+      Stage::DeflateHead(_) => Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Incomplete deflate data")),
+      #[cfg(feature = "deflate")]
+      Stage::Inflate(ref inflate) if !inflate.ended => Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Incomplete deflate data")),
+      #[cfg(feature = "deflate")]
+      Stage::Inflate(_) => Ok(vec![]),
+      #[cfg(feature = "brotli")]
+      Stage::Brotli(ref mut w) => { w.close()?; Ok(std::mem::take(w.get_mut())) }
+      #[cfg(feature = "zstd")]
+      Stage::Zstd(ref mut w) => { w.finish()?; Ok(std::mem::take(w.writer_mut())) }
+    }
+  }
+}
+
+/// Zlib or raw deflate decompressor. The write decoders of flate2 not tell whether the stream
+/// is end, the truncated data would be taken as complete.
+#[cfg(all(feature = "async", feature = "deflate"))]
+struct Inflate {
+  decompress: flate2::Decompress,
+  ended: bool,
+}
+
+#[cfg(all(feature = "async", feature = "deflate"))]
+impl Inflate {
+  fn new(zlib: bool) -> Self {
+    Self { decompress: flate2::Decompress::new(zlib), ended: false }
+  }
+
+  /// Decompress the data, the data after end of stream is ignored.
+  fn decode(&mut self, data: &[u8]) -> io::Result<Vec<u8>> {
+    let mut output = Vec::new();
+    let mut input = data;
+    while !self.ended {
+      output.reserve(32 * 1024);
+      let (total_in, total_out) = (self.decompress.total_in(), self.decompress.total_out());
+      let status = self.decompress.decompress_vec(input, &mut output, flate2::FlushDecompress::None)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+      input = &input[(self.decompress.total_in() - total_in) as usize..];
+      self.ended = status == flate2::Status::StreamEnd;
+      // all the input is consumed and the output is drained
+      if self.decompress.total_in() == total_in && self.decompress.total_out() == total_out {
+        break;
+      }
+    }
+    Ok(output)
+  }
+}
@@ -1,5 +1,10 @@
-
 pub use self::response::*;
+pub use self::stream::*;
+pub(crate) use self::decoder::accept_encoding;
+pub(crate) use self::chunked::{ChunkedDecoder, Next};
 
 mod response;
 mod raw_response;
+mod decoder;
+mod chunked;
+mod stream;
@@ -1,9 +1,11 @@
+use std::convert::TryFrom;
 use std::fmt;
-use std::io::Read;
 
 use crate::error;
 use crate::response::ResponseBody;
-use crate::types::{Header, IntoHeader, Cookie, RoUrl, ToUrl};
+use crate::response::decoder;
+use crate::response::{ChunkedDecoder, Next};
+use crate::types::{HeaderMap, IntoHeader, Cookie, RoUrl, StatusCode, ToUrl};
 use url::Url;
 
 const CR: u8 = b'\r';
@@ -18,9 +20,11 @@ pub struct RawResponse {
   code: u32,
   version: String,
   reason: String,
-  headers: Vec<Header>,
+  headers: HeaderMap,
+  trailers: HeaderMap,
   cookies: Vec<Cookie>,
   body: ResponseBody,
+  history: Vec<Url>,
 }
 
 impl RawResponse {
@@ -33,14 +37,41 @@ impl RawResponse {
       code: 0,
       version: "".to_string(),
       reason: "".to_string(),
-      headers: vec![],
+      headers: HeaderMap::new(),
+      trailers: HeaderMap::new(),
       cookies: vec![],
       body: ResponseBody::new(vec![]),
+      history: vec![],
     };
     Parser::new(binary).parse(&mut response)?;
     Ok(response)
   }
 
+  /// Create response from the status line and headers already parsed by connection reader,
+  /// the `body` is the framed body binary.
+  pub(crate) fn with_parts(url: RoUrl, binary: Vec<u8>, status_line: &str, headers: HeaderMap, body: Vec<u8>) -> error::Result<Self> {
+    let _url = url.to_url().map_err(error::builder)?;
+    let mut response = RawResponse {
+      _url,
+      url,
+      binary: vec![],
+      code: 0,
+      version: "".to_string(),
+      reason: "".to_string(),
+      headers: HeaderMap::new(),
+      trailers: HeaderMap::new(),
+      cookies: vec![],
+      body: ResponseBody::new(vec![]),
+      history: vec![],
+    };
+    let parser = Parser::new(binary);
+    parser.parse_status_line(&mut response, status_line)?;
+    parser.parse_headers(&mut response, headers);
+    parser.parse_body(&mut response, body)?;
+    response.binary = parser.binary;
+    Ok(response)
+  }
+
   pub fn binary(&mut self, binary: Vec<u8>) -> &mut Self {
     self.binary = binary;
     self
@@ -57,10 +88,14 @@ impl RawResponse {
     self.reason = reason.as_ref().to_owned();
     self
   }
-  pub fn headers(&mut self, headers: Vec<Header>) -> &mut Self {
+  pub fn headers(&mut self, headers: HeaderMap) -> &mut Self {
     self.headers = headers;
     self
   }
+  pub fn trailers(&mut self, trailers: HeaderMap) -> &mut Self {
+    self.trailers = trailers;
+    self
+  }
   pub fn body(&mut self, body: ResponseBody) -> &mut Self {
     self.body = body;
     self
@@ -69,6 +104,10 @@ impl RawResponse {
     self.cookies = cookies;
     self
   }
+  pub(crate) fn history(&mut self, history: Vec<Url>) -> &mut Self {
+    self.history = history;
+    self
+  }
 
 
   pub(crate) fn url_get(&self) -> &Url { &self._url }
@@ -76,9 +115,11 @@ impl RawResponse {
   pub fn code_get(&self) -> u32 { self.code }
   pub fn version_get(&self) -> &String { &self.version }
   pub fn reason_get(&self) -> &String { &self.reason }
-  pub fn headers_get(&self) -> &Vec<Header> { &self.headers }
+  pub fn headers_get(&self) -> &HeaderMap { &self.headers }
+  pub fn trailers_get(&self) -> &HeaderMap { &self.trailers }
   pub fn body_get(&self) -> &ResponseBody { &self.body }
   pub fn cookies_get(&self) -> &Vec<Cookie> { &self.cookies }
+  pub fn history_get(&self) -> &Vec<Url> { &self.history }
 
   pub fn string(&self) -> error::Result<String> {
     let mut text = String::new();
@@ -161,17 +202,7 @@ impl Parser {
 
     let parts: Vec<&str> = text.split(CRLF).collect();
     let status_line = parts.get(0).ok_or(error::bad_response("Response not have status line"))?;
-    let status_parts: Vec<&str> = status_line.splitn(3, " ").collect();
-
-    let http_version = status_parts.get(0).ok_or(error::bad_response("Response status not have http version"))?;
-    let status_code: u32 = match status_parts.get(1).ok_or(error::bad_response("Response status not have code"))?.parse() {
-      Ok(c) => c,
-      Err(_) => return Err(error::bad_response("Response status code is not a number")),
-    };
-    let reason = status_parts.get(2).unwrap_or(&"");
-    response.version(http_version)
-      .code(status_code)
-      .reason(reason);
+    self.parse_status_line(response, status_line)?;
 
     let headers = parts.iter().enumerate()
       .filter(|(ix, _)| *ix > 0)
@@ -186,8 +217,30 @@ impl Parser {
       })
       .filter(Option::is_some)
       .map(|h| h.unwrap())
-      .collect::<Vec<Header>>();
+      .collect::<HeaderMap>();
 
+    self.parse_headers(response, headers);
+    Ok(())
+  }
+
+  fn parse_status_line(&self, response: &mut RawResponse, status_line: &str) -> error::Result<()> {
+    let status_parts: Vec<&str> = status_line.splitn(3, " ").collect();
+
+    let http_version = status_parts.first().ok_or(error::bad_response("Response status not have http version"))?;
+    let status_code = status_parts.get(1).ok_or(error::bad_response("Response status not have code"))?;
+    // any three digits, the code not registered is handled by the class of it
+    let status_code = match StatusCode::from_bytes(status_code.as_bytes()) {
+      Ok(code) => u32::from(code.as_u16()),
+      Err(_) => return Err(error::bad_response("Response status code is invalid")),
+    };
+    let reason = status_parts.get(2).unwrap_or(&"");
+    response.version(http_version)
+      .code(status_code)
+      .reason(reason);
+    Ok(())
+  }
+
+  fn parse_headers(&self, response: &mut RawResponse, headers: HeaderMap) {
     let cookies: Vec<Cookie> = headers.iter()
       .filter(|header| header.name().eq_ignore_ascii_case("set-cookie"))
       .map(|header| Cookie::parse(header.value()).ok())
@@ -197,30 +250,76 @@ impl Parser {
 
     response.headers(headers);
     response.cookies(cookies);
-    Ok(())
   }
 
   fn parse_body(&self, response: &mut RawResponse, binary: Vec<u8>) -> error::Result<()> {
     if binary.is_empty() { return Ok(()); }
 
-    let content_encoding = response.headers_get().iter()
-      .find(|header| header.name().eq_ignore_ascii_case("Content-Encoding"));
-
-    if let Some(header) = content_encoding {
-      if header.value().eq_ignore_ascii_case("gzip") {
-        let mut decoder = flate2::read::GzDecoder::new(binary.as_slice());
-        let mut buffer = Vec::new();
-        decoder.read_to_end(&mut buffer).unwrap();
-        let body = ResponseBody::new(buffer);
-        response.body(body);
-        return Ok(());
-      }
+    let chunked = response.headers_get().get_all("Transfer-Encoding").iter()
+      .flat_map(|value| value.split(','))
+      .map(|value| value.trim())
+      .rfind(|value| !value.is_empty())
+      .is_some_and(|value| value.eq_ignore_ascii_case("chunked"));
+    let binary = if chunked {
+      let (binary, trailers) = self.parse_chunked(&binary)?;
+      response.trailers(trailers);
+      binary
+    } else {
+      binary
+    };
+
+    // the body of unsupported encoding is kept as is
+    let encodings = decoder::content_encodings(&response.headers_get().get_all("Content-Encoding"));
+    if !encodings.is_empty() && decoder::supported(&encodings) {
+      let body = ResponseBody::new(decoder::decode(&encodings, &binary)?);
+      response.body(body);
+      return Ok(());
     }
 
     let body = ResponseBody::new(binary);
     response.body(body);
     Ok(())
   }
-}
 
+  /// Decode `Transfer-Encoding: chunked` body, chunk extensions are ignored,
+  /// returns the decoded body and the trailer headers.
+  fn parse_chunked(&self, binary: &[u8]) -> error::Result<(Vec<u8>, HeaderMap)> {
+    let mut decoder = ChunkedDecoder::new();
+    let mut body = Vec::new();
+    let mut position = 0;
+    loop {
+      match decoder.next() {
+        Next::Line => {
+          let line = next_line(binary, &mut position)
+            .ok_or(error::bad_response("Incomplete chunked body"))?;
+          decoder.line(&line)?;
+        }
+        // trailer part, end with empty line or end of binary
+        Next::Trailer => match next_line(binary, &mut position) {
+          Some(line) => decoder.line(&line)?,
+          None => break,
+        },
+        Next::Data(rest) => {
+          let available = binary.len() - position;
+          let len = usize::try_from(rest).map_or(available, |rest| rest.min(available));
+          if len == 0 {
+            return Err(error::bad_response("Incomplete chunked body"));
+          }
+          body.extend_from_slice(&binary[position..position + len]);
+          position += len;
+          decoder.data(len as u64);
+        }
+        Next::End => break,
+      }
+    }
+    Ok((body, decoder.take_trailers()))
+  }
+}
 
+/// Read a line end with `\n` from `position`, the line break is not included.
+fn next_line(binary: &[u8], position: &mut usize) -> Option<String> {
+  let rest = binary.get(*position..)?;
+  let len = rest.iter().position(|b| *b == LF)?;
+  *position += len + 1;
+  Some(String::from_utf8_lossy(&rest[..len]).trim_end_matches(CR as char).to_string())
+}
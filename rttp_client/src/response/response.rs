@@ -4,7 +4,7 @@ use url::Url;
 
 use crate::error;
 use crate::response::raw_response::RawResponse;
-use crate::types::{Cookie, Header, RoUrl};
+use crate::types::{Cookie, Header, HeaderMap, RoUrl, StatusCode};
 
 #[derive(Clone)]
 pub struct Response {
@@ -17,11 +17,82 @@ impl Response {
       raw: RawResponse::new(url, binary)?
     })
   }
+
+  pub(crate) fn with_parts(url: RoUrl, binary: Vec<u8>, status_line: &str, headers: HeaderMap, body: Vec<u8>) -> error::Result<Self> {
+    Ok(Self {
+      raw: RawResponse::with_parts(url, binary, status_line, headers, body)?
+    })
+  }
+
+  pub(crate) fn history_set(&mut self, history: Vec<Url>) -> &mut Self {
+    self.raw.history(history);
+    self
+  }
 }
 
 impl Response {
+  /// Status is `2xx`, same as `is_success`.
   pub fn ok(&self) -> bool {
-    self.code() == 200
+    self.is_success()
+  }
+
+  /// Typed status code of response.
+  pub fn status(&self) -> StatusCode {
+    // the status code is validated when parse response, it is in 100..1000
+    StatusCode::from_u16(self.code() as u16).unwrap_or_default()
+  }
+
+  /// Status is `1xx`
+  pub fn is_informational(&self) -> bool {
+    self.status().is_informational()
+  }
+
+  /// Status is `2xx`
+  pub fn is_success(&self) -> bool {
+    self.status().is_success()
+  }
+
+  /// Status is `4xx`
+  pub fn is_client_error(&self) -> bool {
+    self.status().is_client_error()
+  }
+
+  /// Status is `5xx`
+  pub fn is_server_error(&self) -> bool {
+    self.status().is_server_error()
+  }
+
+  /// Turn a `4xx` or `5xx` response into an error, the error have status code and url of response.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// # use rttp_client::HttpClient;
+  /// # fn run() -> Result<(), rttp_client::error::Error> {
+  /// let response = HttpClient::new()
+  ///   .get()
+  ///   .url("http://httpbin.org/status/404")
+  ///   .emit()?
+  ///   .error_for_status();
+  /// if let Err(e) = response {
+  ///   assert!(e.is_status());
+  ///   assert_eq!(Some(404), e.status().map(|s| s.as_u16()));
+  /// }
+  /// # Ok(())
+  /// # }
+  /// ```
+  pub fn error_for_status(self) -> error::Result<Self> {
+    self.error_for_status_ref()?;
+    Ok(self)
+  }
+
+  /// Same as `error_for_status`, but not consume the response.
+  pub fn error_for_status_ref(&self) -> error::Result<&Self> {
+    let status = self.status();
+    if status.is_client_error() || status.is_server_error() {
+      return Err(error::status_code(self.url().clone(), status));
+    }
+    Ok(self)
   }
 
   pub fn is_redirect(&self) -> bool {
@@ -44,7 +115,8 @@ impl Response {
     self.raw.reason_get()
   }
 
-  fn url(&self) -> &Url {
+  /// The url of this response, if redirected, it's the final url.
+  pub fn url(&self) -> &Url {
     self.raw.url_get()
   }
 
@@ -64,30 +136,43 @@ impl Response {
     self.header_value("location")
   }
 
-  pub fn headers(&self) -> &Vec<Header> {
+  pub fn headers(&self) -> &HeaderMap {
     self.raw.headers_get()
   }
 
   pub fn headers_of_name<S: AsRef<str>>(&self, name: S) -> Vec<&Header> {
-    self.headers().iter()
-      .filter(|header| header.name().eq_ignore_ascii_case(name.as_ref()))
-      .collect()
+    self.headers().headers(name)
   }
 
   pub fn header<S: AsRef<str>>(&self, name: S) -> Option<&Header> {
-    self.headers().iter()
-      .find(|header| header.name().eq_ignore_ascii_case(name.as_ref()))
+    self.headers().header(name)
   }
 
   pub fn header_values<S: AsRef<str>>(&self, name: S) -> Vec<&String> {
-    self.headers().iter()
-      .filter(|header| header.name().eq_ignore_ascii_case(name.as_ref()))
-      .map(|header| header.value())
-      .collect()
+    self.headers().get_all(name)
   }
 
   pub fn header_value<S: AsRef<str>>(&self, name: S) -> Option<&String> {
-    self.header(name).map(|header| header.value())
+    self.headers().get(name)
+  }
+
+  /// Trailer headers sent after a chunked body.
+  pub fn trailers(&self) -> &HeaderMap {
+    self.raw.trailers_get()
+  }
+
+  pub fn trailer<S: AsRef<str>>(&self, name: S) -> Option<&Header> {
+    self.trailers().header(name)
+  }
+
+  pub fn trailer_value<S: AsRef<str>>(&self, name: S) -> Option<&String> {
+    self.trailers().get(name)
+  }
+
+  /// Urls visited to get this response, the first is the request url,
+  /// followed by every redirect url.
+  pub fn history(&self) -> &Vec<Url> {
+    self.raw.history_get()
   }
 
   pub fn cookies(&self) -> &Vec<Cookie> {
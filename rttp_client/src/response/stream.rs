@@ -0,0 +1,272 @@
+use std::fmt;
+use std::io;
+use std::sync::{Arc, Mutex};
+#[cfg(feature = "async")]
+use std::future::Future;
+#[cfg(feature = "async")]
+use std::pin::Pin;
+#[cfg(feature = "async")]
+use std::task::{Context, Poll};
+
+use url::Url;
+
+use crate::connection::BodyReader;
+#[cfg(feature = "async")]
+use crate::connection::{AsyncBodyReader, AsyncStream};
+use crate::error;
+use crate::response::decoder;
+use crate::response::Response;
+use crate::types::{HeaderMap, StatusCode};
+
+/// Response of `emit_stream`, returned once the status line and headers are read.
+/// The body is read from connection as it arrives by `std::io::Read`,
+/// the chunked body and the supported content encodings are decoded while reading.
+///
+/// # Examples
+///
+/// ```rust
+/// # use rttp_client::HttpClient;
+/// # fn run() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut response = HttpClient::new()
+///   .get()
+///   .url("http://httpbin.org/bytes/1024")
+///   .emit_stream()?;
+/// assert_eq!(200, response.code());
+/// let mut file = std::fs::File::create("bytes.bin")?;
+/// std::io::copy(&mut response, &mut file)?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct StreamResponse {
+  response: Response,
+  body: Box<dyn io::Read + Send>,
+  trailers: Arc<Mutex<HeaderMap>>,
+}
+
+impl StreamResponse {
+  pub(crate) fn new<S: io::Read + Send + 'static>(response: Response, body: BodyReader<S>) -> error::Result<Self> {
+    let trailers = body.trailers();
+    let encodings = decoder::content_encodings(&response.header_values("content-encoding"));
+    let body: Box<dyn io::Read + Send> = if !body.is_end() && !encodings.is_empty() && decoder::supported(&encodings) {
+      decoder::decoder(&encodings, body).map_err(error::decode)?
+    } else {
+      Box::new(body)
+    };
+    Ok(Self { response, body, trailers })
+  }
+
+  /// The status line, headers and cookies of response, the body of it is empty.
+  pub fn response(&self) -> &Response {
+    &self.response
+  }
+
+  pub fn code(&self) -> u32 {
+    self.response.code()
+  }
+
+  pub fn status(&self) -> StatusCode {
+    self.response.status()
+  }
+
+  /// The url of this response, if redirected, it's the final url.
+  pub fn url(&self) -> &Url {
+    self.response.url()
+  }
+
+  pub fn headers(&self) -> &HeaderMap {
+    self.response.headers()
+  }
+
+  pub fn header_value<S: AsRef<str>>(&self, name: S) -> Option<&String> {
+    self.response.header_value(name)
+  }
+
+  /// Trailer headers sent after a chunked body, empty until the body is read to end.
+  pub fn trailers(&self) -> HeaderMap {
+    self.trailers.lock().map(|trailers| trailers.clone()).unwrap_or_default()
+  }
+}
+
+impl io::Read for StreamResponse {
+  fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+    self.body.read(buf)
+  }
+}
+
+impl fmt::Debug for StreamResponse {
+  fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+    formatter.debug_struct("StreamResponse")
+      .field("url", &self.url().as_str())
+      .field("code", &self.code())
+      .finish()
+  }
+}
+
+
+#[cfg(feature = "async")]
+type NextPart = Pin<Box<dyn Future<Output=(AsyncBodyReader<Box<dyn AsyncStream>>, io::Result<Option<Vec<u8>>>)> + Send>>;
+
+/// Response of `rasync_stream`, same as `StreamResponse`, the body is read by
+/// `async_std::io::Read`, or part by part as a `Stream` of `Vec<u8>`.
+///
+/// # Examples
+///
+/// ```rust
+/// # use rttp_client::HttpClient;
+/// # #[cfg(feature = "async")]
+/// # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+/// use async_std::prelude::*;
+///
+/// let mut response = HttpClient::new()
+///   .get()
+///   .url("http://httpbin.org/bytes/1024")
+///   .rasync_stream()
+///   .await?;
+/// while let Some(part) = response.next().await {
+///   println!("{}", part?.len());
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(feature = "async")]
+pub struct AsyncStreamResponse {
+  response: Response,
+  body: Option<AsyncBodyReader<Box<dyn AsyncStream>>>,
+  next: Option<NextPart>,
+  decoder: Option<decoder::PartDecoder>,
+  part: Vec<u8>,
+  position: usize,
+  trailers: Arc<Mutex<HeaderMap>>,
+}
+
+#[cfg(feature = "async")]
+impl AsyncStreamResponse {
+  pub(crate) fn new(response: Response, body: AsyncBodyReader<Box<dyn AsyncStream>>) -> error::Result<Self> {
+    let trailers = body.trailers();
+    let encodings = decoder::content_encodings(&response.header_values("content-encoding"));
+    let decoder = if !body.is_end() && !encodings.is_empty() && decoder::supported(&encodings) {
+      Some(decoder::PartDecoder::new(&encodings).map_err(error::decode)?)
+    } else {
+      None
+    };
+    Ok(Self { response, body: Some(body), next: None, decoder, part: vec![], position: 0, trailers })
+  }
+
+  /// The status line, headers and cookies of response, the body of it is empty.
+  pub fn response(&self) -> &Response {
+    &self.response
+  }
+
+  pub fn code(&self) -> u32 {
+    self.response.code()
+  }
+
+  pub fn status(&self) -> StatusCode {
+    self.response.status()
+  }
+
+  /// The url of this response, if redirected, it's the final url.
+  pub fn url(&self) -> &Url {
+    self.response.url()
+  }
+
+  pub fn headers(&self) -> &HeaderMap {
+    self.response.headers()
+  }
+
+  pub fn header_value<S: AsRef<str>>(&self, name: S) -> Option<&String> {
+    self.response.header_value(name)
+  }
+
+  /// Trailer headers sent after a chunked body, empty until the body is read to end.
+  pub fn trailers(&self) -> HeaderMap {
+    self.trailers.lock().map(|trailers| trailers.clone()).unwrap_or_default()
+  }
+
+  /// Poll the next decoded part of body, `None` if the body is read to end.
+  fn poll_part(&mut self, cx: &mut Context) -> Poll<Option<io::Result<Vec<u8>>>> {
+    loop {
+      let mut next = match (self.next.take(), self.body.take()) {
+        (Some(next), _) => next,
+        (None, Some(body)) => Box::pin(body.next_part()),
+        (None, None) => return Poll::Ready(None),
+      };
+      let (body, part) = match next.as_mut().poll(cx) {
+        Poll::Ready(v) => v,
+        Poll::Pending => {
+          self.next = Some(next);
+          return Poll::Pending;
+        }
+      };
+      let part = match part {
+        Ok(Some(part)) => part,
+        // the body is end, the rest data in decoder is the last part
+        Ok(None) => return match self.decoder.take().map(|mut decoder| decoder.finish()) {
+          Some(Ok(part)) if !part.is_empty() => Poll::Ready(Some(Ok(part))),
+          Some(Err(e)) => Poll::Ready(Some(Err(e))),
+          _ => Poll::Ready(None),
+        },
+        Err(e) => return Poll::Ready(Some(Err(e))),
+      };
+      let part = match self.decoder.as_mut() {
+        Some(decoder) => match decoder.decode(&part) {
+          Ok(part) => part,
+          Err(e) => return Poll::Ready(Some(Err(e))),
+        },
+        None => part,
+      };
+      self.body = Some(body);
+      if !part.is_empty() {
+        return Poll::Ready(Some(Ok(part)));
+      }
+    }
+  }
+}
+
+#[cfg(feature = "async")]
+impl async_std::io::Read for AsyncStreamResponse {
+  fn poll_read(self: Pin<&mut Self>, cx: &mut Context, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+    let this = self.get_mut();
+    while this.position >= this.part.len() {
+      match this.poll_part(cx) {
+        Poll::Ready(Some(Ok(part))) => {
+          this.part = part;
+          this.position = 0;
+        }
+        Poll::Ready(Some(Err(e))) => return Poll::Ready(Err(e)),
+        Poll::Ready(None) => return Poll::Ready(Ok(0)),
+        Poll::Pending => return Poll::Pending,
+      }
+    }
+    let size = buf.len().min(this.part.len() - this.position);
+    buf[..size].copy_from_slice(&this.part[this.position..this.position + size]);
+    this.position += size;
+    Poll::Ready(Ok(size))
+  }
+}
+
+#[cfg(feature = "async")]
+impl async_std::stream::Stream for AsyncStreamResponse {
+  type Item = io::Result<Vec<u8>>;
+
+  fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+    let this = self.get_mut();
+    // the rest of the part partly read by `poll_read`
+    if this.position < this.part.len() {
+      let part = this.part.split_off(this.position);
+      this.position = this.part.len();
+      return Poll::Ready(Some(Ok(part)));
+    }
+    this.poll_part(cx)
+  }
+}
+
+#[cfg(feature = "async")]
+impl fmt::Debug for AsyncStreamResponse {
+  fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+    formatter.debug_struct("AsyncStreamResponse")
+      .field("url", &self.url().as_str())
+      .field("code", &self.code())
+      .finish()
+  }
+}
@@ -0,0 +1,94 @@
+use url::Url;
+
+/// Http authentication
+///
+/// # Examples
+///
+/// ```rust
+/// # use rttp_client::HttpClient;
+/// # use rttp_client::types::Auth;
+/// HttpClient::new()
+///   .get()
+///   .url("http://httpbin.org/basic-auth/user/passwd")
+///   .auth(Auth::basic("user", "passwd"));
+/// HttpClient::new()
+///   .get()
+///   .url("http://httpbin.org/bearer")
+///   .auth(Auth::bearer("token"));
+/// HttpClient::new()
+///   .get()
+///   .url("http://httpbin.org/digest-auth/auth/user/passwd")
+///   .auth(Auth::digest("user", "passwd"));
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Auth {
+  Basic {
+    username: String,
+    password: Option<String>,
+  },
+  Bearer(String),
+  /// Digest auth, the request is sent again with credentials
+  /// when server answer `401` with a `Digest` challenge.
+  Digest {
+    username: String,
+    password: String,
+  },
+}
+
+impl Auth {
+  pub fn basic<U: AsRef<str>, P: AsRef<str>>(username: U, password: P) -> Self {
+    Auth::Basic {
+      username: username.as_ref().to_string(),
+      password: Some(password.as_ref().to_string()),
+    }
+  }
+
+  /// Basic auth only have username
+  pub fn basic_username<U: AsRef<str>>(username: U) -> Self {
+    Auth::Basic {
+      username: username.as_ref().to_string(),
+      password: None,
+    }
+  }
+
+  pub fn bearer<T: AsRef<str>>(token: T) -> Self {
+    Auth::Bearer(token.as_ref().to_string())
+  }
+
+  pub fn digest<U: AsRef<str>, P: AsRef<str>>(username: U, password: P) -> Self {
+    Auth::Digest {
+      username: username.as_ref().to_string(),
+      password: password.as_ref().to_string(),
+    }
+  }
+
+  /// Basic auth from the userinfo of url, the username and password are percent decoded.
+  pub fn from_url(url: &Url) -> Option<Self> {
+    if url.username().is_empty() && url.password().is_none() {
+      return None;
+    }
+    let decode = |text: &str| percent_encoding::percent_decode_str(text).decode_utf8_lossy().to_string();
+    Some(Auth::Basic {
+      username: decode(url.username()),
+      password: url.password().map(decode),
+    })
+  }
+
+  /// Value of `Authorization` header, `None` for digest auth, it's answer of server challenge.
+  pub fn header_value(&self) -> Option<String> {
+    match self {
+      Auth::Basic { username, password } => {
+        let credentials = format!("{}:{}", username, password.as_ref().map_or("", |p| &p[..]));
+        Some(format!("Basic {}", base64::encode(&credentials)))
+      }
+      Auth::Bearer(token) => Some(format!("Bearer {}", token)),
+      Auth::Digest { .. } => None,
+    }
+  }
+}
+
+impl AsRef<Auth> for Auth {
+  fn as_ref(&self) -> &Auth {
+    self
+  }
+}
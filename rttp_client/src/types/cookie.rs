@@ -1,5 +1,5 @@
 use std::fmt;
-use std::time::SystemTime;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use crate::error;
 
@@ -14,7 +14,7 @@ pub struct Cookie {
   http_only: bool,
   persistent: bool,
   host_only: bool,
-  same_site: Option<String>,
+  same_site: Option<SameSite>,
 }
 
 impl Cookie {
@@ -27,7 +27,30 @@ impl Cookie {
   pub fn http_only(&self) -> bool { self.http_only }
   pub fn persistent(&self) -> bool { self.persistent }
   pub fn host_only(&self) -> bool { self.host_only }
-  pub fn same_site(&self) -> &Option<String> { &self.same_site }
+  pub fn same_site(&self) -> &Option<SameSite> { &self.same_site }
+
+  /// The cookie have expires time and it's passed.
+  pub fn is_expired(&self) -> bool {
+    self.expires.is_some_and(|expires| expires <= SystemTime::now())
+  }
+
+  /// Same name, domain and path, the new one replace the old one in cookie store.
+  pub(crate) fn same_identity(&self, other: &Cookie) -> bool {
+    self.name == other.name && self.domain == other.domain && self.path == other.path
+  }
+
+  pub(crate) fn path_set(&mut self, path: Option<String>) -> &mut Self {
+    self.path = path;
+    self
+  }
+  pub(crate) fn domain_set(&mut self, domain: Option<String>) -> &mut Self {
+    self.domain = domain;
+    self
+  }
+  pub(crate) fn host_only_set(&mut self, host_only: bool) -> &mut Self {
+    self.host_only = host_only;
+    self
+  }
 
   pub fn string(&self) -> String {
     let mut text = format!(
@@ -70,68 +93,132 @@ impl Cookie {
     CookieBuilder::new()
   }
 
+  /// Parse `Set-Cookie` header value by rfc6265, the first pair is name and value,
+  /// the attributes are case-insensitive and unknown attributes are ignored, the bad `Expires` date is an error.
+  /// `Max-Age` take precedence over `Expires`.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// # use rttp_client::types::{Cookie, SameSite};
+  /// let cookie = Cookie::parse("id=a3fWa; Max-Age=2592000; Path=/; Secure; SameSite=Lax").unwrap();
+  /// assert_eq!("id", cookie.name());
+  /// assert!(cookie.secure());
+  /// assert_eq!(&Some(SameSite::Lax), cookie.same_site());
+  /// ```
   pub fn parse<S: AsRef<str>>(text: S) -> error::Result<Self> {
+    let mut parts = text.as_ref().split(';');
+    let pair = parts.next().unwrap_or_default();
+    let (name, value) = pair.split_once('=')
+      .ok_or_else(|| error::bad_cookie("Cookie not have name value pair"))?;
+    let name = name.trim();
+    if name.is_empty() {
+      return Err(error::bad_cookie("Cookie not have name"));
+    }
+
     let mut builder = Cookie::builder();
-    let parts: Vec<&str> = text.as_ref().split(";").collect();
-    for item in parts {
-      let nvs: Vec<&str> = item.split("=").collect();
-      let name = nvs.get(0).ok_or(error::bad_cookie("Cookie not have name"))?.trim();
-      let value: String = nvs.iter().enumerate()
-        .filter(|(ix, _)| *ix > 0)
-        .map(|(_, v)| *v)
-        .collect::<Vec<&str>>()
-        .join("=");
-      let value = value.trim();
-      match name {
+    builder.name(name).value(value.trim());
+    let mut max_age: Option<i64> = None;
+    for attribute in parts {
+      let (key, value) = match attribute.split_once('=') {
+        Some((key, value)) => (key.trim(), value.trim()),
+        None => (attribute.trim(), ""),
+      };
+      match &key.to_ascii_lowercase()[..] {
         "expires" => {
-          let value = value.replace("-", " ");
-          match httpdate::parse_http_date(&value[..]) {
-            Ok(v) => {
-              builder.expires(v);
-            }
-            Err(e) => eprintln!("=> {:?}", e)
-          }
+          builder.expires(parse_cookie_date(value)?);
         }
-        "path" => {
-          builder.path(value);
+        "max-age" => {
+          // not a number is ignored
+          if let Ok(seconds) = value.parse() {
+            max_age = Some(seconds);
+          }
         }
         "domain" => {
-          builder.domain(value);
-        }
-        "secure" => {
-          if value.is_empty() {
-            builder.secure(true);
-          } else {
-            builder.secure(value.parse().map_err(|_| error::bad_cookie("Cookie secure can not parse to bool"))?);
+          let domain = value.trim_start_matches('.');
+          if !domain.is_empty() {
+            builder.domain(domain.to_ascii_lowercase());
           }
         }
-        "http_only" | "httponly" | "httpOnly" => {
-          if value.is_empty() {
-            builder.http_only(true);
-          } else {
-            builder.http_only(value.parse().map_err(|_| error::bad_cookie("Cookie httpOnly can not parse to bool"))?);
-          }
+        "path" if value.starts_with('/') => {
+          builder.path(value);
         }
-        "host_only" | "hostonly" | "hostOnly" => {
-          if value.is_empty() {
-            builder.host_only(true);
-          } else {
-            builder.host_only(value.parse().map_err(|_| error::bad_cookie("Cookie hostOnly can not parse to bool"))?);
-          }
+        "secure" => {
+          builder.secure(true);
         }
-        "same_site" | "SameSite" | "samSite" => {
-          builder.same_site(value);
+        "httponly" => {
+          builder.http_only(true);
         }
-        _ => {
-          builder.name(name);
-          builder.value(value);
+        // not a rfc6265 attribute, it's written by `Cookie::string`
+        "hostonly" => {
+          builder.host_only(true);
         }
+        "samesite" => {
+          if let Some(same_site) = SameSite::parse(value) {
+            builder.same_site(same_site);
+          }
+        }
+        _ => {}
       }
     }
+
+    if let Some(seconds) = max_age {
+      let expires = if seconds <= 0 {
+        UNIX_EPOCH
+      } else {
+        SystemTime::now().checked_add(Duration::from_secs(seconds as u64))
+          .filter(|expires| *expires < max_expires())
+          .unwrap_or_else(max_expires)
+      };
+      builder.expires(expires);
+    }
     Ok(builder.build())
   }
 }
 
+/// The latest expiry date, `9999-12-31 23:59:59 GMT`, the later date can't be written to `Expires`.
+pub(crate) fn max_expires() -> SystemTime {
+  UNIX_EPOCH + Duration::from_secs(253_402_300_799)
+}
+
+/// Parse the date of `Expires` attribute, the formats of rfc7231 and the `Wed, 21-Oct-2015 07:28:00 GMT`
+/// format widely used by servers.
+fn parse_cookie_date(value: &str) -> error::Result<SystemTime> {
+  httpdate::parse_http_date(value)
+    .or_else(|_| httpdate::parse_http_date(&value.replace('-', " ")))
+    .map_err(|_| error::bad_cookie(format!("Bad cookie expires date: {}", value)))
+}
+
+/// The `SameSite` attribute of cookie.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SameSite {
+  Strict,
+  Lax,
+  None,
+}
+
+impl SameSite {
+  /// Parse case-insensitive `Strict`, `Lax` or `None`.
+  pub fn parse<S: AsRef<str>>(text: S) -> Option<Self> {
+    match &text.as_ref().trim().to_ascii_lowercase()[..] {
+      "strict" => Some(SameSite::Strict),
+      "lax" => Some(SameSite::Lax),
+      "none" => Some(SameSite::None),
+      _ => None,
+    }
+  }
+}
+
+impl fmt::Display for SameSite {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.write_str(match self {
+      SameSite::Strict => "Strict",
+      SameSite::Lax => "Lax",
+      SameSite::None => "None",
+    })
+  }
+}
+
 
 impl fmt::Debug for Cookie {
   #[inline]
@@ -198,8 +285,8 @@ impl CookieBuilder {
     self.cookie.clone()
   }
 
-  pub fn same_site<S: AsRef<str>>(&mut self, same_site: S) -> &mut Self {
-    self.cookie.same_site = Some(same_site.as_ref().to_owned());
+  pub fn same_site(&mut self, same_site: SameSite) -> &mut Self {
+    self.cookie.same_site = Some(same_site);
     self
   }
   pub fn name<S: AsRef<str>>(&mut self, name: S) -> &mut Self {
@@ -0,0 +1,378 @@
+use std::fmt;
+use std::io::{self, BufRead, Read, Write};
+use std::net::IpAddr;
+use std::path::Path;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde_json::{json, Value};
+use url::Url;
+
+use crate::error;
+use crate::types::{cookie, Cookie, PublicSuffixList, SameSite};
+
+/// Cookie store, keep cookies of responses and send them back with rfc6265 matching rules.
+/// `CookieStore` is cheap to clone, the clones share the same cookies.
+///
+/// # Examples
+///
+/// ```rust
+/// # use rttp_client::Client;
+/// # use rttp_client::types::CookieStore;
+/// let store = CookieStore::new();
+/// let client = Client::builder()
+///   .cookie_store(&store)
+///   .build();
+/// client.get().url("http://httpbin.org/cookies/set?name=value").emit();
+/// // the cookie is sent
+/// client.get().url("http://httpbin.org/cookies").emit();
+/// println!("{:?}", store.cookies());
+/// ```
+#[derive(Clone, Default)]
+pub struct CookieStore {
+  cookies: Arc<Mutex<Vec<Cookie>>>,
+  public_suffixes: Arc<Mutex<Option<Arc<PublicSuffixList>>>>,
+}
+
+impl CookieStore {
+  pub fn new() -> Self {
+    Default::default()
+  }
+
+  fn lock(&self) -> MutexGuard<'_, Vec<Cookie>> {
+    self.cookies.lock().unwrap_or_else(|e| e.into_inner())
+  }
+
+  /// Use this public suffix list instead of the embedded one to reject cookies set to public suffix.
+  pub fn public_suffix_list<L: AsRef<PublicSuffixList>>(&self, list: L) -> &Self {
+    let mut public_suffixes = self.public_suffixes.lock().unwrap_or_else(|e| e.into_inner());
+    *public_suffixes = Some(Arc::new(list.as_ref().clone()));
+    self
+  }
+
+  /// Load a newer public suffix list from file, see `PublicSuffixList::from_file`.
+  pub fn load_public_suffix_list<P: AsRef<Path>>(&self, path: P) -> error::Result<&Self> {
+    let list = PublicSuffixList::from_file(path)?;
+    Ok(self.public_suffix_list(list))
+  }
+
+  fn is_public_suffix(&self, domain: &str) -> bool {
+    let public_suffixes = self.public_suffixes.lock().unwrap_or_else(|e| e.into_inner()).clone();
+    match public_suffixes {
+      Some(list) => list.is_public_suffix(domain),
+      None => PublicSuffixList::embedded().is_public_suffix(domain),
+    }
+  }
+
+  /// Store a cookie received from url, return false if the cookie is rejected,
+  /// like the domain of cookie not match url host, or it's a public suffix.
+  /// The cookie without domain is host only.
+  /// A cookie with the same name, domain and path is replaced, an expired cookie remove it.
+  pub fn insert(&self, cookie: &Cookie, url: &Url) -> bool {
+    let host = match url.host_str() {
+      Some(host) => host.to_ascii_lowercase(),
+      None => return false,
+    };
+    let mut cookie = cookie.clone();
+    if cookie.name().is_empty() {
+      return false;
+    }
+
+    let domain = cookie.domain().as_ref()
+      .map(|d| d.trim_start_matches('.').to_ascii_lowercase())
+      .filter(|d| !d.is_empty());
+    // rfc6265 5.3, the cookie of public suffix is rejected, unless it's the request host
+    let domain = match domain {
+      Some(domain) if self.is_public_suffix(&domain) => {
+        if domain != host {
+          return false;
+        }
+        None
+      }
+      domain => domain,
+    };
+    match domain {
+      Some(domain) => {
+        if !domain_match(&host, &domain) {
+          return false;
+        }
+        cookie.domain_set(Some(domain)).host_only_set(false);
+      }
+      None => {
+        cookie.domain_set(Some(host)).host_only_set(true);
+      }
+    }
+    let path = cookie.path().clone().filter(|p| p.starts_with('/'));
+    cookie.path_set(Some(path.unwrap_or_else(|| default_path(url))));
+
+    let mut cookies = self.lock();
+    cookies.retain(|c| !c.same_identity(&cookie));
+    if cookie.is_expired() {
+      return true;
+    }
+    cookies.push(cookie);
+    true
+  }
+
+  /// Store cookies of a response from url.
+  pub fn store<'a, I: IntoIterator<Item=&'a Cookie>>(&self, cookies: I, url: &Url) {
+    for cookie in cookies {
+      self.insert(cookie, url);
+    }
+  }
+
+  /// Cookies should be sent to url, the cookie with longer path is first.
+  pub fn matches(&self, url: &Url) -> Vec<Cookie> {
+    let host = match url.host_str() {
+      Some(host) => host.to_ascii_lowercase(),
+      None => return vec![],
+    };
+    let secure = url.scheme() == "https";
+    let mut cookies = self.lock();
+    cookies.retain(|c| !c.is_expired());
+    let mut matches: Vec<Cookie> = cookies.iter()
+      .filter(|c| {
+        let domain = c.domain().as_deref().unwrap_or_default();
+        let domain_ok = if c.host_only() { host == domain } else { domain_match(&host, domain) };
+        domain_ok &&
+          path_match(url.path(), c.path().as_deref().unwrap_or("/")) &&
+          (secure || !c.secure())
+      })
+      .cloned()
+      .collect();
+    // stable sort, the cookies with the same path length keep creation order
+    matches.sort_by_key(|c| std::cmp::Reverse(c.path().as_ref().map_or(0, |p| p.len())));
+    matches
+  }
+
+  /// Value of `Cookie` header for url.
+  pub(crate) fn header_value(&self, url: &Url) -> Option<String> {
+    let cookies = self.matches(url);
+    if cookies.is_empty() {
+      return None;
+    }
+    let pairs: Vec<String> = cookies.iter()
+      .map(|c| format!("{}={}", c.name(), c.value()))
+      .collect();
+    Some(pairs.join("; "))
+  }
+
+  /// All cookies not expired.
+  pub fn cookies(&self) -> Vec<Cookie> {
+    let mut cookies = self.lock();
+    cookies.retain(|c| !c.is_expired());
+    cookies.clone()
+  }
+
+  /// Remove cookies by name, domain and path.
+  pub fn remove<S: AsRef<str>>(&self, name: S, domain: S, path: S) {
+    let domain = domain.as_ref().trim_start_matches('.').to_ascii_lowercase();
+    self.lock().retain(|c| {
+      !(c.name() == name.as_ref() &&
+        c.domain().as_deref() == Some(&domain[..]) &&
+        c.path().as_deref() == Some(path.as_ref()))
+    });
+  }
+
+  pub fn clear(&self) {
+    self.lock().clear();
+  }
+
+  pub fn len(&self) -> usize {
+    self.cookies().len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+}
+
+// persistence
+impl CookieStore {
+  /// Save cookies to the Netscape `cookies.txt` format, the format used by curl and wget.
+  /// This format can not keep `SameSite`, use json format if it's needed.
+  ///
+  /// # Examples
+  ///
+  /// ```rust,no_run
+  /// # use std::fs::File;
+  /// # use rttp_client::types::CookieStore;
+  /// let store = CookieStore::new();
+  /// store.save_netscape(File::create("cookies.txt").unwrap()).unwrap();
+  /// let store = CookieStore::new();
+  /// store.load_netscape(File::open("cookies.txt").unwrap()).unwrap();
+  /// ```
+  pub fn save_netscape<W: Write>(&self, mut writer: W) -> error::Result<()> {
+    let mut text = String::from("# Netscape HTTP Cookie File\n# Generated by rttp_client, edit at your own risk.\n\n");
+    for cookie in self.cookies() {
+      let domain = cookie.domain().clone().unwrap_or_default();
+      let domain = if cookie.host_only() { domain } else { format!(".{}", domain) };
+      text.push_str(&format!(
+        "{}{}\t{}\t{}\t{}\t{}\t{}\t{}\n",
+        if cookie.http_only() { "#HttpOnly_" } else { "" },
+        domain,
+        netscape_bool(!cookie.host_only()),
+        cookie.path().as_deref().unwrap_or("/"),
+        netscape_bool(cookie.secure()),
+        cookie.expires().map_or(0, unix_seconds),
+        cookie.name(),
+        cookie.value(),
+      ));
+    }
+    writer.write_all(text.as_bytes()).map_err(error::body)?;
+    writer.flush().map_err(error::body)
+  }
+
+  /// Load cookies from the Netscape `cookies.txt` format, the expired cookies are dropped.
+  pub fn load_netscape<R: Read>(&self, reader: R) -> error::Result<()> {
+    for line in io::BufReader::new(reader).lines() {
+      let line = line.map_err(error::decode)?;
+      let (line, http_only) = match line.strip_prefix("#HttpOnly_") {
+        Some(line) => (line, true),
+        None => (&line[..], false),
+      };
+      let line = line.trim_end_matches(['\r', '\n']);
+      if line.trim().is_empty() || line.starts_with('#') {
+        continue;
+      }
+      let fields: Vec<&str> = line.split('\t').collect();
+      if fields.len() < 6 {
+        return Err(error::bad_cookie(format!("Bad cookies.txt line: {}", line)));
+      }
+      let expires: u64 = fields[4].trim().parse()
+        .map_err(|_| error::bad_cookie(format!("Bad cookies.txt expires: {}", fields[4])))?;
+
+      let mut builder = Cookie::builder();
+      builder.domain(fields[0].trim_start_matches('.').to_ascii_lowercase())
+        .host_only(!fields[1].eq_ignore_ascii_case("true"))
+        .path(fields[2])
+        .secure(fields[3].eq_ignore_ascii_case("true"))
+        .name(fields[5])
+        .value(fields.get(6).copied().unwrap_or_default())
+        .http_only(http_only);
+      if expires > 0 {
+        builder.expires(from_unix_seconds(expires));
+      }
+      self.put(builder.build());
+    }
+    Ok(())
+  }
+
+  /// Save cookies to json, an array of cookie objects, keep all flags of cookies.
+  ///
+  /// ```json
+  /// [{"name": "id", "value": "1", "domain": "example.com", "path": "/", "expires": 1700000000,
+  ///   "host_only": true, "secure": false, "http_only": true, "same_site": "Lax"}]
+  /// ```
+  pub fn save_json<W: Write>(&self, writer: W) -> error::Result<()> {
+    let cookies: Vec<Value> = self.cookies().iter()
+      .map(|cookie| json!({
+        "name": cookie.name(),
+        "value": cookie.value(),
+        "domain": cookie.domain(),
+        "path": cookie.path(),
+        "expires": cookie.expires().map(unix_seconds),
+        "host_only": cookie.host_only(),
+        "secure": cookie.secure(),
+        "http_only": cookie.http_only(),
+        "same_site": cookie.same_site().map(|v| v.to_string()),
+      }))
+      .collect();
+    serde_json::to_writer_pretty(writer, &cookies).map_err(error::body)
+  }
+
+  /// Load cookies from json saved by `save_json`, the expired cookies are dropped.
+  pub fn load_json<R: Read>(&self, reader: R) -> error::Result<()> {
+    let value: Value = serde_json::from_reader(reader).map_err(error::decode)?;
+    let items = value.as_array().ok_or_else(|| error::bad_cookie("Cookies json is not an array"))?;
+    for item in items {
+      let text = |name: &str| item.get(name).and_then(Value::as_str);
+      let flag = |name: &str| item.get(name).and_then(Value::as_bool).unwrap_or_default();
+
+      let mut builder = Cookie::builder();
+      builder.name(text("name").ok_or_else(|| error::bad_cookie("Cookie not have name"))?)
+        .value(text("value").unwrap_or_default())
+        .host_only(flag("host_only"))
+        .secure(flag("secure"))
+        .http_only(flag("http_only"));
+      if let Some(domain) = text("domain") {
+        builder.domain(domain);
+      }
+      if let Some(path) = text("path") {
+        builder.path(path);
+      }
+      if let Some(same_site) = text("same_site").and_then(SameSite::parse) {
+        builder.same_site(same_site);
+      }
+      if let Some(expires) = item.get("expires").and_then(Value::as_u64) {
+        builder.expires(from_unix_seconds(expires));
+      }
+      self.put(builder.build());
+    }
+    Ok(())
+  }
+
+  /// Add a cookie already have domain and path, replace the same one.
+  fn put(&self, cookie: Cookie) {
+    if cookie.is_expired() {
+      return;
+    }
+    let mut cookies = self.lock();
+    cookies.retain(|c| !c.same_identity(&cookie));
+    cookies.push(cookie);
+  }
+}
+
+impl fmt::Debug for CookieStore {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.debug_list().entries(self.lock().iter()).finish()
+  }
+}
+
+impl AsRef<CookieStore> for CookieStore {
+  fn as_ref(&self) -> &CookieStore {
+    self
+  }
+}
+
+/// rfc6265 5.1.3, the host is the domain or a subdomain of it, ip address only match itself.
+fn domain_match(host: &str, domain: &str) -> bool {
+  if host == domain {
+    return true;
+  }
+  let is_ip = host.starts_with('[') || host.parse::<IpAddr>().is_ok();
+  !is_ip && host.ends_with(domain) && host[..host.len() - domain.len()].ends_with('.')
+}
+
+/// rfc6265 5.1.4, the path is the cookie path or a sub path of it.
+fn path_match(path: &str, cookie_path: &str) -> bool {
+  if path == cookie_path {
+    return true;
+  }
+  path.starts_with(cookie_path) &&
+    (cookie_path.ends_with('/') || path[cookie_path.len()..].starts_with('/'))
+}
+
+/// rfc6265 5.1.4, the directory of request path.
+fn default_path(url: &Url) -> String {
+  let path = url.path();
+  match path.rfind('/') {
+    Some(0) | None => "/".to_string(),
+    Some(i) => path[..i].to_string(),
+  }
+}
+
+fn netscape_bool(value: bool) -> &'static str {
+  if value { "TRUE" } else { "FALSE" }
+}
+
+fn unix_seconds(time: SystemTime) -> u64 {
+  time.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs())
+}
+
+/// The time of unix seconds, the time later than the latest expiry date is clamped to it.
+fn from_unix_seconds(seconds: u64) -> SystemTime {
+  UNIX_EPOCH.checked_add(Duration::from_secs(seconds))
+    .filter(|time| *time < cookie::max_expires())
+    .unwrap_or_else(cookie::max_expires)
+}
@@ -0,0 +1,290 @@
+use std::fmt;
+use std::sync::Mutex;
+
+use md5::Md5;
+use rand::Rng;
+use sha2::{Digest, Sha256};
+use url::Url;
+
+use crate::error;
+
+/// Hash algorithm of digest auth, rfc7616
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum DigestAlgorithm {
+  MD5,
+  MD5Sess,
+  SHA256,
+  SHA256Sess,
+}
+
+impl DigestAlgorithm {
+  fn parse(text: &str) -> Option<Self> {
+    match &text.to_ascii_uppercase()[..] {
+      "MD5" => Some(DigestAlgorithm::MD5),
+      "MD5-SESS" => Some(DigestAlgorithm::MD5Sess),
+      "SHA-256" => Some(DigestAlgorithm::SHA256),
+      "SHA-256-SESS" => Some(DigestAlgorithm::SHA256Sess),
+      _ => None,
+    }
+  }
+
+  fn is_sess(&self) -> bool {
+    *self == DigestAlgorithm::MD5Sess || *self == DigestAlgorithm::SHA256Sess
+  }
+
+  fn hash(&self, data: &[u8]) -> String {
+    let bytes = match self {
+      DigestAlgorithm::MD5 | DigestAlgorithm::MD5Sess => Md5::digest(data).to_vec(),
+      DigestAlgorithm::SHA256 | DigestAlgorithm::SHA256Sess => Sha256::digest(data).to_vec(),
+    };
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+  }
+}
+
+impl fmt::Display for DigestAlgorithm {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.write_str(match self {
+      DigestAlgorithm::MD5 => "MD5",
+      DigestAlgorithm::MD5Sess => "MD5-sess",
+      DigestAlgorithm::SHA256 => "SHA-256",
+      DigestAlgorithm::SHA256Sess => "SHA-256-sess",
+    })
+  }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Qop {
+  Auth,
+  AuthInt,
+}
+
+impl fmt::Display for Qop {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.write_str(match self {
+      Qop::Auth => "auth",
+      Qop::AuthInt => "auth-int",
+    })
+  }
+}
+
+/// A `Digest` challenge of `WWW-Authenticate` header.
+#[derive(Clone, Debug)]
+pub(crate) struct DigestChallenge {
+  realm: String,
+  nonce: String,
+  opaque: Option<String>,
+  algorithm: DigestAlgorithm,
+  qop: Option<Qop>,
+  stale: bool,
+}
+
+impl DigestChallenge {
+  /// Find the best supported digest challenge from `WWW-Authenticate` header values,
+  /// the `SHA-256` challenge is preferred when server offer more than one.
+  pub fn from_headers(values: &[&String]) -> Option<Self> {
+    let mut challenges: Vec<Self> = values.iter()
+      .flat_map(|value| parse_challenges(value))
+      .filter(|(scheme, _)| scheme.eq_ignore_ascii_case("digest"))
+      .filter_map(|(_, params)| Self::with_params(params))
+      .collect();
+    challenges.sort_by_key(|c| match c.algorithm {
+      DigestAlgorithm::SHA256 | DigestAlgorithm::SHA256Sess => 0,
+      _ => 1,
+    });
+    challenges.into_iter().next()
+  }
+
+  fn with_params(params: Vec<(String, String)>) -> Option<Self> {
+    let param = |name: &str| params.iter()
+      .find(|(n, _)| n.eq_ignore_ascii_case(name))
+      .map(|(_, v)| v.clone());
+    let algorithm = match param("algorithm") {
+      Some(algorithm) => DigestAlgorithm::parse(&algorithm)?,
+      None => DigestAlgorithm::MD5,
+    };
+    // prefer `auth`, `auth-int` only if it's the only one offered
+    let qop = match param("qop") {
+      Some(qop) => {
+        let qops: Vec<String> = qop.split(',').map(|v| v.trim().to_ascii_lowercase()).collect();
+        if qops.iter().any(|v| v == "auth") {
+          Some(Qop::Auth)
+        } else if qops.iter().any(|v| v == "auth-int") {
+          Some(Qop::AuthInt)
+        } else {
+          return None;
+        }
+      }
+      None => None,
+    };
+    Some(Self {
+      realm: param("realm").unwrap_or_default(),
+      nonce: param("nonce")?,
+      opaque: param("opaque"),
+      algorithm,
+      qop,
+      stale: param("stale").is_some_and(|v| v.eq_ignore_ascii_case("true")),
+    })
+  }
+
+  pub fn nonce(&self) -> &String { &self.nonce }
+  pub fn stale(&self) -> bool { self.stale }
+
+  /// Value of `Authorization` header answer this challenge, the body is `None` if it's
+  /// not in memory, it can't be hashed for `auth-int`.
+  fn authorization(&self, username: &str, password: &str, method: &str, uri: &str,
+                   body: Option<&[u8]>, nc: u32) -> error::Result<String> {
+    if self.qop == Some(Qop::AuthInt) && body.is_none() {
+      return Err(error::builder_with_message("Digest auth-int can't hash the body read while sending"));
+    }
+    let algorithm = self.algorithm;
+    let cnonce = cnonce();
+    let mut ha1 = algorithm.hash(format!("{}:{}:{}", username, self.realm, password).as_bytes());
+    if algorithm.is_sess() {
+      ha1 = algorithm.hash(format!("{}:{}:{}", ha1, self.nonce, cnonce).as_bytes());
+    }
+    let ha2 = match self.qop {
+      Some(Qop::AuthInt) => {
+        let body = algorithm.hash(body.unwrap_or_default());
+        algorithm.hash(format!("{}:{}:{}", method, uri, body).as_bytes())
+      }
+      _ => algorithm.hash(format!("{}:{}", method, uri).as_bytes()),
+    };
+    let nc = format!("{:08x}", nc);
+    let response = match self.qop {
+      Some(qop) => algorithm.hash(format!("{}:{}:{}:{}:{}:{}", ha1, self.nonce, nc, cnonce, qop, ha2).as_bytes()),
+      None => algorithm.hash(format!("{}:{}:{}", ha1, self.nonce, ha2).as_bytes()),
+    };
+
+    let mut value = format!(
+      r#"Digest username="{}", realm="{}", nonce="{}", uri="{}", algorithm={}, response="{}""#,
+      quote(username), quote(&self.realm), quote(&self.nonce), quote(uri), algorithm, response
+    );
+    if let Some(opaque) = &self.opaque {
+      value.push_str(&format!(r#", opaque="{}""#, quote(opaque)));
+    }
+    if let Some(qop) = self.qop {
+      value.push_str(&format!(r#", qop={}, nc={}, cnonce="{}""#, qop, nc, cnonce));
+    }
+    Ok(value)
+  }
+}
+
+struct DigestEntry {
+  origin: String,
+  challenge: DigestChallenge,
+  nc: u32,
+}
+
+/// Digest challenges received by a client, a request to the same origin answer the
+/// cached challenge directly, the nonce count increase for each use.
+#[derive(Default)]
+pub(crate) struct DigestCache {
+  entries: Mutex<Vec<DigestEntry>>,
+}
+
+impl DigestCache {
+  /// Save the challenge of the url origin, replace the old one of the same realm.
+  pub fn save(&self, url: &Url, challenge: DigestChallenge) {
+    let origin = url.origin().ascii_serialization();
+    let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+    entries.retain(|e| e.origin != origin || e.challenge.realm != challenge.realm);
+    entries.push(DigestEntry { origin, challenge, nc: 0 });
+  }
+
+  /// The nonce cached for the url origin.
+  pub fn nonce(&self, url: &Url) -> Option<String> {
+    let origin = url.origin().ascii_serialization();
+    let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+    entries.iter().rfind(|e| e.origin == origin).map(|e| e.challenge.nonce.clone())
+  }
+
+  /// `Authorization` header value use the latest challenge of the url origin.
+  pub fn authorization(&self, url: &Url, username: &str, password: &str, method: &str,
+                       uri: &str, body: Option<&[u8]>) -> error::Result<Option<String>> {
+    let origin = url.origin().ascii_serialization();
+    let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+    let entry = match entries.iter_mut().rfind(|e| e.origin == origin) {
+      Some(entry) => entry,
+      None => return Ok(None),
+    };
+    let authorization = entry.challenge.authorization(username, password, method, uri, body, entry.nc + 1)?;
+    entry.nc += 1;
+    Ok(Some(authorization))
+  }
+}
+
+impl fmt::Debug for DigestCache {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let size = self.entries.lock().map_or(0, |e| e.len());
+    f.debug_struct("DigestCache").field("size", &size).finish()
+  }
+}
+
+fn cnonce() -> String {
+  let bytes: [u8; 16] = rand::thread_rng().gen();
+  bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn quote(text: &str) -> String {
+  text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Parse challenges of `WWW-Authenticate` header, `Basic realm="a", Digest realm="b", nonce="c"`
+/// is `[("Basic", [("realm", "a")]), ("Digest", [("realm", "b"), ("nonce", "c")])]`.
+fn parse_challenges(value: &str) -> Vec<(String, Vec<(String, String)>)> {
+  let chars: Vec<char> = value.chars().collect();
+  let mut challenges: Vec<(String, Vec<(String, String)>)> = vec![];
+  let mut i = 0;
+  let is_token = |c: char| !c.is_whitespace() && c != ',' && c != '=' && c != '"';
+  loop {
+    while i < chars.len() && (chars[i].is_whitespace() || chars[i] == ',') {
+      i += 1;
+    }
+    if i >= chars.len() {
+      return challenges;
+    }
+    let start = i;
+    while i < chars.len() && is_token(chars[i]) {
+      i += 1;
+    }
+    let token: String = chars[start..i].iter().collect();
+    let mut j = i;
+    while j < chars.len() && chars[j].is_whitespace() {
+      j += 1;
+    }
+    let is_param = j < chars.len() && chars[j] == '=' && !challenges.is_empty();
+    if !is_param {
+      // a new scheme, or a token68 of the previous scheme
+      if token.is_empty() {
+        i += 1;
+      } else {
+        challenges.push((token, vec![]));
+      }
+      continue;
+    }
+    i = j + 1;
+    while i < chars.len() && chars[i].is_whitespace() {
+      i += 1;
+    }
+    let mut param = String::new();
+    if i < chars.len() && chars[i] == '"' {
+      i += 1;
+      while i < chars.len() && chars[i] != '"' {
+        if chars[i] == '\\' && i + 1 < chars.len() {
+          i += 1;
+        }
+        param.push(chars[i]);
+        i += 1;
+      }
+      i += 1;
+    } else {
+      while i < chars.len() && chars[i] != ',' && !chars[i].is_whitespace() {
+        param.push(chars[i]);
+        i += 1;
+      }
+    }
+    if let Some((_, params)) = challenges.last_mut() {
+      params.push((token, param));
+    }
+  }
+}
@@ -1,4 +1,9 @@
 use std::collections::HashMap;
+use std::time::SystemTime;
+
+use mime::Mime;
+
+use crate::types::{Accept, CacheControl, ContentRange, ETag, Link, RetryAfter};
 
 #[derive(Clone, Debug)]
 pub struct Header {
@@ -19,12 +24,6 @@ impl Header {
     }
   }
 
-  pub(crate) fn replace(&mut self, header: Header) -> &mut Self {
-    self.name = header.name().clone();
-    self.value = header.value().clone();
-    self
-  }
-
   pub fn name(&self) -> &String {
     &self.name
   }
@@ -42,6 +41,80 @@ impl Header {
   }
 }
 
+// typed headers
+impl Header {
+  pub fn content_type(mime: &Mime) -> Self {
+    Self::new("Content-Type", mime)
+  }
+
+  pub fn content_length(length: u64) -> Self {
+    Self::new("Content-Length", length.to_string())
+  }
+
+  pub fn cache_control<C: AsRef<CacheControl>>(cache_control: C) -> Self {
+    Self::new("Cache-Control", cache_control.as_ref().to_string())
+  }
+
+  pub fn etag<E: AsRef<ETag>>(etag: E) -> Self {
+    Self::new("ETag", etag.as_ref().to_string())
+  }
+
+  /// `If-None-Match` of the tags, `*` if tags is empty.
+  pub fn if_none_match(etags: &[ETag]) -> Self {
+    if etags.is_empty() {
+      return Self::new("If-None-Match", "*");
+    }
+    let etags: Vec<String> = etags.iter().map(|etag| etag.to_string()).collect();
+    Self::new("If-None-Match", etags.join(", "))
+  }
+
+  pub fn last_modified(time: SystemTime) -> Self {
+    Self::new("Last-Modified", httpdate::fmt_http_date(time))
+  }
+
+  pub fn if_modified_since(time: SystemTime) -> Self {
+    Self::new("If-Modified-Since", httpdate::fmt_http_date(time))
+  }
+
+  /// `Range` of bytes from first to last position, to the end if last is `None`.
+  pub fn range(first: u64, last: Option<u64>) -> Self {
+    match last {
+      Some(last) => Self::new("Range", format!("bytes={}-{}", first, last)),
+      None => Self::new("Range", format!("bytes={}-", first)),
+    }
+  }
+
+  pub fn content_range(content_range: ContentRange) -> Self {
+    Self::new("Content-Range", content_range.to_string())
+  }
+
+  pub fn retry_after(retry_after: RetryAfter) -> Self {
+    Self::new("Retry-After", retry_after.to_string())
+  }
+
+  pub fn link(links: &[Link]) -> Self {
+    let links: Vec<String> = links.iter().map(|link| link.to_string()).collect();
+    Self::new("Link", links.join(", "))
+  }
+
+  pub fn accept<A: AsRef<Accept>>(accept: A) -> Self {
+    Self::new("Accept", accept.as_ref().to_string())
+  }
+}
+
+/// Header name and method are `token` of RFC 7230, visible ascii characters except delimiters.
+pub(crate) fn is_token(text: &str) -> bool {
+  !text.is_empty() && text.bytes().all(|b| {
+    b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b)
+  })
+}
+
+/// Header value is `field-content` of RFC 7230, control characters except tab are not allowed,
+/// so a value can not break the header line.
+pub(crate) fn is_field_value(text: &str) -> bool {
+  text.bytes().all(|b| b == b'\t' || (b >= 0x20 && b != 0x7f))
+}
+
 impl<'a> IntoHeader for &'a str {
   fn into_headers(&self) -> Vec<Header> {
     self.split("\n").collect::<Vec<&str>>()
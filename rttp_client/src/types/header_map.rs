@@ -0,0 +1,253 @@
+use std::iter::FromIterator;
+use std::slice;
+use std::str::FromStr;
+use std::time::SystemTime;
+
+use mime::Mime;
+
+use crate::types::{Accept, CacheControl, ContentRange, ETag, Header, IntoHeader, Link, RetryAfter};
+
+/// Multi-value headers, the name is case-insensitive.
+/// The order of insertion and the casing of names are preserved.
+///
+/// # Examples
+///
+/// ```rust
+/// # use rttp_client::types::HeaderMap;
+/// let mut headers = HeaderMap::new();
+/// headers.append("Accept", "text/html");
+/// headers.append("accept", "application/json");
+/// assert_eq!(vec!["text/html", "application/json"], headers.get_all("ACCEPT"));
+///
+/// headers.insert("Accept", "*/*");
+/// assert_eq!(Some(&"*/*".to_string()), headers.get("accept"));
+/// assert_eq!(1, headers.len());
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct HeaderMap {
+  headers: Vec<Header>,
+}
+
+impl HeaderMap {
+  pub fn new() -> Self {
+    Default::default()
+  }
+
+  /// Count of header lines, a name with multiple values is counted multiple times.
+  pub fn len(&self) -> usize {
+    self.headers.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.headers.is_empty()
+  }
+
+  pub fn contains<N: AsRef<str>>(&self, name: N) -> bool {
+    self.header(name).is_some()
+  }
+
+  /// The first header of name.
+  pub fn header<N: AsRef<str>>(&self, name: N) -> Option<&Header> {
+    self.headers.iter().find(|h| h.name().eq_ignore_ascii_case(name.as_ref()))
+  }
+
+  /// All headers of name, in order of insertion.
+  pub fn headers<N: AsRef<str>>(&self, name: N) -> Vec<&Header> {
+    self.headers.iter()
+      .filter(|h| h.name().eq_ignore_ascii_case(name.as_ref()))
+      .collect()
+  }
+
+  /// The first value of name.
+  pub fn get<N: AsRef<str>>(&self, name: N) -> Option<&String> {
+    self.header(name).map(|h| h.value())
+  }
+
+  /// All values of name, in order of insertion.
+  pub fn get_all<N: AsRef<str>>(&self, name: N) -> Vec<&String> {
+    self.headers.iter()
+      .filter(|h| h.name().eq_ignore_ascii_case(name.as_ref()))
+      .map(|h| h.value())
+      .collect()
+  }
+
+  /// Set the header, replace all values of the same name. The replaced header keep its position,
+  /// returns the first value replaced.
+  pub fn insert<N: AsRef<str>, V: AsRef<str>>(&mut self, name: N, value: V) -> Option<String> {
+    let header = Header::new(name, value);
+    let mut replaced = None;
+    let mut headers = Vec::with_capacity(self.headers.len() + 1);
+    for h in self.headers.drain(..) {
+      if !h.name().eq_ignore_ascii_case(header.name()) {
+        headers.push(h);
+        continue;
+      }
+      if replaced.is_none() {
+        replaced = Some(h.value().clone());
+        headers.push(header.clone());
+      }
+    }
+    if replaced.is_none() {
+      headers.push(header);
+    }
+    self.headers = headers;
+    replaced
+  }
+
+  /// Insert a batch of headers, the values of the same name in this batch are all kept.
+  pub(crate) fn insert_all(&mut self, headers: Vec<Header>) {
+    let mut names: Vec<&String> = vec![];
+    for header in &headers {
+      if names.iter().any(|name| name.eq_ignore_ascii_case(header.name())) {
+        self.append(header.name(), header.value());
+        continue;
+      }
+      names.push(header.name());
+      self.insert(header.name(), header.value());
+    }
+  }
+
+  /// Add the header after the existing values of the same name.
+  pub fn append<N: AsRef<str>, V: AsRef<str>>(&mut self, name: N, value: V) -> &mut Self {
+    self.headers.push(Header::new(name, value));
+    self
+  }
+
+  /// Remove all values of name, returns the removed values.
+  pub fn remove<N: AsRef<str>>(&mut self, name: N) -> Vec<String> {
+    let mut removed = vec![];
+    self.headers.retain(|h| {
+      if h.name().eq_ignore_ascii_case(name.as_ref()) {
+        removed.push(h.value().clone());
+        return false;
+      }
+      true
+    });
+    removed
+  }
+
+  pub fn clear(&mut self) {
+    self.headers.clear();
+  }
+
+  pub fn iter(&self) -> slice::Iter<'_, Header> {
+    self.headers.iter()
+  }
+
+  pub(crate) fn retain<F: FnMut(&Header) -> bool>(&mut self, f: F) {
+    self.headers.retain(f);
+  }
+}
+
+// typed headers
+impl HeaderMap {
+  pub fn content_type(&self) -> Option<Mime> {
+    Mime::from_str(self.get("content-type")?).ok()
+  }
+
+  pub fn content_length(&self) -> Option<u64> {
+    self.get("content-length")?.trim().parse().ok()
+  }
+
+  /// Directives of all `Cache-Control` headers.
+  pub fn cache_control(&self) -> Option<CacheControl> {
+    let values = self.get_all("cache-control");
+    if values.is_empty() {
+      return None;
+    }
+    Some(CacheControl::parse(values.iter().map(|v| &v[..]).collect::<Vec<&str>>().join(", ")))
+  }
+
+  pub fn etag(&self) -> Option<ETag> {
+    ETag::parse(self.get("etag")?)
+  }
+
+  /// Tags of all `If-None-Match` headers, `*` is not a tag and is skipped.
+  pub fn if_none_match(&self) -> Vec<ETag> {
+    self.get_all("if-none-match").iter().flat_map(ETag::parse_list).collect()
+  }
+
+  pub fn last_modified(&self) -> Option<SystemTime> {
+    httpdate::parse_http_date(self.get("last-modified")?).ok()
+  }
+
+  pub fn if_modified_since(&self) -> Option<SystemTime> {
+    httpdate::parse_http_date(self.get("if-modified-since")?).ok()
+  }
+
+  pub fn content_range(&self) -> Option<ContentRange> {
+    ContentRange::parse(self.get("content-range")?)
+  }
+
+  /// Whether `Accept-Ranges` allows byte ranges.
+  pub fn accept_ranges(&self) -> bool {
+    self.get_all("accept-ranges").iter()
+      .flat_map(|value| value.split(','))
+      .any(|unit| unit.trim().eq_ignore_ascii_case("bytes"))
+  }
+
+  pub fn retry_after(&self) -> Option<RetryAfter> {
+    RetryAfter::parse(self.get("retry-after")?)
+  }
+
+  /// Links of all `Link` headers.
+  pub fn links(&self) -> Vec<Link> {
+    self.get_all("link").iter().flat_map(Link::parse_list).collect()
+  }
+
+  /// The first link have the relation type, like `next`.
+  pub fn link<S: AsRef<str>>(&self, rel: S) -> Option<Link> {
+    self.links().into_iter().find(|link| link.has_rel(rel.as_ref()))
+  }
+
+  /// Media ranges of all `Accept` headers.
+  pub fn accept(&self) -> Option<Accept> {
+    let values = self.get_all("accept");
+    if values.is_empty() {
+      return None;
+    }
+    Some(Accept::parse(values.iter().map(|v| &v[..]).collect::<Vec<&str>>().join(", ")))
+  }
+}
+
+impl<'a> IntoIterator for &'a HeaderMap {
+  type Item = &'a Header;
+  type IntoIter = slice::Iter<'a, Header>;
+
+  fn into_iter(self) -> Self::IntoIter {
+    self.headers.iter()
+  }
+}
+
+impl IntoIterator for HeaderMap {
+  type Item = Header;
+  type IntoIter = std::vec::IntoIter<Header>;
+
+  fn into_iter(self) -> Self::IntoIter {
+    self.headers.into_iter()
+  }
+}
+
+impl FromIterator<Header> for HeaderMap {
+  fn from_iter<T: IntoIterator<Item=Header>>(iter: T) -> Self {
+    Self { headers: iter.into_iter().collect() }
+  }
+}
+
+impl Extend<Header> for HeaderMap {
+  fn extend<T: IntoIterator<Item=Header>>(&mut self, iter: T) {
+    self.headers.extend(iter);
+  }
+}
+
+impl IntoHeader for HeaderMap {
+  fn into_headers(&self) -> Vec<Header> {
+    self.headers.clone()
+  }
+}
+
+impl AsRef<HeaderMap> for HeaderMap {
+  fn as_ref(&self) -> &HeaderMap {
+    self
+  }
+}
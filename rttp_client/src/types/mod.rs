@@ -1,18 +1,32 @@
 
+pub use self::auth::*;
+pub(crate) use self::digest::{DigestCache, DigestChallenge};
 pub use self::status::*;
 pub use self::url::*;
 pub use self::para::*;
 pub use self::header::*;
+pub use self::header_map::HeaderMap;
+pub use self::typed_header::{Accept, CacheControl, ContentRange, ETag, Link, RetryAfter};
 pub use self::form_data::*;
 pub use self::proxy::*;
-pub use self::cookie::Cookie;
+pub use self::redirect::*;
+pub use self::cookie::{Cookie, SameSite};
+pub use self::cookie_store::CookieStore;
+pub use self::public_suffix::PublicSuffixList;
 
+mod auth;
+mod digest;
 mod status;
 mod url;
 mod para;
 mod header;
+mod header_map;
+mod typed_header;
 mod form_data;
 mod proxy;
+mod redirect;
 mod cookie;
+mod cookie_store;
+mod public_suffix;
 
 mod type_helper;
@@ -0,0 +1,108 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use url::Url;
+
+use crate::error;
+
+/// The list embedded in this crate, from https://publicsuffix.org/list/public_suffix_list.dat
+const EMBEDDED_LIST: &str = include_str!("public_suffix_list.dat");
+
+/// Public suffix list, the domains under which anyone can register names, like `com` or `co.uk`.
+/// A cookie can not set to a public suffix, otherwise it will be sent to all sites under it.
+///
+/// # Examples
+///
+/// ```rust
+/// # use rttp_client::types::PublicSuffixList;
+/// let list = PublicSuffixList::embedded();
+/// assert!(list.is_public_suffix("co.uk"));
+/// assert_eq!("co.uk", list.public_suffix("www.example.co.uk"));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct PublicSuffixList {
+  rules: HashSet<String>,
+  wildcards: HashSet<String>,
+  exceptions: HashSet<String>,
+}
+
+impl PublicSuffixList {
+  /// Parse list of the `public_suffix_list.dat` format.
+  pub fn parse<S: AsRef<str>>(text: S) -> Self {
+    let mut list = Self::default();
+    for line in text.as_ref().lines() {
+      let rule = match line.split_whitespace().next() {
+        Some(rule) if !rule.starts_with("//") => rule,
+        _ => continue,
+      };
+      if let Some(rule) = rule.strip_prefix('!') {
+        list.exceptions.insert(to_ascii(rule));
+      } else if let Some(rule) = rule.strip_prefix("*.") {
+        list.wildcards.insert(to_ascii(rule));
+      } else {
+        list.rules.insert(to_ascii(rule));
+      }
+    }
+    list
+  }
+
+  /// Load a newer list from file, download from https://publicsuffix.org/list/public_suffix_list.dat
+  pub fn from_file<P: AsRef<Path>>(path: P) -> error::Result<Self> {
+    let text = fs::read_to_string(path).map_err(error::decode)?;
+    Ok(Self::parse(text))
+  }
+
+  /// The list embedded in this crate.
+  pub fn embedded() -> &'static PublicSuffixList {
+    static EMBEDDED: OnceLock<PublicSuffixList> = OnceLock::new();
+    EMBEDDED.get_or_init(|| Self::parse(EMBEDDED_LIST))
+  }
+
+  /// The public suffix of domain, the last label if no rule matched.
+  pub fn public_suffix<S: AsRef<str>>(&self, domain: S) -> String {
+    let domain = domain.as_ref().trim_end_matches('.').to_ascii_lowercase();
+    let labels: Vec<&str> = domain.split('.').collect();
+
+    // exception rule take precedence, the suffix is the exception without first label
+    for i in 0..labels.len() {
+      if self.exceptions.contains(&labels[i..].join(".")) {
+        return labels[i + 1..].join(".");
+      }
+    }
+    // the first matched from left is the longest
+    for i in 0..labels.len() {
+      let suffix = labels[i..].join(".");
+      if self.rules.contains(&suffix) {
+        return suffix;
+      }
+      if i + 1 < labels.len() && self.wildcards.contains(&labels[i + 1..].join(".")) {
+        return suffix;
+      }
+    }
+    labels.last().map_or(String::new(), |v| v.to_string())
+  }
+
+  /// The domain itself is a public suffix.
+  pub fn is_public_suffix<S: AsRef<str>>(&self, domain: S) -> bool {
+    let domain = domain.as_ref().trim_end_matches('.').to_ascii_lowercase();
+    self.public_suffix(&domain) == domain
+  }
+}
+
+impl AsRef<PublicSuffixList> for PublicSuffixList {
+  fn as_ref(&self) -> &PublicSuffixList {
+    self
+  }
+}
+
+/// Rules of internationalized domain are written in unicode, the host of url is punycode.
+fn to_ascii(rule: &str) -> String {
+  if rule.is_ascii() {
+    return rule.to_ascii_lowercase();
+  }
+  Url::parse(&format!("http://{}/", rule)).ok()
+    .and_then(|url| url.host_str().map(|v| v.to_string()))
+    .unwrap_or_else(|| rule.to_lowercase())
+}
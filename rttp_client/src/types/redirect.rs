@@ -0,0 +1,147 @@
+use std::fmt;
+use std::sync::Arc;
+
+use url::Url;
+
+type PolicyFn = dyn Fn(&Attempt) -> Action + Send + Sync;
+
+/// Redirect policy, decide whether follow the `Location` of a redirect response.
+///
+/// # Examples
+///
+/// ```rust
+/// # use rttp_client::Config;
+/// # use rttp_client::types::RedirectPolicy;
+/// // follow at most 5 redirects, and only to the same origin
+/// let config = Config::builder()
+///   .redirect(RedirectPolicy::limited(5).same_origin(true))
+///   .build();
+///
+/// // stop when redirect to login page
+/// let policy = RedirectPolicy::custom(|attempt| {
+///   if attempt.url().path() == "/login" {
+///     attempt.stop()
+///   } else {
+///     attempt.follow()
+///   }
+/// });
+/// ```
+#[derive(Clone)]
+pub struct RedirectPolicy {
+  max: u32,
+  same_origin: bool,
+  custom: Option<Arc<PolicyFn>>,
+}
+
+impl RedirectPolicy {
+  /// Follow at most `max` redirects, more redirects will be a `too many redirects` error.
+  pub fn limited(max: u32) -> Self {
+    Self { max, same_origin: false, custom: None }
+  }
+
+  /// Never follow redirect, the redirect response will be returned.
+  pub fn none() -> Self {
+    Self::limited(0)
+  }
+
+  /// Decide every redirect by closure, at most 10 redirects will be followed,
+  /// use `max` to change it.
+  pub fn custom<F>(policy: F) -> Self
+    where
+      F: Fn(&Attempt) -> Action + Send + Sync + 'static,
+  {
+    Self { max: 10, same_origin: false, custom: Some(Arc::new(policy)) }
+  }
+
+  /// Set max redirects can be followed.
+  pub fn max(mut self, max: u32) -> Self {
+    self.max = max;
+    self
+  }
+
+  /// Only follow redirect to the same origin (scheme, host and port) of the first request,
+  /// a redirect to other origin will be returned.
+  pub fn same_origin(mut self, same_origin: bool) -> Self {
+    self.same_origin = same_origin;
+    self
+  }
+
+  pub fn max_redirect(&self) -> u32 { self.max }
+  pub fn is_same_origin(&self) -> bool { self.same_origin }
+
+  /// Whether follow the redirect attempt.
+  pub(crate) fn check(&self, attempt: &Attempt) -> Action {
+    if self.same_origin {
+      let first = attempt.previous.first().unwrap_or(attempt.url);
+      if first.origin() != attempt.url.origin() {
+        return Action::Stop;
+      }
+    }
+    match &self.custom {
+      Some(custom) => custom(attempt),
+      None => Action::Follow,
+    }
+  }
+}
+
+impl Default for RedirectPolicy {
+  fn default() -> Self {
+    Self::none()
+  }
+}
+
+impl fmt::Debug for RedirectPolicy {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.debug_struct("RedirectPolicy")
+      .field("max", &self.max)
+      .field("same_origin", &self.same_origin)
+      .field("custom", &self.custom.is_some())
+      .finish()
+  }
+}
+
+impl AsRef<RedirectPolicy> for RedirectPolicy {
+  fn as_ref(&self) -> &RedirectPolicy {
+    self
+  }
+}
+
+/// A redirect going to be followed.
+#[derive(Debug)]
+pub struct Attempt<'a> {
+  status: u32,
+  url: &'a Url,
+  previous: &'a [Url],
+}
+
+impl<'a> Attempt<'a> {
+  pub(crate) fn new(status: u32, url: &'a Url, previous: &'a [Url]) -> Self {
+    Self { status, url, previous }
+  }
+
+  /// Status code of the redirect response.
+  pub fn status(&self) -> u32 { self.status }
+
+  /// The url redirect to.
+  pub fn url(&self) -> &Url { self.url }
+
+  /// Urls already requested, the first is the original request url.
+  pub fn previous(&self) -> &[Url] { self.previous }
+
+  pub fn follow(&self) -> Action {
+    Action::Follow
+  }
+
+  pub fn stop(&self) -> Action {
+    Action::Stop
+  }
+}
+
+/// What to do with a redirect attempt.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Action {
+  /// Follow the redirect.
+  Follow,
+  /// Stop and return the redirect response.
+  Stop,
+}
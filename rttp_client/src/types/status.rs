@@ -23,7 +23,7 @@ use std::str::FromStr;
 /// An HTTP status code (`status-code` in RFC 7230 et al.).
 ///
 /// This type contains constants for all common status codes.
-/// It allows status codes in the range [100, 599].
+/// It allows status codes in the range [100, 999].
 ///
 /// IANA maintain the [Hypertext Transfer Protocol (HTTP) Status Code
 /// Registry](http://www.iana.org/assignments/http-status-codes/http-status-codes.xhtml) which is
@@ -45,7 +45,7 @@ pub struct StatusCode(u16);
 /// A possible error value when converting a `StatusCode` from a `u16` or `&str`
 ///
 /// This error indicates that the supplied input was not a valid number, was less
-/// than 100, or was greater than 599.
+/// than 100, or was greater than 999.
 pub struct InvalidStatusCode {
   _priv: (),
 }
@@ -54,7 +54,7 @@ impl StatusCode {
   /// Converts a u16 to a status code.
   ///
   /// The function validates the correctness of the supplied u16. It must be
-  /// greater or equal to 100 but less than 600.
+  /// greater or equal to 100 but less than 1000.
   ///
   /// # Example
   ///
@@ -69,7 +69,7 @@ impl StatusCode {
   /// ```
   #[inline]
   pub fn from_u16(src: u16) -> Result<StatusCode, InvalidStatusCode> {
-    if src < 100 || src >= 600 {
+    if !(100..1000).contains(&src) {
       return Err(InvalidStatusCode::new());
     }
 
@@ -86,7 +86,7 @@ impl StatusCode {
     let b = src[1].wrapping_sub(b'0') as u16;
     let c = src[2].wrapping_sub(b'0') as u16;
 
-    if a == 0 || a > 5 || b > 9 || c > 9 {
+    if a == 0 || a > 9 || b > 9 || c > 9 {
       return Err(InvalidStatusCode::new());
     }
 
@@ -533,7 +533,7 @@ impl Error for InvalidStatusCode {
 
 macro_rules! status_code_strs {
   ($($num:expr,)+) => {
-    const CODES_AS_STR: [&'static str; 500] = [ $( stringify!($num), )+ ];
+    const CODES_AS_STR: [&'static str; 900] = [ $( stringify!($num), )+ ];
   }
 }
 
@@ -567,4 +567,28 @@ status_code_strs!(
   540, 541, 542, 543, 544, 545, 546, 547, 548, 549, 550, 551, 552, 553, 554, 555, 556, 557, 558, 559,
   560, 561, 562, 563, 564, 565, 566, 567, 568, 569, 570, 571, 572, 573, 574, 575, 576, 577, 578, 579,
   580, 581, 582, 583, 584, 585, 586, 587, 588, 589, 590, 591, 592, 593, 594, 595, 596, 597, 598, 599,
+
+  600, 601, 602, 603, 604, 605, 606, 607, 608, 609, 610, 611, 612, 613, 614, 615, 616, 617, 618, 619,
+  620, 621, 622, 623, 624, 625, 626, 627, 628, 629, 630, 631, 632, 633, 634, 635, 636, 637, 638, 639,
+  640, 641, 642, 643, 644, 645, 646, 647, 648, 649, 650, 651, 652, 653, 654, 655, 656, 657, 658, 659,
+  660, 661, 662, 663, 664, 665, 666, 667, 668, 669, 670, 671, 672, 673, 674, 675, 676, 677, 678, 679,
+  680, 681, 682, 683, 684, 685, 686, 687, 688, 689, 690, 691, 692, 693, 694, 695, 696, 697, 698, 699,
+
+  700, 701, 702, 703, 704, 705, 706, 707, 708, 709, 710, 711, 712, 713, 714, 715, 716, 717, 718, 719,
+  720, 721, 722, 723, 724, 725, 726, 727, 728, 729, 730, 731, 732, 733, 734, 735, 736, 737, 738, 739,
+  740, 741, 742, 743, 744, 745, 746, 747, 748, 749, 750, 751, 752, 753, 754, 755, 756, 757, 758, 759,
+  760, 761, 762, 763, 764, 765, 766, 767, 768, 769, 770, 771, 772, 773, 774, 775, 776, 777, 778, 779,
+  780, 781, 782, 783, 784, 785, 786, 787, 788, 789, 790, 791, 792, 793, 794, 795, 796, 797, 798, 799,
+
+  800, 801, 802, 803, 804, 805, 806, 807, 808, 809, 810, 811, 812, 813, 814, 815, 816, 817, 818, 819,
+  820, 821, 822, 823, 824, 825, 826, 827, 828, 829, 830, 831, 832, 833, 834, 835, 836, 837, 838, 839,
+  840, 841, 842, 843, 844, 845, 846, 847, 848, 849, 850, 851, 852, 853, 854, 855, 856, 857, 858, 859,
+  860, 861, 862, 863, 864, 865, 866, 867, 868, 869, 870, 871, 872, 873, 874, 875, 876, 877, 878, 879,
+  880, 881, 882, 883, 884, 885, 886, 887, 888, 889, 890, 891, 892, 893, 894, 895, 896, 897, 898, 899,
+
+  900, 901, 902, 903, 904, 905, 906, 907, 908, 909, 910, 911, 912, 913, 914, 915, 916, 917, 918, 919,
+  920, 921, 922, 923, 924, 925, 926, 927, 928, 929, 930, 931, 932, 933, 934, 935, 936, 937, 938, 939,
+  940, 941, 942, 943, 944, 945, 946, 947, 948, 949, 950, 951, 952, 953, 954, 955, 956, 957, 958, 959,
+  960, 961, 962, 963, 964, 965, 966, 967, 968, 969, 970, 971, 972, 973, 974, 975, 976, 977, 978, 979,
+  980, 981, 982, 983, 984, 985, 986, 987, 988, 989, 990, 991, 992, 993, 994, 995, 996, 997, 998, 999,
 );
@@ -0,0 +1,560 @@
+use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime};
+
+use mime::Mime;
+
+use crate::types::is_token;
+
+/// Directives of `Cache-Control` header.
+///
+/// # Examples
+///
+/// ```rust
+/// # use std::time::Duration;
+/// # use rttp_client::types::CacheControl;
+/// let cache_control = CacheControl::parse("no-cache, max-age=60, private=\"Set-Cookie\"");
+/// assert!(cache_control.no_cache());
+/// assert_eq!(Some(Duration::from_secs(60)), cache_control.max_age());
+/// assert_eq!(Some(&"Set-Cookie".to_string()), cache_control.value("private"));
+///
+/// let cache_control = CacheControl::new().directive("no-store").directive_value("max-age", "0").clone();
+/// assert_eq!("no-store, max-age=0", cache_control.to_string());
+/// ```
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct CacheControl {
+  directives: Vec<(String, Option<String>)>,
+}
+
+impl CacheControl {
+  pub fn new() -> Self {
+    Default::default()
+  }
+
+  pub fn parse<S: AsRef<str>>(text: S) -> Self {
+    let directives = split_list(text.as_ref(), ',').iter()
+      .filter_map(|directive| {
+        let (name, value) = match directive.split_once('=') {
+          Some((name, value)) => (name, Some(unquote(value))),
+          None => (&directive[..], None),
+        };
+        let name = name.trim().to_ascii_lowercase();
+        if name.is_empty() { None } else { Some((name, value)) }
+      })
+      .collect();
+    Self { directives }
+  }
+
+  /// Add a directive without value, like `no-cache`.
+  pub fn directive<N: AsRef<str>>(&mut self, name: N) -> &mut Self {
+    self.directives.push((name.as_ref().to_ascii_lowercase(), None));
+    self
+  }
+
+  /// Add a directive with value, like `max-age=60`.
+  pub fn directive_value<N: AsRef<str>, V: AsRef<str>>(&mut self, name: N, value: V) -> &mut Self {
+    self.directives.push((name.as_ref().to_ascii_lowercase(), Some(value.as_ref().to_string())));
+    self
+  }
+
+  pub fn directives(&self) -> &Vec<(String, Option<String>)> {
+    &self.directives
+  }
+
+  pub fn contains<N: AsRef<str>>(&self, name: N) -> bool {
+    self.directives.iter().any(|(n, _)| n.eq_ignore_ascii_case(name.as_ref()))
+  }
+
+  /// Value of the directive, none if the directive not exists or have no value.
+  pub fn value<N: AsRef<str>>(&self, name: N) -> Option<&String> {
+    self.directives.iter()
+      .find(|(n, _)| n.eq_ignore_ascii_case(name.as_ref()))
+      .and_then(|(_, v)| v.as_ref())
+  }
+
+  pub fn no_cache(&self) -> bool { self.contains("no-cache") }
+  pub fn no_store(&self) -> bool { self.contains("no-store") }
+  pub fn must_revalidate(&self) -> bool { self.contains("must-revalidate") }
+  pub fn public(&self) -> bool { self.contains("public") }
+  pub fn private(&self) -> bool { self.contains("private") }
+
+  pub fn max_age(&self) -> Option<Duration> {
+    self.seconds("max-age")
+  }
+
+  pub fn s_maxage(&self) -> Option<Duration> {
+    self.seconds("s-maxage")
+  }
+
+  fn seconds(&self, name: &str) -> Option<Duration> {
+    self.value(name)?.parse().ok().map(Duration::from_secs)
+  }
+}
+
+impl fmt::Display for CacheControl {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let directives: Vec<String> = self.directives.iter()
+      .map(|(name, value)| match value {
+        Some(value) if is_token(value) => format!("{}={}", name, value),
+        Some(value) => format!("{}={}", name, quote(value)),
+        None => name.clone(),
+      })
+      .collect();
+    f.write_str(&directives.join(", "))
+  }
+}
+
+impl AsRef<CacheControl> for CacheControl {
+  fn as_ref(&self) -> &CacheControl {
+    self
+  }
+}
+
+/// Entity tag of `ETag` and `If-None-Match` header, a weak tag is written as `W/"tag"`.
+///
+/// # Examples
+///
+/// ```rust
+/// # use rttp_client::types::ETag;
+/// let etag = ETag::parse("W/\"v1\"").unwrap();
+/// assert!(etag.is_weak());
+/// assert!(etag.weak_eq(&ETag::strong("v1")));
+/// assert!(!etag.strong_eq(&ETag::strong("v1")));
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ETag {
+  weak: bool,
+  tag: String,
+}
+
+impl ETag {
+  pub fn strong<S: AsRef<str>>(tag: S) -> Self {
+    Self { weak: false, tag: tag.as_ref().to_string() }
+  }
+
+  pub fn weak<S: AsRef<str>>(tag: S) -> Self {
+    Self { weak: true, tag: tag.as_ref().to_string() }
+  }
+
+  /// Parse `"tag"` or `W/"tag"`, the unquoted tag is accepted for servers not follow the spec.
+  pub fn parse<S: AsRef<str>>(text: S) -> Option<Self> {
+    let text = text.as_ref().trim();
+    let (weak, tag) = match text.strip_prefix("W/") {
+      Some(tag) => (true, tag),
+      None => (false, text),
+    };
+    let tag = tag.strip_prefix('"').and_then(|t| t.strip_suffix('"')).unwrap_or(tag);
+    if tag.is_empty() || tag.contains('"') || tag == "*" {
+      return None;
+    }
+    Some(Self { weak, tag: tag.to_string() })
+  }
+
+  /// Parse the list of `If-None-Match` header.
+  pub fn parse_list<S: AsRef<str>>(text: S) -> Vec<Self> {
+    split_list(text.as_ref(), ',').iter().filter_map(Self::parse).collect()
+  }
+
+  pub fn tag(&self) -> &String { &self.tag }
+  pub fn is_weak(&self) -> bool { self.weak }
+
+  /// Strong comparison, both are strong and the tags are same.
+  pub fn strong_eq(&self, other: &ETag) -> bool {
+    !self.weak && !other.weak && self.tag == other.tag
+  }
+
+  /// Weak comparison, the tags are same.
+  pub fn weak_eq(&self, other: &ETag) -> bool {
+    self.tag == other.tag
+  }
+}
+
+impl fmt::Display for ETag {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    if self.weak {
+      f.write_str("W/")?;
+    }
+    write!(f, "\"{}\"", self.tag)
+  }
+}
+
+impl AsRef<ETag> for ETag {
+  fn as_ref(&self) -> &ETag {
+    self
+  }
+}
+
+/// Value of `Retry-After` header, a delay in seconds or a http date.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RetryAfter {
+  Delay(Duration),
+  Date(SystemTime),
+}
+
+impl RetryAfter {
+  pub fn parse<S: AsRef<str>>(text: S) -> Option<Self> {
+    let text = text.as_ref().trim();
+    if let Ok(seconds) = text.parse::<u64>() {
+      return Some(RetryAfter::Delay(Duration::from_secs(seconds)));
+    }
+    httpdate::parse_http_date(text).ok().map(RetryAfter::Date)
+  }
+
+  /// How long to wait from now, zero if the date is passed.
+  pub fn delay(&self) -> Duration {
+    match self {
+      RetryAfter::Delay(delay) => *delay,
+      RetryAfter::Date(date) => date.duration_since(SystemTime::now()).unwrap_or_default(),
+    }
+  }
+}
+
+impl fmt::Display for RetryAfter {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      RetryAfter::Delay(delay) => write!(f, "{}", delay.as_secs()),
+      RetryAfter::Date(date) => f.write_str(&httpdate::fmt_http_date(*date)),
+    }
+  }
+}
+
+/// Value of `Content-Range` header of a byte range, `bytes 0-499/1234`.
+/// The `416 Range Not Satisfiable` response sends `bytes */1234`, it has no range.
+///
+/// # Examples
+///
+/// ```rust
+/// # use rttp_client::types::ContentRange;
+/// let content_range = ContentRange::parse("bytes 500-999/1234").unwrap();
+/// assert_eq!(Some((500, 999)), content_range.range());
+/// assert_eq!(Some(500), content_range.range_len());
+/// assert_eq!(Some(1234), content_range.complete_length());
+///
+/// assert_eq!("bytes */1234", ContentRange::unsatisfied(1234).to_string());
+/// assert_eq!("bytes 0-9/*", ContentRange::new(0, 9, None).to_string());
+/// ```
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ContentRange {
+  range: Option<(u64, u64)>,
+  complete_length: Option<u64>,
+}
+
+impl ContentRange {
+  /// Range of first and last byte position, both are inclusive.
+  pub fn new(first: u64, last: u64, complete_length: Option<u64>) -> Self {
+    Self { range: Some((first, last)), complete_length }
+  }
+
+  pub fn unsatisfied(complete_length: u64) -> Self {
+    Self { range: None, complete_length: Some(complete_length) }
+  }
+
+  /// Parse `bytes first-last/length`, the length is `*` if unknown.
+  pub fn parse<S: AsRef<str>>(text: S) -> Option<Self> {
+    let text = text.as_ref().trim();
+    let (unit, rest) = text.split_once(' ')?;
+    if !unit.eq_ignore_ascii_case("bytes") {
+      return None;
+    }
+    let (range, length) = rest.trim().split_once('/')?;
+    let complete_length = match length.trim() {
+      "*" => None,
+      length => Some(length.parse::<u64>().ok()?),
+    };
+    let range = match range.trim() {
+      "*" => None,
+      range => {
+        let (first, last) = range.split_once('-')?;
+        let (first, last) = (first.trim().parse::<u64>().ok()?, last.trim().parse::<u64>().ok()?);
+        if first > last || complete_length.is_some_and(|length| last >= length) {
+          return None;
+        }
+        Some((first, last))
+      }
+    };
+    if range.is_none() && complete_length.is_none() {
+      return None;
+    }
+    Some(Self { range, complete_length })
+  }
+
+  /// First and last byte position, `None` if the range is unsatisfied.
+  pub fn range(&self) -> Option<(u64, u64)> { self.range }
+  pub fn complete_length(&self) -> Option<u64> { self.complete_length }
+
+  /// Count of bytes in the range.
+  pub fn range_len(&self) -> Option<u64> {
+    self.range.map(|(first, last)| last - first + 1)
+  }
+}
+
+impl fmt::Display for ContentRange {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self.range {
+      Some((first, last)) => write!(f, "bytes {}-{}/", first, last)?,
+      None => f.write_str("bytes */")?,
+    }
+    match self.complete_length {
+      Some(length) => write!(f, "{}", length),
+      None => f.write_str("*"),
+    }
+  }
+}
+
+/// A link of `Link` header, RFC 8288.
+///
+/// # Examples
+///
+/// ```rust
+/// # use rttp_client::types::Link;
+/// let links = Link::parse_list(r#"<https://api.example.com/items?page=2>; rel="next last", <https://api.example.com/items?page=1>; rel=first"#);
+/// assert_eq!(2, links.len());
+/// assert!(links[0].has_rel("LAST"));
+/// assert_eq!("https://api.example.com/items?page=1", links[1].uri());
+///
+/// let link = Link::new("/style.css").param("rel", "preload").param("as", "style").clone();
+/// assert_eq!("</style.css>; rel=preload; as=style", link.to_string());
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Link {
+  uri: String,
+  params: Vec<(String, String)>,
+}
+
+impl Link {
+  pub fn new<S: AsRef<str>>(uri: S) -> Self {
+    Self { uri: uri.as_ref().to_string(), params: vec![] }
+  }
+
+  /// Parse one link, `<uri>; name=value; ...`
+  pub fn parse<S: AsRef<str>>(text: S) -> Option<Self> {
+    let parts = split_list(text.as_ref(), ';');
+    let uri = parts.first()?.trim();
+    let uri = uri.strip_prefix('<')?.strip_suffix('>')?;
+    let params = parts.iter().skip(1)
+      .filter_map(|param| {
+        let (name, value) = param.split_once('=').unwrap_or((param, ""));
+        let name = name.trim().to_ascii_lowercase();
+        if name.is_empty() { None } else { Some((name, unquote(value))) }
+      })
+      .collect();
+    Some(Self { uri: uri.to_string(), params })
+  }
+
+  /// Parse links of `Link` header value.
+  pub fn parse_list<S: AsRef<str>>(text: S) -> Vec<Self> {
+    split_list(text.as_ref(), ',').iter().filter_map(Self::parse).collect()
+  }
+
+  /// Add link param.
+  pub fn param<N: AsRef<str>, V: AsRef<str>>(&mut self, name: N, value: V) -> &mut Self {
+    self.params.push((name.as_ref().to_ascii_lowercase(), value.as_ref().to_string()));
+    self
+  }
+
+  /// The target uri, maybe relative to the request url.
+  pub fn uri(&self) -> &String { &self.uri }
+  pub fn params(&self) -> &Vec<(String, String)> { &self.params }
+
+  /// Value of the first param of name, RFC 8288 ignore the occurrences after the first.
+  pub fn param_value<N: AsRef<str>>(&self, name: N) -> Option<&String> {
+    self.params.iter()
+      .find(|(n, _)| n.eq_ignore_ascii_case(name.as_ref()))
+      .map(|(_, v)| v)
+  }
+
+  /// Relation types of `rel` param, separated by space.
+  pub fn rels(&self) -> Vec<&str> {
+    self.param_value("rel").map_or(vec![], |rel| rel.split_whitespace().collect())
+  }
+
+  /// Whether have the relation type, case-insensitive.
+  pub fn has_rel<S: AsRef<str>>(&self, rel: S) -> bool {
+    self.rels().iter().any(|r| r.eq_ignore_ascii_case(rel.as_ref()))
+  }
+}
+
+impl fmt::Display for Link {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "<{}>", self.uri)?;
+    for (name, value) in &self.params {
+      if is_token(value) {
+        write!(f, "; {}={}", name, value)?;
+      } else {
+        write!(f, "; {}={}", name, quote(value))?;
+      }
+    }
+    Ok(())
+  }
+}
+
+impl AsRef<Link> for Link {
+  fn as_ref(&self) -> &Link {
+    self
+  }
+}
+
+/// Media ranges of `Accept` header with quality values.
+///
+/// # Examples
+///
+/// ```rust
+/// # use rttp_client::types::Accept;
+/// let accept = Accept::parse("text/*;q=0.5, application/json, */*;q=0.1");
+/// assert_eq!(mime::APPLICATION_JSON, *accept.preferred().unwrap());
+/// assert_eq!(0.5, accept.quality(&mime::TEXT_HTML));
+/// assert_eq!(0.1, accept.quality(&mime::IMAGE_PNG));
+///
+/// let accept = Accept::new().media(mime::APPLICATION_JSON).media_quality(mime::STAR_STAR, 0.8).clone();
+/// assert_eq!("application/json, */*;q=0.8", accept.to_string());
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Accept {
+  items: Vec<(Mime, f32)>,
+}
+
+impl Accept {
+  pub fn new() -> Self {
+    Default::default()
+  }
+
+  /// Parse `Accept` header value, the bad media ranges are ignored.
+  pub fn parse<S: AsRef<str>>(text: S) -> Self {
+    let items = split_list(text.as_ref(), ',').iter()
+      .filter_map(|item| {
+        // the params after `q` are accept extensions, not part of media range
+        let params = split_list(item, ';');
+        let position = params.iter().position(|p| p.to_ascii_lowercase().starts_with("q="));
+        let quality = match position {
+          Some(position) => params[position][2..].trim().parse::<f32>().ok()
+            .filter(|q| (0.0..=1.0).contains(q))?,
+          None => 1.0,
+        };
+        let range = params[..position.unwrap_or(params.len())].join(";");
+        Some((Mime::from_str(&range).ok()?, quality))
+      })
+      .collect();
+    Self { items }
+  }
+
+  /// Add media range of quality 1.
+  pub fn media(&mut self, mime: Mime) -> &mut Self {
+    self.items.push((mime, 1.0));
+    self
+  }
+
+  /// Add media range with quality, clamped to 0 ~ 1.
+  pub fn media_quality(&mut self, mime: Mime, quality: f32) -> &mut Self {
+    self.items.push((mime, quality.clamp(0.0, 1.0)));
+    self
+  }
+
+  /// Media ranges in order of written.
+  pub fn items(&self) -> &Vec<(Mime, f32)> {
+    &self.items
+  }
+
+  /// Media ranges sorted by quality from high to low, the order of the same quality is kept.
+  pub fn sorted(&self) -> Vec<&(Mime, f32)> {
+    let mut items: Vec<&(Mime, f32)> = self.items.iter().filter(|(_, q)| *q > 0.0).collect();
+    items.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+    items
+  }
+
+  /// The media range of the highest quality.
+  pub fn preferred(&self) -> Option<&Mime> {
+    self.sorted().first().map(|(mime, _)| mime)
+  }
+
+  /// Quality of the media type, use the most specific matched range, 0 if not acceptable.
+  pub fn quality(&self, mime: &Mime) -> f32 {
+    self.items.iter()
+      .filter_map(|(range, q)| {
+        let type_matched = range.type_() == mime::STAR || range.type_() == mime.type_();
+        let subtype_matched = range.subtype() == mime::STAR || range.subtype() == mime.subtype();
+        if !type_matched || !subtype_matched {
+          return None;
+        }
+        let params_matched = range.params().all(|(name, value)| mime.get_param(name) == Some(value));
+        if !params_matched {
+          return None;
+        }
+        let specificity = match (range.type_() == mime::STAR, range.subtype() == mime::STAR) {
+          (true, _) => 0,
+          (false, true) => 1,
+          (false, false) => 2 + range.params().count(),
+        };
+        Some((specificity, *q))
+      })
+      .max_by_key(|(specificity, _)| *specificity)
+      .map_or(0.0, |(_, q)| q)
+  }
+}
+
+impl fmt::Display for Accept {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let items: Vec<String> = self.items.iter()
+      .map(|(mime, q)| if *q >= 1.0 {
+        mime.to_string()
+      } else {
+        format!("{};q={}", mime, (q * 1000.0).round() / 1000.0)
+      })
+      .collect();
+    f.write_str(&items.join(", "))
+  }
+}
+
+impl AsRef<Accept> for Accept {
+  fn as_ref(&self) -> &Accept {
+    self
+  }
+}
+
+/// Split header value by separator, the separators in quoted string or `<uri>` are not split.
+fn split_list(text: &str, separator: char) -> Vec<String> {
+  let mut items = vec![];
+  let mut item = String::new();
+  let (mut quoted, mut escaped, mut bracketed) = (false, false, false);
+  for c in text.chars() {
+    if escaped {
+      escaped = false;
+    } else if quoted && c == '\\' {
+      escaped = true;
+    } else if c == '"' {
+      quoted = !quoted;
+    } else if !quoted && c == '<' {
+      bracketed = true;
+    } else if !quoted && c == '>' {
+      bracketed = false;
+    } else if !quoted && !bracketed && c == separator {
+      items.push(item.trim().to_string());
+      item.clear();
+      continue;
+    }
+    item.push(c);
+  }
+  items.push(item.trim().to_string());
+  items.into_iter().filter(|item| !item.is_empty()).collect()
+}
+
+fn unquote(text: &str) -> String {
+  let text = text.trim();
+  match text.strip_prefix('"').and_then(|t| t.strip_suffix('"')) {
+    Some(text) => {
+      let mut unquoted = String::with_capacity(text.len());
+      let mut chars = text.chars();
+      while let Some(c) = chars.next() {
+        match c {
+          '\\' => unquoted.extend(chars.next()),
+          c => unquoted.push(c),
+        }
+      }
+      unquoted
+    }
+    None => text.to_string(),
+  }
+}
+
+fn quote(text: &str) -> String {
+  format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
@@ -0,0 +1,366 @@
+#![allow(dead_code)]
+
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+/// A request received by the test server.
+#[derive(Clone, Debug)]
+pub struct Incoming {
+  pub method: String,
+  pub path: String,
+  pub headers: Vec<(String, String)>,
+  pub body: Vec<u8>,
+  /// Sizes of chunks if the body is chunked.
+  pub chunks: Vec<usize>,
+}
+
+impl Incoming {
+  pub fn header(&self, name: &str) -> Option<&String> {
+    self.headers.iter()
+      .find(|(n, _)| n.eq_ignore_ascii_case(name))
+      .map(|(_, v)| v)
+  }
+
+  /// Path of request target, the absolute form target `http://host/path` is striped to `/path`.
+  pub fn uri_path(&self) -> &str {
+    match self.path.find("://") {
+      Some(i) => {
+        let rest = &self.path[i + 3..];
+        rest.find('/').map_or("/", |j| &rest[j..])
+      }
+      None => &self.path,
+    }
+  }
+
+  pub fn headers_of(&self, name: &str) -> Vec<&String> {
+    self.headers.iter()
+      .filter(|(n, _)| n.eq_ignore_ascii_case(name))
+      .map(|(_, v)| v)
+      .collect()
+  }
+}
+
+/// A local http server for offline tests, every request is answered by the handler.
+pub struct Server {
+  addr: String,
+  connections: Arc<AtomicUsize>,
+  hits: Arc<AtomicUsize>,
+}
+
+impl Server {
+  pub fn url(&self, path: &str) -> String {
+    format!("http://{}{}", self.addr, path)
+  }
+
+  pub fn addr(&self) -> &String { &self.addr }
+
+  /// Count of accepted tcp connections.
+  pub fn connections(&self) -> usize { self.connections.load(Ordering::SeqCst) }
+
+  /// Count of received requests.
+  pub fn hits(&self) -> usize { self.hits.load(Ordering::SeqCst) }
+}
+
+/// Start a server, the connection is kept alive unless the response or the request
+/// have `Connection: close` header, or the response have `X-Test-Close` header.
+/// The response have `X-Test-Broken` header is cut after the status line, then the connection is closed.
+pub fn serve<F>(handler: F) -> Server
+  where
+    F: Fn(&Incoming) -> Vec<u8> + Send + Sync + 'static,
+{
+  let listener = TcpListener::bind("127.0.0.1:0").expect("Can't bind test server");
+  let addr = listener.local_addr().unwrap().to_string();
+  let connections = Arc::new(AtomicUsize::new(0));
+  let hits = Arc::new(AtomicUsize::new(0));
+  let handler = Arc::new(handler);
+
+  let server = Server { addr, connections: connections.clone(), hits: hits.clone() };
+  thread::spawn(move || {
+    for stream in listener.incoming() {
+      let stream = match stream {
+        Ok(s) => s,
+        Err(_) => continue,
+      };
+      connections.fetch_add(1, Ordering::SeqCst);
+      let handler = handler.clone();
+      let hits = hits.clone();
+      thread::spawn(move || handle(stream, handler, hits));
+    }
+  });
+  server
+}
+
+/// Start a server always answer the same response.
+pub fn serve_text(response: &'static str) -> Server {
+  serve(move |_| response.as_bytes().to_vec())
+}
+
+fn handle<F>(stream: TcpStream, handler: Arc<F>, hits: Arc<AtomicUsize>)
+  where
+    F: Fn(&Incoming) -> Vec<u8>,
+{
+  let mut reader = BufReader::new(stream.try_clone().unwrap());
+  let mut writer = stream;
+  loop {
+    let incoming = match read_request(&mut reader) {
+      Some(v) => v,
+      None => return,
+    };
+    hits.fetch_add(1, Ordering::SeqCst);
+    let response = handler(&incoming);
+    let head = String::from_utf8_lossy(&response).to_ascii_lowercase();
+    if head.split("\r\n\r\n").next().unwrap_or_default().contains("\r\nx-test-broken:") {
+      let status_line = head.find("\r\n").map_or(0, |i| i + 2);
+      let _ = writer.write_all(&response[..status_line]);
+      return;
+    }
+    if writer.write_all(&response).is_err() {
+      return;
+    }
+    let _ = writer.flush();
+
+    // `X-Test-Close` close the connection without tell the client
+    let response_close = head.split("\r\n\r\n").next().unwrap_or_default()
+      .split("\r\n")
+      .any(|line| line == "connection: close" || line.starts_with("x-test-close:"));
+    let request_close = incoming.header("connection")
+      .is_some_and(|v| v.eq_ignore_ascii_case("close"));
+    if response_close || request_close {
+      return;
+    }
+  }
+}
+
+fn read_request<R: BufRead>(reader: &mut R) -> Option<Incoming> {
+  let mut line = String::new();
+  if reader.read_line(&mut line).ok()? == 0 {
+    return None;
+  }
+  let mut parts = line.trim_end().splitn(3, ' ');
+  let method = parts.next()?.to_string();
+  let path = parts.next()?.to_string();
+
+  let mut headers = vec![];
+  loop {
+    let mut line = String::new();
+    if reader.read_line(&mut line).ok()? == 0 {
+      return None;
+    }
+    let line = line.trim_end();
+    if line.is_empty() {
+      break;
+    }
+    let mut nv = line.splitn(2, ':');
+    let name = nv.next()?.trim().to_string();
+    let value = nv.next().unwrap_or_default().trim().to_string();
+    headers.push((name, value));
+  }
+
+  let mut incoming = Incoming { method, path, headers, body: vec![], chunks: vec![] };
+  let chunked = incoming.header("transfer-encoding")
+    .is_some_and(|v| v.eq_ignore_ascii_case("chunked"));
+  if chunked {
+    loop {
+      let mut size = String::new();
+      reader.read_line(&mut size).ok()?;
+      let size = size.trim_end().split(';').next()?.trim().to_string();
+      let size = usize::from_str_radix(&size, 16).ok()?;
+      if size == 0 {
+        // trailers
+        loop {
+          let mut line = String::new();
+          reader.read_line(&mut line).ok()?;
+          let line = line.trim_end();
+          if line.is_empty() {
+            break;
+          }
+          let mut nv = line.splitn(2, ':');
+          let name = nv.next()?.trim().to_string();
+          let value = nv.next().unwrap_or_default().trim().to_string();
+          incoming.headers.push((name, value));
+        }
+        break;
+      }
+      let mut chunk = vec![0u8; size + 2];
+      reader.read_exact(&mut chunk).ok()?;
+      chunk.truncate(size);
+      incoming.body.extend(chunk);
+      incoming.chunks.push(size);
+    }
+  } else if let Some(len) = incoming.header("content-length").and_then(|v| v.parse::<usize>().ok()) {
+    let mut body = vec![0u8; len];
+    reader.read_exact(&mut body).ok()?;
+    incoming.body = body;
+  }
+  Some(incoming)
+}
+
+/// Build a response with `Content-Length` header.
+pub fn response(status: &str, headers: &[(&str, &str)], body: &[u8]) -> Vec<u8> {
+  let mut text = format!("HTTP/1.1 {}\r\n", status);
+  for (name, value) in headers {
+    text.push_str(&format!("{}: {}\r\n", name, value));
+  }
+  text.push_str(&format!("Content-Length: {}\r\n\r\n", body.len()));
+  let mut binary = text.into_bytes();
+  binary.extend_from_slice(body);
+  binary
+}
+
+/// Answer the file body, a single `Range` of `bytes=first-` or `bytes=first-last` is
+/// answered by `206 Partial Content` unless `If-Range` not match the `ETag` in headers.
+/// `HEAD` is answered without body.
+pub fn ranged(incoming: &Incoming, headers: &[(&str, &str)], body: &[u8]) -> Vec<u8> {
+  if incoming.method == "HEAD" {
+    let mut binary = response("200 OK", headers, body);
+    binary.truncate(binary.len() - body.len());
+    return binary;
+  }
+  let etag = headers.iter().find(|(n, _)| n.eq_ignore_ascii_case("etag")).map(|(_, v)| *v);
+  let if_range = incoming.header("if-range").is_none_or(|v| Some(&v[..]) == etag);
+  let range = incoming.header("range")
+    .and_then(|v| v.strip_prefix("bytes="))
+    .and_then(|v| v.split_once('-'))
+    .filter(|_| if_range);
+  let (first, last) = match range {
+    Some((first, last)) => (first.parse::<usize>().unwrap(), last.parse::<usize>().ok()),
+    None => return response("200 OK", headers, body),
+  };
+  if first >= body.len() {
+    let content_range = format!("bytes */{}", body.len());
+    let mut headers = headers.to_vec();
+    headers.push(("Content-Range", &content_range));
+    return response("416 Range Not Satisfiable", &headers, b"");
+  }
+  let last = last.unwrap_or(body.len() - 1).min(body.len() - 1);
+  let content_range = format!("bytes {}-{}/{}", first, last, body.len());
+  let mut headers = headers.to_vec();
+  headers.push(("Content-Range", &content_range));
+  response("206 Partial Content", &headers, &body[first..=last])
+}
+
+/// Start a proxy server, the protocol is detected from the first byte,
+/// support socks4/socks4a, socks5 (no auth or `user:pass`) and http `CONNECT`.
+pub fn serve_proxy() -> Server {
+  let listener = TcpListener::bind("127.0.0.1:0").expect("Can't bind test proxy");
+  let addr = listener.local_addr().unwrap().to_string();
+  let connections = Arc::new(AtomicUsize::new(0));
+  let hits = Arc::new(AtomicUsize::new(0));
+
+  let server = Server { addr, connections: connections.clone(), hits: hits.clone() };
+  thread::spawn(move || {
+    for stream in listener.incoming() {
+      let stream = match stream {
+        Ok(s) => s,
+        Err(_) => continue,
+      };
+      connections.fetch_add(1, Ordering::SeqCst);
+      let hits = hits.clone();
+      thread::spawn(move || {
+        if let Some(target) = proxy_handshake(&stream) {
+          hits.fetch_add(1, Ordering::SeqCst);
+          tunnel(stream, target);
+        }
+      });
+    }
+  });
+  server
+}
+
+fn read_n(stream: &mut TcpStream, n: usize) -> Option<Vec<u8>> {
+  let mut buf = vec![0u8; n];
+  stream.read_exact(&mut buf).ok()?;
+  Some(buf)
+}
+
+fn read_until_nul(stream: &mut TcpStream) -> Option<String> {
+  let mut text = vec![];
+  loop {
+    let b = read_n(stream, 1)?[0];
+    if b == 0 {
+      return String::from_utf8(text).ok();
+    }
+    text.push(b);
+  }
+}
+
+fn proxy_handshake(stream: &TcpStream) -> Option<TcpStream> {
+  let mut stream = stream.try_clone().ok()?;
+  let first = read_n(&mut stream, 1)?[0];
+  match first {
+    4 => {
+      let head = read_n(&mut stream, 7)?;
+      let port = u16::from_be_bytes([head[1], head[2]]);
+      let _user = read_until_nul(&mut stream)?;
+      let host = if head[3] == 0 && head[4] == 0 && head[5] == 0 && head[6] != 0 {
+        read_until_nul(&mut stream)?
+      } else {
+        format!("{}.{}.{}.{}", head[3], head[4], head[5], head[6])
+      };
+      let target = TcpStream::connect((&host[..], port)).ok()?;
+      stream.write_all(&[0, 90, 0, 0, 0, 0, 0, 0]).ok()?;
+      Some(target)
+    }
+    5 => {
+      let n = read_n(&mut stream, 1)?[0] as usize;
+      let methods = read_n(&mut stream, n)?;
+      if methods.contains(&2) {
+        stream.write_all(&[5, 2]).ok()?;
+        let _ver = read_n(&mut stream, 1)?;
+        let ulen = read_n(&mut stream, 1)?[0] as usize;
+        let user = read_n(&mut stream, ulen)?;
+        let plen = read_n(&mut stream, 1)?[0] as usize;
+        let pass = read_n(&mut stream, plen)?;
+        let ok = user == b"user" && pass == b"pass";
+        stream.write_all(&[1, if ok { 0 } else { 1 }]).ok()?;
+        if !ok {
+          return None;
+        }
+      } else {
+        stream.write_all(&[5, 0]).ok()?;
+      }
+      let head = read_n(&mut stream, 4)?;
+      let host = match head[3] {
+        1 => read_n(&mut stream, 4)?.iter().map(|b| b.to_string()).collect::<Vec<_>>().join("."),
+        3 => {
+          let len = read_n(&mut stream, 1)?[0] as usize;
+          String::from_utf8(read_n(&mut stream, len)?).ok()?
+        }
+        _ => return None,
+      };
+      let port = read_n(&mut stream, 2)?;
+      let port = u16::from_be_bytes([port[0], port[1]]);
+      let target = TcpStream::connect((&host[..], port)).ok()?;
+      stream.write_all(&[5, 0, 0, 1, 127, 0, 0, 1, 0, 0]).ok()?;
+      Some(target)
+    }
+    b'C' => {
+      let mut head = vec![first];
+      while !head.ends_with(b"\r\n\r\n") {
+        head.push(read_n(&mut stream, 1)?[0]);
+      }
+      let head = String::from_utf8(head).ok()?;
+      let authority = head.split_whitespace().nth(1)?.to_string();
+      let target = TcpStream::connect(&authority[..]).ok()?;
+      stream.write_all(b"HTTP/1.1 200 Connection Established\r\n\r\n").ok()?;
+      Some(target)
+    }
+    _ => None,
+  }
+}
+
+fn tunnel(client: TcpStream, target: TcpStream) {
+  let mut client_read = client.try_clone().unwrap();
+  let mut target_write = target.try_clone().unwrap();
+  thread::spawn(move || {
+    let _ = io::copy(&mut client_read, &mut target_write);
+    let _ = target_write.shutdown(std::net::Shutdown::Write);
+  });
+  let mut target_read = target;
+  let mut client_write = client;
+  let _ = io::copy(&mut target_read, &mut client_write);
+  let _ = client_write.shutdown(std::net::Shutdown::Write);
+}
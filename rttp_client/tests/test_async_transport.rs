@@ -0,0 +1,229 @@
+#![cfg(feature = "async")]
+
+use async_std::task;
+
+use rttp_client::{Client, Config};
+use rttp_client::types::{Auth, Proxy};
+
+mod support;
+
+fn client() -> Client {
+  Client::builder()
+    .config(Config::builder().read_timeout(3000))
+    .build()
+}
+
+#[test]
+fn test_async_framed_body() {
+  let body = "x".repeat(64 * 1024);
+  let server = support::serve(move |_| support::response("200 OK", &[], body.as_bytes()));
+  task::block_on(async {
+    let response = client().get().url(server.url("/")).rasync().await.expect("REQUEST FAIL");
+    assert_eq!(200, response.code());
+    assert_eq!(64 * 1024, response.body().binary().len());
+  });
+}
+
+#[test]
+fn test_async_chunked_body() {
+  let server = support::serve_text("HTTP/1.1 100 Continue\r\n\r\nHTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n\
+    5\r\nhello\r\n6\r\n, rttp\r\n0\r\nX-Checksum: 42\r\n\r\n");
+  task::block_on(async {
+    let response = client().post().url(server.url("/")).rasync().await.expect("REQUEST FAIL");
+    assert_eq!("hello, rttp", response.body().string().unwrap());
+    assert_eq!(Some(&"42".to_string()), response.trailer_value("x-checksum"));
+  });
+}
+
+#[test]
+fn test_async_huge_length() {
+  let length = support::serve_text("HTTP/1.1 200 OK\r\nContent-Length: 18446744073709551615\r\nX-Test-Close: true\r\n\r\nshort");
+  let chunked = support::serve_text("HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\nX-Test-Close: true\r\n\r\n\
+    ffffffffffffffff\r\nshort");
+  task::block_on(async {
+    assert!(client().get().url(length.url("/")).rasync().await.is_err());
+    assert!(client().get().url(chunked.url("/")).rasync().await.is_err());
+  });
+}
+
+#[test]
+fn test_async_head_no_body() {
+  let server = support::serve_text("HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\n");
+  task::block_on(async {
+    let response = client().head().url(server.url("/")).rasync().await.expect("REQUEST FAIL");
+    assert_eq!(200, response.code());
+    assert!(response.body().binary().is_empty());
+  });
+}
+
+#[test]
+fn test_async_concurrent() {
+  let server = support::serve(|req| support::response("200 OK", &[], req.path.as_bytes()));
+  let client = client();
+  task::block_on(async {
+    let tasks = (0..10).map(|i| {
+      let client = client.clone();
+      let url = server.url(&format!("/{}", i));
+      task::spawn(async move {
+        let response = client.get().url(&url).rasync().await.expect("REQUEST FAIL");
+        assert!(response.body().string().unwrap().ends_with(&format!("/{}", i)));
+      })
+    }).collect::<Vec<_>>();
+    for t in tasks {
+      t.await;
+    }
+  });
+  assert_eq!(10, server.hits());
+}
+
+#[test]
+fn test_async_proxy_socks4() {
+  let server = support::serve(|_| support::response("200 OK", &[], b"socks4"));
+  let proxy = support::serve_proxy();
+  let (host, port) = proxy.addr().split_once(':').unwrap();
+  task::block_on(async {
+    let response = client().get()
+      .url(server.url("/"))
+      .proxy(Proxy::socks4(host, port.parse().unwrap()))
+      .rasync()
+      .await
+      .expect("REQUEST FAIL");
+    assert_eq!("socks4", response.body().string().unwrap());
+  });
+  assert_eq!(1, proxy.hits());
+}
+
+#[test]
+fn test_async_proxy_socks5() {
+  let server = support::serve(|_| support::response("200 OK", &[], b"socks5"));
+  let proxy = support::serve_proxy();
+  let (host, port) = proxy.addr().split_once(':').unwrap();
+  task::block_on(async {
+    let response = client().get()
+      .url(server.url("/"))
+      .proxy(Proxy::socks5(host, port.parse().unwrap()))
+      .rasync()
+      .await
+      .expect("REQUEST FAIL");
+    assert_eq!("socks5", response.body().string().unwrap());
+
+    let response = client().get()
+      .url(server.url("/"))
+      .proxy(Proxy::socks5_with_authorization(host, port.parse().unwrap(), "user", "pass"))
+      .rasync()
+      .await
+      .expect("REQUEST FAIL");
+    assert_eq!("socks5", response.body().string().unwrap());
+
+    let response = client().get()
+      .url(server.url("/"))
+      .proxy(Proxy::socks5_with_authorization(host, port.parse().unwrap(), "user", "wrong"))
+      .rasync()
+      .await;
+    assert!(response.is_err());
+  });
+}
+
+#[test]
+fn test_async_proxy_connect() {
+  let server = support::serve(|_| support::response("200 OK", &[], b"tunnel"));
+  let proxy = support::serve_proxy();
+  let (host, port) = proxy.addr().split_once(':').unwrap();
+  task::block_on(async {
+    let response = client().get()
+      .url(server.url("/"))
+      .proxy(Proxy::http(host, port.parse().unwrap()))
+      .rasync()
+      .await
+      .expect("REQUEST FAIL");
+    assert_eq!("tunnel", response.body().string().unwrap());
+  });
+  assert_eq!(1, proxy.hits());
+}
+
+#[test]
+#[cfg(any(feature = "tls-rustls", feature = "tls-native"))]
+fn test_async_https_bad_handshake() {
+  let server = support::serve_text("HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok");
+  let url = format!("https://{}/", server.addr());
+  task::block_on(async {
+    assert!(client().get().url(&url).rasync().await.is_err());
+  });
+}
+
+fn redirect_server() -> support::Server {
+  support::serve(|req| {
+    let host = req.header("host").cloned().unwrap_or_default();
+    let path = req.path.rsplit(&host[..]).next().unwrap_or_default().to_string();
+    match &path[..] {
+      "/start" => support::response("302 Found", &[("Location", &format!("http://{}/target", host))], b""),
+      "/loop" => support::response("302 Found", &[("Location", &format!("http://{}/loop", host))], b""),
+      "/target" => support::response("200 OK", &[], b"target"),
+      _ => {
+        let n: u32 = path.trim_start_matches("/chain/").parse().unwrap_or(0);
+        support::response("302 Found", &[("Location", &format!("http://{}/chain/{}", host, n + 1))], b"")
+      }
+    }
+  })
+}
+
+#[test]
+fn test_async_redirect() {
+  let server = redirect_server();
+  task::block_on(async {
+    let response = client().get()
+      .url(server.url("/start"))
+      .config(Config::builder().read_timeout(3000).auto_redirect(true))
+      .rasync()
+      .await
+      .expect("REQUEST FAIL");
+    assert_eq!(200, response.code());
+    assert_eq!("target", response.body().string().unwrap());
+
+    let response = client().get()
+      .url(server.url("/start"))
+      .config(Config::builder().read_timeout(3000).auto_redirect(false))
+      .rasync()
+      .await
+      .expect("REQUEST FAIL");
+    assert_eq!(302, response.code());
+  });
+}
+
+#[test]
+fn test_async_redirect_errors() {
+  let server = redirect_server();
+  task::block_on(async {
+    let response = client().get()
+      .url(server.url("/loop"))
+      .config(Config::builder().auto_redirect(true))
+      .rasync()
+      .await;
+    assert!(response.err().unwrap().is_redirect());
+
+    let response = client().get()
+      .url(server.url("/chain/0"))
+      .config(Config::builder().auto_redirect(true).max_redirect(3))
+      .rasync()
+      .await;
+    assert!(response.err().unwrap().is_redirect());
+  });
+  // one request for loop, the chain stop after max redirect follows
+  assert_eq!(1 + 1 + 3, server.hits());
+}
+
+#[test]
+fn test_async_digest_auth() {
+  let server = support::serve(|req| {
+    match req.header("authorization") {
+      Some(authorization) if authorization.contains(r#"nonce="abc""#) => support::response("200 OK", &[], b"authorized"),
+      _ => support::response("401 Unauthorized", &[("WWW-Authenticate", r#"Digest realm="rttp", nonce="abc", qop="auth""#)], b""),
+    }
+  });
+  task::block_on(async {
+    let response = client().get().url(server.url("/")).auth(Auth::digest("user", "passwd"))
+      .rasync().await.expect("REQUEST FAIL");
+    assert_eq!("authorized", response.body().string().unwrap());
+    assert_eq!(2, server.hits());
+  });
+}
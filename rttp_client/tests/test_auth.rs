@@ -0,0 +1,89 @@
+use rttp_client::{Client, Config, HttpClient};
+use rttp_client::types::{Auth, RedirectPolicy};
+
+mod support;
+
+/// `/echo` answer the request target and authorization header,
+/// `/redirect/<url>` redirect to `url`.
+fn server() -> support::Server {
+  support::serve(|req| {
+    if let Some(location) = req.uri_path().strip_prefix("/redirect/") {
+      let location = location.replacen(":/", "://", 1);
+      return support::response("302 Found", &[("Location", &location)], b"");
+    }
+    let auth = req.header("authorization").cloned().unwrap_or_default();
+    let text = format!("{} auth={}", req.path, auth);
+    support::response("200 OK", &[], text.as_bytes())
+  })
+}
+
+fn send(client: &mut HttpClient) -> String {
+  client.emit().expect("REQUEST FAIL").body().string().unwrap()
+}
+
+#[test]
+fn test_auth_basic() {
+  let server = server();
+  let text = send(HttpClient::new().get().url(server.url("/echo")).auth(Auth::basic("user", "passwd")));
+  assert!(text.ends_with("auth=Basic dXNlcjpwYXNzd2Q="));
+
+  let text = send(HttpClient::new().get().url(server.url("/echo")).auth(Auth::basic_username("user")));
+  assert!(text.ends_with("auth=Basic dXNlcjo="));
+}
+
+#[test]
+fn test_auth_bearer() {
+  let server = server();
+  let text = send(HttpClient::new().get().url(server.url("/echo")).auth(Auth::bearer("token")));
+  assert!(text.ends_with("auth=Bearer token"));
+}
+
+#[test]
+fn test_auth_url_userinfo() {
+  let server = server();
+  let url = server.url("/echo").replacen("http://", "http://user:p%40ss@", 1);
+  let text = send(HttpClient::new().get().url(&url));
+  // user:p@ss
+  assert!(text.ends_with("auth=Basic dXNlcjpwQHNz"));
+  assert!(!text.contains("user:"), "userinfo leaked in request line: {}", text);
+
+  // explicit auth take precedence
+  let text = send(HttpClient::new().get().url(&url).auth(Auth::bearer("token")));
+  assert!(text.ends_with("auth=Bearer token"));
+}
+
+#[test]
+fn test_auth_header_precedence() {
+  let server = server();
+  let text = send(HttpClient::new()
+    .get()
+    .url(server.url("/echo"))
+    .header(("Authorization", "Custom value"))
+    .auth(Auth::basic("user", "passwd")));
+  assert!(text.ends_with("auth=Custom value"));
+}
+
+#[test]
+fn test_auth_redirect() {
+  let first = server();
+  let second = server();
+  let client = Client::builder()
+    .config(Config::builder().redirect(RedirectPolicy::limited(5)))
+    .build();
+
+  let url = first.url(&format!("/redirect/{}", first.url("/echo")));
+  let text = client.get().url(&url).auth(Auth::bearer("token")).emit()
+    .expect("REQUEST FAIL").body().string().unwrap();
+  assert!(text.ends_with("auth=Bearer token"));
+
+  let url = first.url(&format!("/redirect/{}", second.url("/echo")));
+  let text = client.get().url(&url).auth(Auth::bearer("token")).emit()
+    .expect("REQUEST FAIL").body().string().unwrap();
+  assert!(text.ends_with("auth="));
+
+  let url = first.url(&format!("/redirect/{}", second.url("/echo")))
+    .replacen("http://", "http://user:passwd@", 1);
+  let text = client.get().url(&url).emit()
+    .expect("REQUEST FAIL").body().string().unwrap();
+  assert!(text.ends_with("auth="));
+}
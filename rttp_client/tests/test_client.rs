@@ -0,0 +1,130 @@
+use std::thread;
+
+use rttp_client::{Client, Config, HttpClient};
+use rttp_client::types::Proxy;
+
+mod support;
+
+fn assert_send_sync<T: Send + Sync + Clone>() {}
+
+#[test]
+fn test_client_send_sync() {
+  assert_send_sync::<Client>();
+  assert_send_sync::<HttpClient>();
+}
+
+#[test]
+fn test_client_default_headers() {
+  let server = support::serve(|req| {
+    let token = req.header("x-token").cloned().unwrap_or_default();
+    support::response("200 OK", &[], token.as_bytes())
+  });
+  let client = Client::builder()
+    .header(("X-Token", "shared"))
+    .build();
+
+  let response = client.get().url(server.url("/")).emit().expect("REQUEST FAIL");
+  assert_eq!("shared", response.body().string().unwrap());
+
+  let response = client.get()
+    .url(server.url("/"))
+    .header(("X-Token", "override"))
+    .emit()
+    .expect("REQUEST FAIL");
+  assert_eq!("override", response.body().string().unwrap());
+}
+
+#[test]
+fn test_client_share_across_threads() {
+  let server = support::serve(|req| support::response("200 OK", &[], req.path.as_bytes()));
+  let client = Client::builder()
+    .config(Config::builder().read_timeout(3000))
+    .build();
+
+  let handles = (0..8).map(|i| {
+    let client = client.clone();
+    let url = server.url(&format!("/{}", i));
+    thread::spawn(move || {
+      (0..5).for_each(|_| {
+        let response = client.get().url(&url).emit().expect("REQUEST FAIL");
+        assert!(response.body().string().unwrap().ends_with(&format!("/{}", i)));
+      });
+    })
+  }).collect::<Vec<_>>();
+  handles.into_iter().for_each(|h| h.join().unwrap());
+  assert_eq!(40, server.hits());
+}
+
+#[test]
+fn test_client_reset_keep_settings() {
+  let server = support::serve(|req| {
+    let token = req.header("x-token").cloned().unwrap_or_default();
+    support::response("200 OK", &[], token.as_bytes())
+  });
+  let mut http = Client::builder()
+    .header(("X-Token", "shared"))
+    .build()
+    .request();
+  assert!(http.url(server.url("/")).emit().is_ok());
+  assert!(http.url(server.url("/")).emit().is_err());
+
+  let response = http.reset().url(server.url("/")).emit().expect("REQUEST FAIL");
+  assert_eq!("shared", response.body().string().unwrap());
+}
+
+#[test]
+fn test_client_settings() {
+  let client = Client::builder()
+    .header("Accept: application/json")
+    .config(Config::builder().max_redirect(7))
+    .proxy(Proxy::socks5("127.0.0.1", 1080))
+    .build();
+  assert_eq!(1, client.headers().len());
+  assert_eq!(7, client.config().max_redirect());
+  assert!(client.proxy().is_some());
+  assert_eq!(7, client.post().client().config().max_redirect());
+}
+
+#[test]
+fn test_client_user_agent() {
+  let server = support::serve(|req| {
+    let ua = req.header("user-agent").cloned().unwrap_or_else(|| "<none>".to_string());
+    support::response("200 OK", &[], ua.as_bytes())
+  });
+  let send = |http: &mut HttpClient| http.url(server.url("/")).emit().expect("REQUEST FAIL").body().string().unwrap();
+
+  assert!(send(HttpClient::new().get()).starts_with("Mozilla/5.0 rttp/"));
+
+  let client = Client::builder().user_agent("my-app/1.0").build();
+  assert_eq!(Some("my-app/1.0".to_string()), *client.user_agent());
+  assert_eq!("my-app/1.0", send(&mut client.get()));
+  assert_eq!("other/2.0", send(client.get().header(("User-Agent", "other/2.0"))));
+
+  let client = Client::builder().no_user_agent().build();
+  assert_eq!("<none>", send(&mut client.get()));
+  assert_eq!("other/2.0", send(client.get().header(("User-Agent", "other/2.0"))));
+
+  let client = Client::builder().user_agent("bad\r\nX-Injected: 1").build();
+  assert!(client.get().url(server.url("/")).emit().expect_err("INVALID USER AGENT SENT").is_builder());
+}
+
+#[test]
+fn test_client_bodyless_content_type() {
+  let server = support::serve(|req| {
+    let content_type = req.header("content-type").cloned().unwrap_or_else(|| "<none>".to_string());
+    support::response("200 OK", &[], content_type.as_bytes())
+  });
+  let send = |http: &mut HttpClient| http.url(server.url("/")).emit().expect("REQUEST FAIL").body().string().unwrap();
+
+  assert_ne!("<none>", send(HttpClient::new().get()));
+
+  let client = Client::builder()
+    .bodyless_content_type(false)
+    .header(("Accept", "application/json"))
+    .header(("X-Api-Key", "secret"))
+    .build();
+  assert!(!client.bodyless_content_type());
+  assert_eq!("<none>", send(&mut client.get()));
+  assert_eq!("text/plain", send(client.post().raw("text")));
+  assert_eq!("application/json", send(client.get().content_type("application/json")));
+}
@@ -0,0 +1,139 @@
+use rttp_client::HttpClient;
+
+mod support;
+
+const TEXT: &str = "hello content encoding, hello content encoding, hello content encoding";
+
+fn serve(encoding: &'static str, body: Vec<u8>) -> support::Server {
+  support::serve(move |req| {
+    let accept = req.header("accept-encoding").cloned().unwrap_or_default();
+    support::response("200 OK", &[("Content-Encoding", encoding), ("X-Accept-Encoding", &accept)], &body)
+  })
+}
+
+fn fetch(server: &support::Server) -> rttp_client::error::Result<String> {
+  let response = HttpClient::new().get().url(server.url("/")).emit()?;
+  Ok(response.body().string().unwrap())
+}
+
+#[cfg(feature = "gzip")]
+fn gzip(data: &[u8]) -> Vec<u8> {
+  use std::io::Write;
+  let mut encoder = flate2::write::GzEncoder::new(vec![], flate2::Compression::default());
+  encoder.write_all(data).unwrap();
+  encoder.finish().unwrap()
+}
+
+#[cfg(feature = "brotli")]
+fn brotli(data: &[u8]) -> Vec<u8> {
+  use std::io::Write;
+  let mut binary = vec![];
+  {
+    let mut encoder = brotli::CompressorWriter::new(&mut binary, 4096, 5, 22);
+    encoder.write_all(data).unwrap();
+  }
+  binary
+}
+
+#[test]
+#[cfg(feature = "gzip")]
+fn test_content_encoding_gzip() {
+  let server = serve("gzip", gzip(TEXT.as_bytes()));
+  assert_eq!(TEXT, fetch(&server).unwrap());
+}
+
+#[test]
+#[cfg(feature = "gzip")]
+fn test_content_encoding_corrupt() {
+  let mut binary = gzip(TEXT.as_bytes());
+  binary.truncate(binary.len() / 2);
+  let server = serve("gzip", binary);
+  assert!(fetch(&server).expect_err("TRUNCATED BODY DECODED").is_decode());
+
+  let server = serve("gzip", b"not gzip at all".to_vec());
+  assert!(fetch(&server).expect_err("BAD BODY DECODED").is_decode());
+}
+
+#[test]
+#[cfg(feature = "deflate")]
+fn test_content_encoding_deflate() {
+  use std::io::Write;
+
+  let mut zlib = flate2::write::ZlibEncoder::new(vec![], flate2::Compression::default());
+  zlib.write_all(TEXT.as_bytes()).unwrap();
+  let server = serve("deflate", zlib.finish().unwrap());
+  assert_eq!(TEXT, fetch(&server).unwrap());
+
+  // raw deflate without zlib header
+  let mut raw = flate2::write::DeflateEncoder::new(vec![], flate2::Compression::default());
+  raw.write_all(TEXT.as_bytes()).unwrap();
+  let server = serve("deflate", raw.finish().unwrap());
+  assert_eq!(TEXT, fetch(&server).unwrap());
+}
+
+#[test]
+#[cfg(feature = "brotli")]
+fn test_content_encoding_brotli() {
+  let server = serve("br", brotli(TEXT.as_bytes()));
+  assert_eq!(TEXT, fetch(&server).unwrap());
+
+  let server = serve("br", b"\xff\xff\xff\xff".to_vec());
+  assert!(fetch(&server).expect_err("BAD BODY DECODED").is_decode());
+}
+
+#[test]
+#[cfg(feature = "zstd")]
+fn test_content_encoding_zstd() {
+  let server = serve("zstd", zstd::encode_all(TEXT.as_bytes(), 3).unwrap());
+  assert_eq!(TEXT, fetch(&server).unwrap());
+
+  let server = serve("zstd", b"not zstd".to_vec());
+  assert!(fetch(&server).expect_err("BAD BODY DECODED").is_decode());
+}
+
+#[test]
+#[cfg(all(feature = "gzip", feature = "brotli"))]
+fn test_content_encoding_stacked() {
+  // gzip applied first, then br
+  let server = serve("gzip, br", brotli(&gzip(TEXT.as_bytes())));
+  assert_eq!(TEXT, fetch(&server).unwrap());
+}
+
+#[test]
+#[cfg(feature = "gzip")]
+fn test_content_encoding_unsupported() {
+  // unknown coding in the stack, the body is kept as is
+  let binary = gzip(TEXT.as_bytes());
+  let server = serve("gzip, unknown", binary.clone());
+  let response = HttpClient::new().get().url(server.url("/")).emit().expect("REQUEST FAIL");
+  assert_eq!(binary, response.body().binary());
+}
+
+#[test]
+fn test_content_encoding_identity() {
+  let server = serve("identity", TEXT.as_bytes().to_vec());
+  assert_eq!(TEXT, fetch(&server).unwrap());
+  let server = serve("unknown", TEXT.as_bytes().to_vec());
+  assert_eq!(TEXT, fetch(&server).unwrap());
+}
+
+#[test]
+fn test_content_encoding_accept_encoding() {
+  let server = serve("identity", vec![]);
+  let response = HttpClient::new().get().url(server.url("/")).emit().expect("REQUEST FAIL");
+  let accept = response.header_value("x-accept-encoding").cloned().unwrap_or_default();
+  let mut expected: Vec<&str> = vec![];
+  if cfg!(feature = "gzip") { expected.push("gzip"); }
+  if cfg!(feature = "deflate") { expected.push("deflate"); }
+  if cfg!(feature = "brotli") { expected.push("br"); }
+  if cfg!(feature = "zstd") { expected.push("zstd"); }
+  assert_eq!(expected.join(", "), accept);
+
+  let response = HttpClient::new()
+    .get()
+    .url(server.url("/"))
+    .header(("Accept-Encoding", "identity"))
+    .emit()
+    .expect("REQUEST FAIL");
+  assert_eq!(Some(&"identity".to_string()), response.header_value("x-accept-encoding"));
+}
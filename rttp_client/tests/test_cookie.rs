@@ -0,0 +1,88 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use rttp_client::types::{Cookie, SameSite};
+
+#[test]
+fn test_cookie_parse_name_value() {
+  let cookie = Cookie::parse(" id = a=b=c ; Path=/").unwrap();
+  assert_eq!("id", cookie.name());
+  assert_eq!("a=b=c", cookie.value());
+
+  // attributes never overwrite name and value
+  let cookie = Cookie::parse("id=1; Max-Age=60; Unknown=2; Comment").unwrap();
+  assert_eq!("id", cookie.name());
+  assert_eq!("1", cookie.value());
+
+  let cookie = Cookie::parse("empty=").unwrap();
+  assert_eq!("", cookie.value());
+
+  assert!(Cookie::parse("novalue").is_err());
+  assert!(Cookie::parse("=value").is_err());
+}
+
+#[test]
+fn test_cookie_parse_attributes() {
+  let cookie = Cookie::parse("id=1; DOMAIN=.Example.COM; pAtH=/docs; SECURE; HttpOnly; samesite=strict").unwrap();
+  assert_eq!(&Some("example.com".to_string()), cookie.domain());
+  assert_eq!(&Some("/docs".to_string()), cookie.path());
+  assert!(cookie.secure());
+  assert!(cookie.http_only());
+  assert_eq!(&Some(SameSite::Strict), cookie.same_site());
+  assert!(!cookie.persistent());
+
+  // bad path and unknown same site are ignored
+  let cookie = Cookie::parse("id=1; Path=docs; SameSite=Unknown; Domain=").unwrap();
+  assert_eq!(&None, cookie.path());
+  assert_eq!(&None, cookie.same_site());
+  assert_eq!(&None, cookie.domain());
+
+  assert_eq!(Some(SameSite::None), SameSite::parse("None"));
+  assert_eq!("Lax", SameSite::Lax.to_string());
+}
+
+#[test]
+fn test_cookie_parse_expires() {
+  let expected = UNIX_EPOCH + Duration::from_secs(1445412480);
+  for date in ["Wed, 21 Oct 2015 07:28:00 GMT", "Wed, 21-Oct-2015 07:28:00 GMT", "Wednesday, 21-Oct-15 07:28:00 GMT"].iter() {
+    let cookie = Cookie::parse(format!("id=1; Expires={}", date)).unwrap();
+    assert_eq!(&Some(expected), cookie.expires(), "{}", date);
+    assert!(cookie.persistent());
+    assert!(cookie.is_expired());
+  }
+
+  // the bad date is an error, even max age is given
+  let err = Cookie::parse("id=1; Expires=tomorrow").unwrap_err();
+  assert!(err.to_string().contains("Bad cookie expires date: tomorrow"), "{}", err);
+  assert!(Cookie::parse("a=1; Expires=garbage; Max-Age=60").is_err());
+}
+
+#[test]
+fn test_cookie_parse_max_age() {
+  // max age take precedence over expires, whatever the order
+  let cookie = Cookie::parse("id=1; Max-Age=3600; Expires=Wed, 21 Oct 2015 07:28:00 GMT").unwrap();
+  let expires = cookie.expires().unwrap();
+  assert!(expires > SystemTime::now() + Duration::from_secs(3500));
+  assert!(!cookie.is_expired());
+
+  let cookie = Cookie::parse("id=1; Expires=Wed, 21 Oct 2100 07:28:00 GMT; max-age=0").unwrap();
+  assert!(cookie.is_expired());
+  let cookie = Cookie::parse("id=1; Max-Age=-1").unwrap();
+  assert!(cookie.is_expired());
+
+  // not a number is ignored
+  let cookie = Cookie::parse("id=1; Max-Age=abc").unwrap();
+  assert_eq!(&None, cookie.expires());
+
+  // the overflowed max age expires at the latest date
+  let cookie = Cookie::parse("a=1; Max-Age=9223372036854775807").unwrap();
+  assert_eq!(&Some(UNIX_EPOCH + Duration::from_secs(253_402_300_799)), cookie.expires());
+  assert!(cookie.string().contains("expires=Fri, 31 Dec 9999 23:59:59 GMT"), "{}", cookie.string());
+}
+
+#[test]
+fn test_cookie_string() {
+  let cookie = Cookie::parse("id=1; Path=/; Domain=example.com; Secure; HttpOnly; SameSite=Lax").unwrap();
+  let parsed = Cookie::parse(cookie.string()).unwrap();
+  assert_eq!(cookie.string(), parsed.string());
+  assert_eq!("id=1; path=/; domain=example.com; secure; httpOnly; SameSite=Lax", cookie.string());
+}
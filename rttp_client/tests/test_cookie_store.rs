@@ -0,0 +1,279 @@
+use std::time::{Duration, UNIX_EPOCH};
+
+use url::Url;
+
+use rttp_client::{Client, Config};
+use rttp_client::types::{Cookie, CookieStore, PublicSuffixList, RedirectPolicy, SameSite};
+
+mod support;
+
+fn url(text: &str) -> Url {
+  Url::parse(text).unwrap()
+}
+
+fn names(cookies: Vec<Cookie>) -> Vec<String> {
+  cookies.iter().map(|c| c.name().clone()).collect()
+}
+
+fn store_with(cookies: &[&str], from: &str) -> CookieStore {
+  let store = CookieStore::new();
+  for cookie in cookies {
+    assert!(store.insert(&Cookie::parse(cookie).unwrap(), &url(from)), "{}", cookie);
+  }
+  store
+}
+
+#[test]
+fn test_cookie_store_domain() {
+  let store = store_with(&["host=1", "sub=2; domain=.example.com"], "http://www.example.com/");
+  assert_eq!(vec!["host", "sub"], names(store.matches(&url("http://www.example.com/"))));
+  assert_eq!(vec!["sub"], names(store.matches(&url("http://api.example.com/"))));
+  assert_eq!(vec!["sub"], names(store.matches(&url("http://example.com/"))));
+  assert!(store.matches(&url("http://example.org/")).is_empty());
+  assert!(store.matches(&url("http://badexample.com/")).is_empty());
+
+  let cookies = store.cookies();
+  assert!(cookies[0].host_only());
+  assert_eq!(&Some("www.example.com".to_string()), cookies[0].domain());
+  assert!(!cookies[1].host_only());
+  assert_eq!(&Some("example.com".to_string()), cookies[1].domain());
+
+  // domain not match request host
+  let store = CookieStore::new();
+  assert!(!store.insert(&Cookie::parse("name=value; domain=example.org").unwrap(), &url("http://example.com/")));
+  assert!(!store.insert(&Cookie::parse("name=value; domain=www.example.com").unwrap(), &url("http://example.com/")));
+  assert!(!store.insert(&Cookie::parse("name=value; domain=0.0.1").unwrap(), &url("http://127.0.0.1/")));
+  assert!(store.is_empty());
+}
+
+#[test]
+fn test_cookie_store_path() {
+  let store = store_with(&["root=1; path=/", "a=2; path=/a", "default=3"], "http://example.com/a/b/page");
+  assert_eq!(&Some("/a/b".to_string()), store.cookies()[2].path());
+
+  // longer path first
+  assert_eq!(vec!["default", "a", "root"], names(store.matches(&url("http://example.com/a/b/c"))));
+  assert_eq!(vec!["a", "root"], names(store.matches(&url("http://example.com/a"))));
+  assert_eq!(vec!["a", "root"], names(store.matches(&url("http://example.com/a/"))));
+  assert_eq!(vec!["root"], names(store.matches(&url("http://example.com/ab"))));
+}
+
+#[test]
+fn test_cookie_store_secure_and_expires() {
+  let store = store_with(&["token=1; secure", "plain=2"], "https://example.com/");
+  assert_eq!(vec!["token", "plain"], names(store.matches(&url("https://example.com/"))));
+  assert_eq!(vec!["plain"], names(store.matches(&url("http://example.com/"))));
+
+  // replace the same cookie, and expired cookie remove it
+  store.insert(&Cookie::parse("plain=3").unwrap(), &url("https://example.com/"));
+  assert_eq!("3", store.cookies()[1].value());
+  store.insert(&Cookie::parse("plain=4; expires=Thu, 01 Jan 1970 00:00:00 GMT").unwrap(), &url("https://example.com/"));
+  assert_eq!(vec!["token"], names(store.cookies()));
+
+  store.remove("token", "example.com", "/");
+  assert!(store.is_empty());
+}
+
+/// `/set/<cookie>` set cookie of path `/`, `/redirect/<cookie>` set cookie and redirect to `/echo`,
+/// `/echo` answer the `Cookie` header.
+fn server() -> support::Server {
+  support::serve(|req| {
+    let path = req.uri_path().to_string();
+    if let Some(cookie) = path.strip_prefix("/set/") {
+      let cookie = format!("{}; path=/", cookie);
+      return support::response("200 OK", &[("Set-Cookie", &cookie)], b"");
+    }
+    if let Some(cookie) = path.strip_prefix("/redirect/") {
+      let cookie = format!("{}; path=/", cookie);
+      return support::response("302 Found", &[("Set-Cookie", &cookie), ("Location", "/echo")], b"");
+    }
+    let cookie = req.header("cookie").cloned().unwrap_or_default();
+    support::response("200 OK", &[], cookie.as_bytes())
+  })
+}
+
+fn client(store: &CookieStore) -> Client {
+  Client::builder()
+    .config(Config::builder().redirect(RedirectPolicy::limited(5)))
+    .cookie_store(store)
+    .build()
+}
+
+fn echo(client: &Client, url: String) -> String {
+  client.get().url(url).emit().expect("REQUEST FAIL").body().string().unwrap()
+}
+
+#[test]
+fn test_cookie_store_client() {
+  let server = server();
+  let store = CookieStore::new();
+  let client = client(&store);
+
+  client.get().url(server.url("/set/session=abc")).emit().expect("REQUEST FAIL");
+  client.get().url(server.url("/set/lang=en")).emit().expect("REQUEST FAIL");
+  assert_eq!(2, store.len());
+  assert_eq!("session=abc; lang=en", echo(&client, server.url("/echo")));
+
+  // cookies set by request come first
+  let text = client.get().url(server.url("/echo")).cookie("manual=1").emit()
+    .expect("REQUEST FAIL").body().string().unwrap();
+  assert_eq!("manual=1; session=abc; lang=en", text);
+
+  // the cookie is host only
+  let other = server.url("/echo").replace("127.0.0.1", "localhost");
+  assert_eq!("", echo(&client, other));
+
+  // client without cookie store
+  assert_eq!("", echo(&Client::new(), server.url("/echo")));
+}
+
+#[test]
+fn test_cookie_store_redirect() {
+  let server = server();
+  let store = CookieStore::new();
+  let client = client(&store);
+
+  let response = client.get().url(server.url("/redirect/login=ok")).emit().expect("REQUEST FAIL");
+  assert_eq!(2, response.history().len());
+  assert_eq!("login=ok", response.body().string().unwrap());
+}
+
+fn persisted_store() -> CookieStore {
+  let store = CookieStore::new();
+  let from = url("https://www.example.com/account/login");
+  let cookies = [
+    "session=abc; path=/; secure; httpOnly",
+    "lang=en; domain=example.com; expires=Fri, 01 Jan 2100 00:00:00 GMT; SameSite=Lax",
+    "page=2",
+  ];
+  for cookie in cookies.iter() {
+    store.insert(&Cookie::parse(cookie).unwrap(), &from);
+  }
+  store
+}
+
+fn flags(store: &CookieStore) -> Vec<String> {
+  store.cookies().iter()
+    .map(|c| format!("{}={} {:?} {:?} {:?} host_only={} secure={} http_only={}",
+                     c.name(), c.value(), c.domain(), c.path(), c.expires(),
+                     c.host_only(), c.secure(), c.http_only()))
+    .collect()
+}
+
+#[test]
+fn test_cookie_store_netscape() {
+  let store = persisted_store();
+  let mut text = Vec::new();
+  store.save_netscape(&mut text).unwrap();
+  let text = String::from_utf8(text).unwrap();
+  assert!(text.starts_with("# Netscape HTTP Cookie File\n"));
+  assert!(text.contains("#HttpOnly_www.example.com\tFALSE\t/\tTRUE\t0\tsession\tabc\n"));
+  assert!(text.contains(".example.com\tTRUE\t/account\tFALSE\t4102444800\tlang\ten\n"));
+  assert!(text.contains("www.example.com\tFALSE\t/account\tFALSE\t0\tpage\t2\n"));
+
+  let loaded = CookieStore::new();
+  loaded.load_netscape(text.as_bytes()).unwrap();
+  assert_eq!(flags(&store), flags(&loaded));
+  assert_eq!(vec!["lang", "page", "session"], names(loaded.matches(&url("https://www.example.com/account/a"))));
+  assert_eq!(vec!["lang"], names(loaded.matches(&url("http://api.example.com/account"))));
+}
+
+#[test]
+fn test_cookie_store_netscape_curl() {
+  // written by curl, the expired cookie is dropped
+  let text = "# Netscape HTTP Cookie File\n\
+    # https://curl.se/docs/http-cookies.html\n\
+    \n\
+    .example.com\tTRUE\t/\tFALSE\t0\tsession\t1\n\
+    example.com\tFALSE\t/\tFALSE\t1\texpired\t2\n\
+    #HttpOnly_example.com\tFALSE\t/api\tTRUE\t4102444800\ttoken\t\n";
+  let store = CookieStore::new();
+  store.load_netscape(text.as_bytes()).unwrap();
+  assert_eq!(vec!["session", "token"], names(store.cookies()));
+  let token = &store.cookies()[1];
+  assert!(token.http_only() && token.secure() && token.host_only());
+  assert_eq!("", token.value());
+
+  assert!(CookieStore::new().load_netscape("example.com\tFALSE\t/".as_bytes()).is_err());
+
+  // the expires out of time range is clamped to the latest date
+  let latest = Some(UNIX_EPOCH + Duration::from_secs(253_402_300_799));
+  let store = CookieStore::new();
+  store.load_netscape("example.com\tFALSE\t/\tFALSE\t18446744073709551615\tforever\t1\n".as_bytes()).unwrap();
+  assert_eq!(&latest, store.cookies()[0].expires());
+  let store = CookieStore::new();
+  store.load_json(r#"[{"name": "forever", "value": "1", "domain": "example.com", "expires": 18446744073709551615}]"#.as_bytes()).unwrap();
+  assert_eq!(&latest, store.cookies()[0].expires());
+}
+
+#[test]
+fn test_cookie_store_json() {
+  let store = persisted_store();
+  let mut text = Vec::new();
+  store.save_json(&mut text).unwrap();
+
+  let loaded = CookieStore::new();
+  loaded.load_json(&text[..]).unwrap();
+  assert_eq!(flags(&store), flags(&loaded));
+  assert_eq!(&Some(SameSite::Lax), loaded.cookies()[1].same_site());
+
+  let text = r#"[{"name": "old", "value": "1", "domain": "example.com", "path": "/", "expires": 1},
+    {"name": "new", "value": "2", "domain": "example.com", "path": "/", "expires": null, "host_only": true}]"#;
+  let store = CookieStore::new();
+  store.load_json(text.as_bytes()).unwrap();
+  assert_eq!(vec!["new"], names(store.cookies()));
+
+  assert!(CookieStore::new().load_json(r#"{"name": "a"}"#.as_bytes()).is_err());
+}
+
+#[test]
+fn test_public_suffix_list() {
+  let list = PublicSuffixList::embedded();
+  assert_eq!("com", list.public_suffix("www.example.com"));
+  assert_eq!("co.uk", list.public_suffix("evil.co.uk"));
+  assert_eq!("github.io", list.public_suffix("user.github.io"));
+  // wildcard and exception rules, `*.ck` and `!www.ck`
+  assert_eq!("example.ck", list.public_suffix("www.example.ck"));
+  assert_eq!("ck", list.public_suffix("www.ck"));
+  // internationalized rule, `公司.cn`
+  assert_eq!("xn--55qx5d.cn", list.public_suffix("example.xn--55qx5d.cn"));
+  // not listed, the last label
+  assert_eq!("unknown", list.public_suffix("example.unknown"));
+  assert!(list.is_public_suffix("co.uk."));
+  assert!(!list.is_public_suffix("example.co.uk"));
+}
+
+#[test]
+fn test_cookie_store_public_suffix() {
+  let store = CookieStore::new();
+  let from = url("http://evil.co.uk/");
+  assert!(!store.insert(&Cookie::parse("super=1; domain=co.uk").unwrap(), &from));
+  assert!(!store.insert(&Cookie::parse("super=1; domain=.uk").unwrap(), &from));
+  assert!(store.insert(&Cookie::parse("site=1; domain=evil.co.uk").unwrap(), &from));
+  assert!(store.matches(&url("http://good.co.uk/")).is_empty());
+  assert_eq!(vec!["site"], names(store.matches(&url("http://www.evil.co.uk/"))));
+
+  // the public suffix is the request host, the cookie is host only
+  let from = url("http://github.io/");
+  assert!(store.insert(&Cookie::parse("host=1; domain=github.io").unwrap(), &from));
+  assert!(store.cookies()[1].host_only());
+  assert!(store.matches(&url("http://user.github.io/")).is_empty());
+}
+
+#[test]
+fn test_cookie_store_custom_public_suffix() {
+  let path = std::env::temp_dir().join(format!("rttp_public_suffix_{}.dat", std::process::id()));
+  std::fs::write(&path, "// custom list\nexample\n*.wild.example\n!keep.wild.example\n").unwrap();
+
+  let store = CookieStore::new();
+  store.load_public_suffix_list(&path).unwrap();
+  std::fs::remove_file(&path).unwrap();
+
+  assert!(!store.insert(&Cookie::parse("a=1; domain=example").unwrap(), &url("http://a.example/")));
+  assert!(!store.insert(&Cookie::parse("a=1; domain=x.wild.example").unwrap(), &url("http://a.x.wild.example/")));
+  assert!(store.insert(&Cookie::parse("a=1; domain=keep.wild.example").unwrap(), &url("http://a.keep.wild.example/")));
+  // `co.uk` is not in the custom list
+  assert!(store.insert(&Cookie::parse("a=1; domain=co.uk").unwrap(), &url("http://evil.co.uk/")));
+
+  assert!(CookieStore::new().load_public_suffix_list("/not/exists/public_suffix_list.dat").is_err());
+}
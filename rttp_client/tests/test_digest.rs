@@ -0,0 +1,219 @@
+use std::sync::Mutex;
+
+use md5::Md5;
+use sha2::{Digest, Sha256};
+
+use rttp_client::{Client, HttpClient};
+use rttp_client::types::{Auth, FormData};
+
+mod support;
+
+const USERNAME: &str = "Mufasa";
+const PASSWORD: &str = "Circle of Life";
+const REALM: &str = "http-auth@example.org";
+
+fn hash(algorithm: &str, data: String) -> String {
+  let bytes = if algorithm.starts_with("SHA-256") {
+    Sha256::digest(data.as_bytes()).to_vec()
+  } else {
+    Md5::digest(data.as_bytes()).to_vec()
+  };
+  bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Params of `Authorization: Digest ...` header, the quoted values not contain comma in these tests.
+fn params(authorization: &str) -> Vec<(String, String)> {
+  authorization.trim_start_matches("Digest ")
+    .split(", ")
+    .filter_map(|kv| kv.split_once('='))
+    .map(|(k, v)| (k.to_string(), v.trim_matches('"').to_string()))
+    .collect()
+}
+
+fn param<'a>(params: &'a [(String, String)], name: &str) -> &'a str {
+  params.iter().find(|(k, _)| k == name).map_or("", |(_, v)| &v[..])
+}
+
+/// A server protected by digest auth, the nonces in `stale` are answered with `stale=true`.
+struct DigestServer {
+  algorithm: &'static str,
+  qop: Option<&'static str>,
+  nonce: Mutex<String>,
+  stale: Mutex<Vec<String>>,
+  ncs: Mutex<Vec<String>>,
+}
+
+impl DigestServer {
+  fn new(algorithm: &'static str, qop: Option<&'static str>) -> Self {
+    Self {
+      algorithm,
+      qop,
+      nonce: Mutex::new("dcd98b7102dd2f0e8b11d0f600bfb0c093".to_string()),
+      stale: Mutex::new(vec![]),
+      ncs: Mutex::new(vec![]),
+    }
+  }
+
+  fn challenge(&self, stale: bool) -> String {
+    let mut challenge = format!(r#"Basic realm="basic", Digest realm="{}", nonce="{}", opaque="5ccc069c403ebaf9f0171e9517f40e41", algorithm={}"#,
+                                REALM, self.nonce.lock().unwrap(), self.algorithm);
+    if let Some(qop) = self.qop {
+      challenge.push_str(&format!(r#", qop="{}""#, qop));
+    }
+    if stale {
+      challenge.push_str(", stale=true");
+    }
+    challenge
+  }
+
+  fn answer(&self, req: &support::Incoming) -> Vec<u8> {
+    let authorization = match req.header("authorization") {
+      Some(authorization) => authorization,
+      None => return support::response("401 Unauthorized", &[("WWW-Authenticate", &self.challenge(false))], b""),
+    };
+    let params = params(authorization);
+    let nonce = param(&params, "nonce");
+    if self.stale.lock().unwrap().iter().any(|v| v == nonce) {
+      return support::response("401 Unauthorized", &[("WWW-Authenticate", &self.challenge(true))], b"");
+    }
+
+    let algorithm = self.algorithm;
+    let (nc, cnonce, qop) = (param(&params, "nc"), param(&params, "cnonce"), param(&params, "qop"));
+    let mut ha1 = hash(algorithm, format!("{}:{}:{}", USERNAME, REALM, PASSWORD));
+    if algorithm.ends_with("-sess") {
+      ha1 = hash(algorithm, format!("{}:{}:{}", ha1, nonce, cnonce));
+    }
+    let ha2 = if qop == "auth-int" {
+      let body = String::from_utf8_lossy(&req.body).to_string();
+      hash(algorithm, format!("{}:{}:{}", req.method, req.path, hash(algorithm, body)))
+    } else {
+      hash(algorithm, format!("{}:{}", req.method, req.path))
+    };
+    let expected = if qop.is_empty() {
+      hash(algorithm, format!("{}:{}:{}", ha1, nonce, ha2))
+    } else {
+      hash(algorithm, format!("{}:{}:{}:{}:{}:{}", ha1, nonce, nc, cnonce, qop, ha2))
+    };
+
+    let valid = param(&params, "username") == USERNAME &&
+      param(&params, "uri") == req.path &&
+      nonce == *self.nonce.lock().unwrap() &&
+      param(&params, "response") == expected;
+    if !valid {
+      return support::response("401 Unauthorized", &[("WWW-Authenticate", &self.challenge(false))], b"");
+    }
+    self.ncs.lock().unwrap().push(nc.to_string());
+    support::response("200 OK", &[], format!("{} {}", param(&params, "algorithm"), qop).as_bytes())
+  }
+}
+
+fn serve(server: DigestServer) -> (support::Server, std::sync::Arc<DigestServer>) {
+  let digest = std::sync::Arc::new(server);
+  let handler = digest.clone();
+  (support::serve(move |req| handler.answer(req)), digest)
+}
+
+fn send(client: &mut HttpClient) -> (u32, String) {
+  let response = client.emit().expect("REQUEST FAIL");
+  (response.code(), response.body().string().unwrap())
+}
+
+#[test]
+fn test_digest_algorithms() {
+  let cases = [
+    ("MD5", None, "MD5 "),
+    ("MD5", Some("auth"), "MD5 auth"),
+    ("MD5-sess", Some("auth,auth-int"), "MD5-sess auth"),
+    ("SHA-256", Some("auth"), "SHA-256 auth"),
+    ("SHA-256-sess", Some("auth"), "SHA-256-sess auth"),
+  ];
+  for (algorithm, qop, expected) in cases.iter() {
+    let (server, _) = serve(DigestServer::new(algorithm, *qop));
+    let (code, text) = send(HttpClient::new()
+      .get()
+      .url(server.url("/dir/index.html?a=b"))
+      .auth(Auth::digest(USERNAME, PASSWORD)));
+    assert_eq!(200, code, "{}", algorithm);
+    assert_eq!(*expected, text);
+    assert_eq!(2, server.hits());
+  }
+}
+
+#[test]
+fn test_digest_auth_int() {
+  let (server, _) = serve(DigestServer::new("MD5", Some("auth-int")));
+  let (code, text) = send(HttpClient::new()
+    .post()
+    .url(server.url("/post"))
+    .raw("hello digest")
+    .auth(Auth::digest(USERNAME, PASSWORD)));
+  assert_eq!(200, code);
+  assert_eq!("MD5 auth-int", text);
+}
+
+#[test]
+fn test_digest_auth_int_unhashable_body() {
+  let path = std::env::temp_dir().join(format!("rttp_digest_{}.txt", std::process::id()));
+  std::fs::write(&path, "hello digest").unwrap();
+  let post = |server: &support::Server| {
+    HttpClient::new()
+      .post()
+      .url(server.url("/post"))
+      .form(FormData::with_file("file", &path))
+      .auth(Auth::digest(USERNAME, PASSWORD))
+      .emit()
+  };
+
+  // the file is read while sending, it can't be hashed
+  let (server, _) = serve(DigestServer::new("MD5", Some("auth-int")));
+  let err = post(&server).expect_err("AUTH INT");
+  assert!(err.is_builder(), "{}", err);
+  assert_eq!(1, server.hits());
+
+  let (server, _) = serve(DigestServer::new("MD5", Some("auth-int, auth")));
+  let response = post(&server).expect("REQUEST FAIL");
+  assert_eq!("MD5 auth", response.body().string().unwrap());
+  std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_digest_bad_password() {
+  let (server, _) = serve(DigestServer::new("MD5", Some("auth")));
+  let (code, _) = send(HttpClient::new()
+    .get()
+    .url(server.url("/"))
+    .auth(Auth::digest(USERNAME, "bad password")));
+  assert_eq!(401, code);
+  assert_eq!(2, server.hits());
+}
+
+#[test]
+fn test_digest_nonce_cache() {
+  let (server, digest) = serve(DigestServer::new("SHA-256", Some("auth")));
+  let client = Client::new();
+  for _ in 0..3 {
+    let (code, _) = send(client.get().url(server.url("/")).auth(Auth::digest(USERNAME, PASSWORD)));
+    assert_eq!(200, code);
+  }
+  // only the first request is challenged
+  assert_eq!(4, server.hits());
+  assert_eq!(vec!["00000001", "00000002", "00000003"], *digest.ncs.lock().unwrap());
+}
+
+#[test]
+fn test_digest_stale_nonce() {
+  let (server, digest) = serve(DigestServer::new("MD5", Some("auth")));
+  let client = Client::new();
+  let (code, _) = send(client.get().url(server.url("/")).auth(Auth::digest(USERNAME, PASSWORD)));
+  assert_eq!(200, code);
+
+  let old = digest.nonce.lock().unwrap().clone();
+  digest.stale.lock().unwrap().push(old);
+  *digest.nonce.lock().unwrap() = "0a4f113b".to_string();
+
+  let (code, _) = send(client.get().url(server.url("/")).auth(Auth::digest(USERNAME, PASSWORD)));
+  assert_eq!(200, code);
+  // the cached nonce is stale, answer the new one
+  assert_eq!(4, server.hits());
+  assert_eq!(vec!["00000001", "00000001"], *digest.ncs.lock().unwrap());
+}
@@ -0,0 +1,289 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use sha2::{Digest, Sha256};
+
+use rttp_client::HttpClient;
+
+mod support;
+
+fn pattern(len: usize) -> Vec<u8> {
+  (0..len).map(|i| (i % 251) as u8).collect()
+}
+
+fn temp_path(name: &str) -> PathBuf {
+  let path = std::env::temp_dir().join(format!("rttp_download_{}_{}", std::process::id(), name));
+  clean(&path);
+  path
+}
+
+fn part_path(path: &Path) -> PathBuf {
+  PathBuf::from(format!("{}.part", path.display()))
+}
+
+fn clean(path: &Path) {
+  let _ = fs::remove_file(path);
+  let _ = fs::remove_file(part_path(path));
+  let _ = fs::remove_file(format!("{}.part.if-range", path.display()));
+}
+
+#[test]
+fn test_download_file() {
+  let body = pattern(300 * 1024);
+  let expected = body.clone();
+  let encodings = Arc::new(Mutex::new(vec![]));
+  let received = encodings.clone();
+  let server = support::serve(move |req| {
+    received.lock().unwrap().push(req.header("accept-encoding").cloned());
+    support::ranged(req, &[("ETag", "\"v1\"")], &body)
+  });
+
+  let path = temp_path("file");
+  let mut progress = vec![];
+  let size = HttpClient::new()
+    .url(server.url("/file"))
+    .download(&path)
+    .progress(|done, total| progress.push((done, total)))
+    .emit()
+    .expect("DOWNLOAD FAIL");
+  assert_eq!(expected.len() as u64, size);
+  assert!(expected == fs::read(&path).unwrap());
+  assert!(!part_path(&path).exists());
+  assert_eq!(Some(&(0, Some(size))), progress.first());
+  assert_eq!(Some(&(size, Some(size))), progress.last());
+  assert!(progress.windows(2).all(|w| w[0].0 <= w[1].0));
+  assert_eq!(vec![Some("identity".to_string())], *encodings.lock().unwrap());
+  clean(&path);
+}
+
+#[test]
+fn test_download_resume() {
+  let body = pattern(200 * 1024);
+  let expected = body.clone();
+  let ranges = Arc::new(Mutex::new(vec![]));
+  let received = ranges.clone();
+  let hits = AtomicUsize::new(0);
+  let server = support::serve(move |req| {
+    received.lock().unwrap().push((req.header("range").cloned(), req.header("if-range").cloned()));
+    let headers = [("ETag", "\"v1\"")];
+    if hits.fetch_add(1, Ordering::SeqCst) == 0 {
+      // the connection is closed after half of the body
+      let mut binary = support::response("200 OK", &[("ETag", "\"v1\""), ("Connection", "close")], &body);
+      binary.truncate(binary.len() - body.len() / 2);
+      return binary;
+    }
+    support::ranged(req, &headers, &body)
+  });
+
+  let path = temp_path("resume");
+  let err = HttpClient::new().url(server.url("/file")).download(&path).emit().expect_err("INCOMPLETE DOWNLOAD");
+  assert!(!path.exists());
+  let downloaded = fs::metadata(part_path(&path)).unwrap().len();
+  assert!(downloaded > 0 && downloaded < expected.len() as u64, "{:?}", err);
+
+  let mut progress = vec![];
+  let size = HttpClient::new()
+    .url(server.url("/file"))
+    .download(&path)
+    .progress(|done, total| progress.push((done, total)))
+    .emit()
+    .expect("DOWNLOAD FAIL");
+  assert_eq!(expected.len() as u64, size);
+  assert!(expected == fs::read(&path).unwrap());
+  assert_eq!(Some(&(downloaded, Some(size))), progress.first());
+  let ranges = ranges.lock().unwrap();
+  assert_eq!((None, None), ranges[0]);
+  assert_eq!((Some(format!("bytes={}-", downloaded)), Some("\"v1\"".to_string())), ranges[1]);
+  clean(&path);
+}
+
+#[test]
+fn test_download_restart() {
+  let body = pattern(64 * 1024);
+  let expected = body.clone();
+  let server = support::serve(move |req| support::ranged(req, &[("ETag", "\"v2\"")], &body));
+
+  // the file is changed on server, the partial data is dropped
+  let path = temp_path("restart");
+  fs::write(part_path(&path), b"stale data").unwrap();
+  fs::write(format!("{}.part.if-range", path.display()), "\"v1\"").unwrap();
+  let size = HttpClient::new().url(server.url("/file")).download(&path).emit().expect("DOWNLOAD FAIL");
+  assert_eq!(expected.len() as u64, size);
+  assert!(expected == fs::read(&path).unwrap());
+
+  // the part file is as long as the file, `416` is answered
+  fs::write(part_path(&path), &expected).unwrap();
+  fs::write(format!("{}.part.if-range", path.display()), "\"v2\"").unwrap();
+  let size = HttpClient::new().url(server.url("/file")).download(&path).emit().expect("DOWNLOAD FAIL");
+  assert_eq!(expected.len() as u64, size);
+  assert!(expected == fs::read(&path).unwrap());
+
+  // not resume, the part file is ignored
+  fs::write(part_path(&path), b"stale data").unwrap();
+  fs::write(format!("{}.part.if-range", path.display()), "\"v2\"").unwrap();
+  let size = HttpClient::new().url(server.url("/file")).download(&path).resume(false).emit().expect("DOWNLOAD FAIL");
+  assert_eq!(expected.len() as u64, size);
+  assert!(expected == fs::read(&path).unwrap());
+  clean(&path);
+}
+
+#[test]
+fn test_download_sha256() {
+  let body = pattern(10 * 1024);
+  let sha256: String = Sha256::digest(&body).iter().map(|b| format!("{:02x}", b)).collect();
+  let server = support::serve(move |req| support::ranged(req, &[], &body));
+
+  let path = temp_path("sha256");
+  let size = HttpClient::new()
+    .url(server.url("/file"))
+    .download(&path)
+    .sha256(sha256.to_ascii_uppercase())
+    .emit()
+    .expect("DOWNLOAD FAIL");
+  assert_eq!(10 * 1024, size);
+  clean(&path);
+
+  let err = HttpClient::new()
+    .url(server.url("/file"))
+    .download(&path)
+    .sha256("00")
+    .emit()
+    .expect_err("DIGEST MISMATCH");
+  assert!(err.to_string().contains("digest mismatch"), "{}", err);
+  assert!(!path.exists());
+  assert!(!part_path(&path).exists());
+}
+
+#[test]
+fn test_download_status() {
+  let server = support::serve(|_| support::response("404 Not Found", &[], b"not found"));
+  let path = temp_path("status");
+  let err = HttpClient::new().url(server.url("/file")).download(&path).emit().expect_err("STATUS ERROR");
+  assert_eq!(Some(404), err.status().map(|status| status.as_u16()));
+  assert!(!path.exists());
+  assert!(!part_path(&path).exists());
+}
+
+#[test]
+fn test_segmented_download() {
+  let body = pattern(1024 * 1024 + 3);
+  let expected = body.clone();
+  let requests = Arc::new(Mutex::new(vec![]));
+  let received = requests.clone();
+  let server = support::serve(move |req| {
+    received.lock().unwrap().push((req.method.clone(), req.header("range").cloned(), req.header("if-range").cloned()));
+    support::ranged(req, &[("ETag", "\"v1\""), ("Accept-Ranges", "bytes")], &body)
+  });
+
+  let path = temp_path("segmented");
+  let mut progress = vec![];
+  let size = HttpClient::new()
+    .url(server.url("/file"))
+    .segmented_download(&path)
+    .segments(4)
+    .progress(|done, total| progress.push((done, total)))
+    .emit()
+    .expect("DOWNLOAD FAIL");
+  assert_eq!(expected.len() as u64, size);
+  assert!(expected == fs::read(&path).unwrap());
+  assert!(!part_path(&path).exists());
+  assert_eq!(Some(&(size, Some(size))), progress.last());
+
+  let requests = requests.lock().unwrap();
+  assert_eq!(5, requests.len());
+  assert_eq!(("HEAD".to_string(), None, None), requests[0]);
+  let mut ranges: Vec<String> = requests[1..].iter().map(|(_, range, _)| range.clone().unwrap()).collect();
+  ranges.sort_by_key(|range| range[6..].split('-').next().unwrap().parse::<u64>().unwrap());
+  assert_eq!(vec!["bytes=0-262144", "bytes=262145-524289", "bytes=524290-786434", "bytes=786435-1048578"], ranges);
+  assert!(requests[1..].iter().all(|(method, _, if_range)| method == "GET" && if_range.as_deref() == Some("\"v1\"")));
+  clean(&path);
+}
+
+#[test]
+fn test_segmented_download_retry() {
+  let body = pattern(256 * 1024);
+  let expected = body.clone();
+  let failures = AtomicUsize::new(0);
+  let ranges = Arc::new(Mutex::new(vec![]));
+  let received = ranges.clone();
+  let server = support::serve(move |req| {
+    received.lock().unwrap().extend(req.header("range").cloned());
+    let headers = [("Accept-Ranges", "bytes")];
+    if req.method == "GET" && failures.fetch_add(1, Ordering::SeqCst) < 2 {
+      // the connection is closed before the range is complete
+      let mut binary = support::ranged(req, &[headers[0], ("Connection", "close")], &body);
+      binary.truncate(binary.len() - 1000);
+      return binary;
+    }
+    support::ranged(req, &headers, &body)
+  });
+
+  let path = temp_path("segmented_retry");
+  let size = HttpClient::new()
+    .url(server.url("/file"))
+    .segmented_download(&path)
+    .segments(2)
+    .emit()
+    .expect("DOWNLOAD FAIL");
+  assert_eq!(expected.len() as u64, size);
+  assert!(expected == fs::read(&path).unwrap());
+  // the retries continue from where the segments stopped
+  let ranges = ranges.lock().unwrap();
+  assert_eq!(4, ranges.len());
+  assert!(ranges.contains(&format!("bytes={}-131071", 131072 - 1000)));
+  assert!(ranges.contains(&format!("bytes={}-262143", 262144 - 1000)));
+  clean(&path);
+
+  let server = support::serve(|req| {
+    let mut binary = support::ranged(req, &[("Accept-Ranges", "bytes"), ("Connection", "close")], &pattern(1024));
+    if req.method == "GET" {
+      binary.truncate(binary.len() - 1);
+    }
+    binary
+  });
+  let err = HttpClient::new()
+    .url(server.url("/file"))
+    .segmented_download(&path)
+    .retries(1)
+    .emit()
+    .expect_err("SEGMENT FAIL");
+  assert!(!path.exists(), "{}", err);
+  assert!(!part_path(&path).exists());
+  assert_eq!(1 + 4 * 2, server.hits());
+}
+
+#[test]
+fn test_segmented_download_fallback() {
+  let body = pattern(100 * 1024);
+  let expected = body.clone();
+  let server = support::serve(move |req| match req.header("range") {
+    Some(_) => support::response("500 Internal Server Error", &[], b""),
+    None => support::ranged(req, &[], &body),
+  });
+
+  let path = temp_path("segmented_fallback");
+  let size = HttpClient::new().url(server.url("/file")).segmented_download(&path).emit().expect("DOWNLOAD FAIL");
+  assert_eq!(expected.len() as u64, size);
+  assert!(expected == fs::read(&path).unwrap());
+  assert_eq!(2, server.hits());
+  clean(&path);
+
+  // the file is changed after `HEAD`, the whole file is answered to `If-Range`
+  let body = pattern(100 * 1024);
+  let server = support::serve(move |req| {
+    let etag = if req.method == "HEAD" { "\"v1\"" } else { "\"v2\"" };
+    support::ranged(req, &[("ETag", etag), ("Accept-Ranges", "bytes")], &body)
+  });
+  let err = HttpClient::new()
+    .url(server.url("/file"))
+    .segmented_download(&path)
+    .retries(0)
+    .emit()
+    .expect_err("FILE CHANGED");
+  assert!(err.to_string().contains("Unexpected response of range"), "{}", err);
+  assert!(!path.exists());
+  assert!(!part_path(&path).exists());
+}
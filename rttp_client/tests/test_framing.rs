@@ -0,0 +1,108 @@
+use rttp_client::{Client, Config};
+
+mod support;
+
+fn client() -> Client {
+  Client::builder()
+    .config(Config::builder().read_timeout(3000))
+    .build()
+}
+
+#[test]
+fn test_framing_content_length() {
+  let server = support::serve_text("HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nhello");
+  let response = client().get().url(server.url("/")).emit().expect("REQUEST FAIL");
+  assert_eq!(200, response.code());
+  assert_eq!("hello", response.body().string().unwrap());
+}
+
+#[test]
+fn test_framing_chunked() {
+  let server = support::serve_text("HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhello\r\n0\r\n\r\n");
+  let response = client().get().url(server.url("/")).emit().expect("REQUEST FAIL");
+  assert_eq!(200, response.code());
+  assert_eq!("hello", response.body().string().unwrap());
+  assert_eq!(1, server.connections());
+}
+
+#[test]
+fn test_framing_chunked_trailers() {
+  let server = support::serve_text("HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n\
+    3;ext=1\r\nabc\r\n3\r\ndef\r\n0\r\nX-Checksum: 42\r\n\r\n");
+  let client = client();
+  for _ in 0..2 {
+    let response = client.get().url(server.url("/")).emit().expect("REQUEST FAIL");
+    assert_eq!("abcdef", response.body().string().unwrap());
+    assert_eq!(Some(&"42".to_string()), response.trailer_value("x-checksum"));
+  }
+  assert_eq!(1, server.connections());
+}
+
+#[test]
+fn test_framing_read_to_close() {
+  let server = support::serve_text("HTTP/1.1 200 OK\r\nConnection: close\r\n\r\nuntil close");
+  let client = client();
+  let response = client.get().url(server.url("/")).emit().expect("REQUEST FAIL");
+  assert_eq!("until close", response.body().string().unwrap());
+  assert_eq!(0, client.idle_connections());
+}
+
+#[test]
+fn test_framing_no_body_status() {
+  let server = support::serve(|req| {
+    match req.path.rsplit('/').next().unwrap_or_default() {
+      "204" => b"HTTP/1.1 204 No Content\r\n\r\n".to_vec(),
+      "304" => b"HTTP/1.1 304 Not Modified\r\nContent-Length: 10\r\n\r\n".to_vec(),
+      _ => support::response("200 OK", &[], b"ok"),
+    }
+  });
+  let client = client();
+  let response = client.get().url(server.url("/204")).emit().expect("REQUEST FAIL");
+  assert_eq!(204, response.code());
+  assert!(response.body().binary().is_empty());
+  let response = client.get().url(server.url("/304")).emit().expect("REQUEST FAIL");
+  assert_eq!(304, response.code());
+  assert!(response.body().binary().is_empty());
+  let response = client.get().url(server.url("/")).emit().expect("REQUEST FAIL");
+  assert_eq!("ok", response.body().string().unwrap());
+  assert_eq!(1, server.connections());
+}
+
+#[test]
+fn test_framing_skip_continue() {
+  let server = support::serve_text("HTTP/1.1 100 Continue\r\n\r\nHTTP/1.1 201 Created\r\nContent-Length: 4\r\n\r\ndone");
+  let response = client().post().url(server.url("/")).emit().expect("REQUEST FAIL");
+  assert_eq!(201, response.code());
+  assert_eq!("done", response.body().string().unwrap());
+}
+
+#[test]
+fn test_framing_large_body() {
+  let body = "x".repeat(64 * 1024);
+  let server = support::serve(move |_| support::response("200 OK", &[], body.as_bytes()));
+  let response = client().get().url(server.url("/")).emit().expect("REQUEST FAIL");
+  assert_eq!(64 * 1024, response.body().binary().len());
+}
+
+#[test]
+fn test_framing_truncated_body() {
+  let server = support::serve_text("HTTP/1.1 200 OK\r\nContent-Length: 10\r\nX-Test-Close: true\r\n\r\nshort");
+  assert!(client().get().url(server.url("/")).emit().is_err());
+}
+
+#[test]
+fn test_framing_bad_content_length() {
+  let server = support::serve_text("HTTP/1.1 200 OK\r\nContent-Length: abc\r\n\r\nhello");
+  assert!(client().get().url(server.url("/")).emit().is_err());
+}
+
+#[test]
+fn test_framing_huge_length() {
+  let server = support::serve_text("HTTP/1.1 200 OK\r\nContent-Length: 18446744073709551615\r\nX-Test-Close: true\r\n\r\nshort");
+  let err = client().get().url(server.url("/")).emit().expect_err("HUGE LENGTH");
+  assert!(err.to_string().contains("before body end"), "{}", err);
+
+  let server = support::serve_text("HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\nX-Test-Close: true\r\n\r\n\
+    ffffffffffffffff\r\nshort");
+  assert!(client().get().url(server.url("/")).emit().is_err());
+}
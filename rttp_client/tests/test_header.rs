@@ -0,0 +1,138 @@
+use rttp_client::{Client, HttpClient};
+use rttp_client::types::{Auth, Header, HeaderMap};
+
+mod support;
+
+#[test]
+fn test_header_map() {
+  let mut headers = HeaderMap::new();
+  headers.append("Accept", "text/html")
+    .append("X-Token", "a")
+    .append("accept", "application/json");
+  assert_eq!(3, headers.len());
+  assert!(headers.contains("ACCEPT"));
+  assert_eq!(Some(&"text/html".to_string()), headers.get("accept"));
+  assert_eq!(vec!["text/html", "application/json"], headers.get_all("Accept"));
+
+  // replaced at the position of the first one, with the new casing
+  assert_eq!(Some("text/html".to_string()), headers.insert("ACCEPT", "*/*"));
+  let lines: Vec<String> = headers.iter().map(|h| format!("{}: {}", h.name(), h.value())).collect();
+  assert_eq!(vec!["ACCEPT: */*", "X-Token: a"], lines);
+
+  assert_eq!(vec!["a"], headers.remove("x-token"));
+  assert!(headers.remove("x-token").is_empty());
+  assert_eq!(None, headers.insert("Host", "example.com"));
+  let names: Vec<&String> = headers.iter().map(|h| h.name()).collect();
+  assert_eq!(vec!["ACCEPT", "Host"], names);
+}
+
+#[test]
+fn test_header_send_multiple() {
+  let server = support::serve(|req| {
+    let values: Vec<String> = req.headers_of("accept").into_iter().cloned().collect();
+    support::response("200 OK", &[], values.join("|").as_bytes())
+  });
+
+  let response = HttpClient::new()
+    .get()
+    .url(server.url("/"))
+    .header(("Accept", "text/plain"))
+    .header(("Accept", "text/html"))
+    .header_append(("accept", "application/json"))
+    .emit()
+    .expect("REQUEST FAIL");
+  assert_eq!("text/html|application/json", response.body().string().unwrap());
+
+  // the same name in one call are all kept
+  let response = HttpClient::new()
+    .get()
+    .url(server.url("/"))
+    .header("Accept: text/html\nAccept: application/json")
+    .emit()
+    .expect("REQUEST FAIL");
+  assert_eq!("text/html|application/json", response.body().string().unwrap());
+}
+
+#[test]
+fn test_header_client_default() {
+  let server = support::serve(|req| {
+    let values: Vec<String> = req.headers_of("x-forwarded-for").into_iter().cloned().collect();
+    support::response("200 OK", &[], values.join("|").as_bytes())
+  });
+  let client = Client::builder()
+    .header(("X-Forwarded-For", "10.0.0.1"))
+    .header_append(Header::new("X-Forwarded-For", "10.0.0.2"))
+    .build();
+  assert_eq!(2, client.headers().get_all("x-forwarded-for").len());
+
+  let response = client.get().url(server.url("/")).emit().expect("REQUEST FAIL");
+  assert_eq!("10.0.0.1|10.0.0.2", response.body().string().unwrap());
+
+  let response = client.get()
+    .url(server.url("/"))
+    .header(("X-Forwarded-For", "10.0.0.3"))
+    .emit()
+    .expect("REQUEST FAIL");
+  assert_eq!("10.0.0.3", response.body().string().unwrap());
+}
+
+#[test]
+fn test_header_response_multiple() {
+  let server = support::serve(|_| support::response("200 OK", &[
+    ("Vary", "Accept"),
+    ("X-Trace", "a"),
+    ("vary", "Accept-Encoding"),
+  ], b"ok"));
+  let response = HttpClient::new().get().url(server.url("/")).emit().expect("REQUEST FAIL");
+  let headers = response.headers();
+  assert_eq!(vec!["Accept", "Accept-Encoding"], headers.get_all("VARY"));
+  assert_eq!(Some(&"a".to_string()), headers.get("x-trace"));
+  let names: Vec<&String> = headers.iter().map(|h| h.name()).filter(|n| n.eq_ignore_ascii_case("vary")).collect();
+  assert_eq!(vec!["Vary", "vary"], names);
+}
+
+#[test]
+fn test_header_invalid() {
+  let server = support::serve(|_| support::response("200 OK", &[], b"ok"));
+  let invalids = vec![
+    Header::new("X Token", "a"),
+    Header::new("X:Token", "a"),
+    Header::new("X-Tökén", "a"),
+    Header::new("X-Token", "a\nInjected: true"),
+    Header::new("X-Token", "a\rInjected: true"),
+    Header::new("X-Token", "a\0b"),
+  ];
+  for header in invalids {
+    let result = HttpClient::new().get().url(server.url("/")).header(header).emit();
+    assert!(result.expect_err("INVALID HEADER SENT").is_builder());
+  }
+
+  let result = Client::builder().header(Header::new("X-Token", "a\r\nb")).build()
+    .get().url(server.url("/")).emit();
+  assert!(result.expect_err("INVALID HEADER SENT").is_builder());
+  assert_eq!(0, server.hits());
+
+  let response = HttpClient::new().get().url(server.url("/")).header(("X-Token", "a\tb c")).emit();
+  assert!(response.is_ok());
+}
+
+#[test]
+fn test_header_invalid_method_and_path() {
+  let server = support::serve(|_| support::response("200 OK", &[], b"ok"));
+  for method in &["", "GET /admin HTTP/1.1\r\nHost: a\r\n\r\nGET", "GE T", "GET\0"] {
+    let result = HttpClient::new().method(method).url(server.url("/")).emit();
+    assert!(result.expect_err("INVALID METHOD SENT").is_builder(), "{:?}", method);
+  }
+  for path in &["a\r\nInjected: true", "a\nb", "a\0b"] {
+    let result = HttpClient::new().get().url(server.url("/")).path(path).emit();
+    assert!(result.expect_err("INVALID PATH SENT").is_builder(), "{:?}", path);
+  }
+  let result = HttpClient::new().get().url(server.url("/"))
+    .auth(Auth::bearer("token\r\nInjected: true"))
+    .emit();
+  assert!(result.expect_err("INVALID AUTH SENT").is_builder());
+  assert_eq!(0, server.hits());
+
+  let response = HttpClient::new().method("PROPFIND").url(server.url("/")).path("a b").emit();
+  assert!(response.is_ok());
+}
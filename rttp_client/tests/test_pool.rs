@@ -0,0 +1,168 @@
+use rttp_client::{Client, Config};
+
+mod support;
+
+fn client() -> Client {
+  Client::builder()
+    .config(Config::builder().read_timeout(3000))
+    .build()
+}
+
+#[test]
+fn test_keep_alive_reuse() {
+  let server = support::serve(|req| {
+    let connection = req.header("connection").cloned().unwrap_or_default();
+    support::response("200 OK", &[], connection.as_bytes())
+  });
+  let client = client();
+  for _ in 0..5 {
+    let response = client.get().url(server.url("/")).emit().expect("REQUEST FAIL");
+    assert_eq!("keep-alive", response.body().string().unwrap());
+  }
+  assert_eq!(5, server.hits());
+  assert_eq!(1, server.connections());
+  assert_eq!(1, client.idle_connections());
+}
+
+#[test]
+fn test_keep_alive_chunked() {
+  let server = support::serve(|_| {
+    b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhello\r\n0\r\n\r\n".to_vec()
+  });
+  let client = client();
+  for _ in 0..3 {
+    assert!(client.get().url(server.url("/")).emit().is_ok());
+  }
+  assert_eq!(3, server.hits());
+  assert_eq!(1, server.connections());
+}
+
+#[test]
+fn test_keep_alive_head() {
+  let server = support::serve(|req| {
+    if req.method == "HEAD" {
+      return b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\n".to_vec();
+    }
+    support::response("200 OK", &[], b"hello")
+  });
+  let client = client();
+  let response = client.head().url(server.url("/")).emit().expect("REQUEST FAIL");
+  assert_eq!(200, response.code());
+  let response = client.get().url(server.url("/")).emit().expect("REQUEST FAIL");
+  assert_eq!("hello", response.body().string().unwrap());
+  assert_eq!(1, server.connections());
+}
+
+#[test]
+fn test_server_close() {
+  let server = support::serve(|_| support::response("200 OK", &[("Connection", "close")], b"bye"));
+  let client = client();
+  for _ in 0..3 {
+    assert!(client.get().url(server.url("/")).emit().is_ok());
+  }
+  assert_eq!(3, server.connections());
+  assert_eq!(0, client.idle_connections());
+}
+
+#[test]
+fn test_pool_disabled() {
+  let server = support::serve(|req| {
+    let connection = req.header("connection").cloned().unwrap_or_default();
+    support::response("200 OK", &[], connection.as_bytes())
+  });
+  let client = Client::builder()
+    .pool_max_idle_per_host(0)
+    .build();
+  for _ in 0..3 {
+    let response = client.get().url(server.url("/")).emit().expect("REQUEST FAIL");
+    assert_eq!("Close", response.body().string().unwrap());
+  }
+  assert_eq!(3, server.connections());
+}
+
+#[test]
+fn test_pool_idle_timeout() {
+  let server = support::serve(|_| support::response("200 OK", &[], b"ok"));
+  let client = Client::builder()
+    .pool_idle_timeout(0)
+    .build();
+  for _ in 0..3 {
+    assert!(client.get().url(server.url("/")).emit().is_ok());
+  }
+  assert_eq!(3, server.connections());
+}
+
+#[test]
+fn test_pool_max_total() {
+  let first = support::serve(|_| support::response("200 OK", &[], b"ok"));
+  let second = support::serve(|_| support::response("200 OK", &[], b"ok"));
+  let client = Client::builder()
+    .pool_max_total(1)
+    .build();
+  assert!(client.get().url(first.url("/")).emit().is_ok());
+  assert!(client.get().url(second.url("/")).emit().is_ok());
+  assert_eq!(1, client.idle_connections());
+}
+
+#[test]
+fn test_pool_stale_connection() {
+  let server = support::serve(|req| {
+    if req.path.ends_with("/close") {
+      return support::response("200 OK", &[("X-Test-Close", "true")], b"ok");
+    }
+    support::response("200 OK", &[], b"ok")
+  });
+  let client = client();
+  assert!(client.get().url(server.url("/close")).emit().is_ok());
+  assert_eq!(1, client.idle_connections());
+  let response = client.get().url(server.url("/")).emit().expect("REQUEST FAIL");
+  assert_eq!("ok", response.body().string().unwrap());
+  assert_eq!(2, server.connections());
+}
+
+/// The second request is answered by a broken response, then the connection is closed.
+fn broken_second() -> support::Server {
+  let count = std::sync::atomic::AtomicUsize::new(0);
+  support::serve(move |req| {
+    let body = format!("{} {}", req.method, String::from_utf8_lossy(&req.body));
+    if count.fetch_add(1, std::sync::atomic::Ordering::SeqCst) == 1 {
+      return support::response("200 OK", &[("X-Test-Broken", "true")], body.as_bytes());
+    }
+    support::response("200 OK", &[], body.as_bytes())
+  })
+}
+
+#[test]
+fn test_pool_broken_connection() {
+  // the idempotent request is sent again by a new connection
+  let server = broken_second();
+  let client = client();
+  assert!(client.get().url(server.url("/")).emit().is_ok());
+  let response = client.put().url(server.url("/")).raw("again").emit().expect("REQUEST FAIL");
+  assert_eq!("PUT again", response.body().string().unwrap());
+  assert_eq!(3, server.hits());
+  assert_eq!(2, server.connections());
+
+  // the server maybe handled the request, it's not sent again
+  let server = broken_second();
+  assert!(client.get().url(server.url("/")).emit().is_ok());
+  let err = client.post().url(server.url("/")).raw("once").emit().expect_err("POST SENT AGAIN");
+  assert!(err.to_string().contains("Connection closed"), "{}", err);
+  assert_eq!(2, server.hits());
+  assert_eq!(1, server.connections());
+}
+
+#[test]
+fn test_pool_stale_connection_post() {
+  // the connection closed by server when idle is found before the request is sent
+  let server = support::serve(|req| {
+    support::response("200 OK", &[("X-Test-Close", "true")], req.method.as_bytes())
+  });
+  let client = client();
+  assert!(client.get().url(server.url("/")).emit().is_ok());
+  assert_eq!(1, client.idle_connections());
+  std::thread::sleep(std::time::Duration::from_millis(100));
+  let response = client.post().url(server.url("/")).raw("a=1").emit().expect("REQUEST FAIL");
+  assert_eq!("POST", response.body().string().unwrap());
+  assert_eq!(2, server.connections());
+}
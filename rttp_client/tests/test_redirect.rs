@@ -0,0 +1,213 @@
+use rttp_client::{Client, Config};
+use rttp_client::types::RedirectPolicy;
+
+mod support;
+
+fn client(policy: RedirectPolicy) -> Client {
+  Client::builder()
+    .config(Config::builder().read_timeout(3000).redirect(policy))
+    .build()
+}
+
+/// `/echo` answer the method, path, body, and the credential headers of request,
+/// `/loop` redirect to itself, `/<code>/<url>` redirect to `url` with status `code`.
+fn server() -> support::Server {
+  support::serve(|req| {
+    let path = req.uri_path().to_string();
+    if path.starts_with("/echo") {
+      let auth = req.header("authorization").cloned().unwrap_or_default();
+      let cookie = req.header("cookie").cloned().unwrap_or_default();
+      let text = format!("{} {} {} auth={} cookie={}",
+                         req.method, path, String::from_utf8_lossy(&req.body), auth, cookie);
+      return support::response("200 OK", &[], text.as_bytes());
+    }
+    let relative = match &path[..] {
+      "/relative/a/b" => Some("../next?q=a%20b".to_string()),
+      "/relative/root" => Some("/echo/root".to_string()),
+      "/relative/scheme" => Some(format!("//{}/echo/scheme", req.header("host").unwrap())),
+      _ => None,
+    };
+    if let Some(location) = relative {
+      return support::response("302 Found", &[("Location", &location)], b"");
+    }
+    if path.starts_with("/relative/next") {
+      return support::response("200 OK", &[], path.as_bytes());
+    }
+    if path == "/loop" {
+      let location = format!("http://{}/loop", req.header("host").unwrap());
+      return support::response("302 Found", &[("Location", &location)], b"");
+    }
+    match path.trim_start_matches('/').split_once('/') {
+      Some((code, location)) => {
+        let location = location.replacen(":/", "://", 1);
+        support::response(&format!("{} Redirect", code), &[("Location", &location)], b"")
+      }
+      None => support::response("404 Not Found", &[], b""),
+    }
+  })
+}
+
+fn redirect(server: &support::Server, code: u32, location: &str) -> String {
+  server.url(&format!("/{}/{}", code, location))
+}
+
+#[test]
+fn test_redirect_history() {
+  let first = server();
+  let second = server();
+  let url = redirect(&first, 302, &redirect(&second, 301, &first.url("/echo")));
+  let response = client(RedirectPolicy::limited(5)).get().url(&url).emit().expect("REQUEST FAIL");
+  assert_eq!(200, response.code());
+  assert!(response.body().string().unwrap().starts_with("GET /echo"));
+
+  let history = response.history();
+  assert_eq!(3, history.len());
+  assert_eq!(Some(second.addr().split(':').nth(1).unwrap().parse().unwrap()), history[1].port());
+  assert_eq!("/echo", history[2].path());
+}
+
+#[test]
+fn test_redirect_none() {
+  let server = server();
+  let url = redirect(&server, 302, &server.url("/echo"));
+  let response = client(RedirectPolicy::none()).get().url(&url).emit().expect("REQUEST FAIL");
+  assert_eq!(302, response.code());
+  assert_eq!(1, response.history().len());
+  assert_eq!(1, server.hits());
+}
+
+#[test]
+fn test_redirect_not_redirect_status() {
+  let server = support::serve_text("HTTP/1.1 201 Created\r\nLocation: http://127.0.0.1:1/\r\nContent-Length: 0\r\n\r\n");
+  let response = client(RedirectPolicy::limited(5)).post().url(server.url("/")).emit().expect("REQUEST FAIL");
+  assert_eq!(201, response.code());
+}
+
+#[test]
+fn test_redirect_limit() {
+  let server = server();
+  let url = redirect(&server, 302, &redirect(&server, 302, &server.url("/echo")));
+  let response = client(RedirectPolicy::limited(1)).get().url(&url).emit();
+  assert!(response.err().unwrap().is_redirect());
+  assert!(client(RedirectPolicy::limited(2)).get().url(&url).emit().is_ok());
+}
+
+#[test]
+fn test_redirect_loop() {
+  let server = server();
+  let response = client(RedirectPolicy::limited(5)).get().url(server.url("/loop")).emit();
+  assert!(response.err().unwrap().is_redirect());
+  assert_eq!(1, server.hits());
+}
+
+#[test]
+fn test_redirect_same_url_other_method() {
+  let server = support::serve(|req| match (&req.method[..], req.uri_path()) {
+    ("POST", "/form") => support::response("303 See Other", &[("Location", "/form")], b""),
+    ("PUT", "/form") => support::response("307 Temporary Redirect", &[("Location", "/form")], b""),
+    (method, _) => support::response("200 OK", &[], method.as_bytes()),
+  });
+  let client = client(RedirectPolicy::limited(5));
+  let response = client.post().url(server.url("/form")).emit().expect("REQUEST FAIL");
+  assert_eq!("GET", response.body().string().unwrap());
+  assert_eq!(2, response.history().len());
+
+  // the method is not changed, it's a loop
+  let response = client.put().url(server.url("/form")).emit();
+  assert!(response.err().unwrap().is_redirect());
+}
+
+#[test]
+fn test_redirect_custom() {
+  let server = server();
+  let client = client(RedirectPolicy::custom(|attempt| {
+    if attempt.url().path() == "/echo/login" {
+      attempt.stop()
+    } else {
+      attempt.follow()
+    }
+  }));
+
+  let url = redirect(&server, 302, &server.url("/echo/login"));
+  let response = client.get().url(&url).emit().expect("REQUEST FAIL");
+  assert_eq!(302, response.code());
+
+  let url = redirect(&server, 302, &server.url("/echo/home"));
+  let response = client.get().url(&url).emit().expect("REQUEST FAIL");
+  assert_eq!(200, response.code());
+}
+
+#[test]
+fn test_redirect_same_origin() {
+  let first = server();
+  let second = server();
+  let client = client(RedirectPolicy::limited(5).same_origin(true));
+
+  let url = redirect(&first, 302, &second.url("/echo"));
+  let response = client.get().url(&url).emit().expect("REQUEST FAIL");
+  assert_eq!(302, response.code());
+  assert_eq!(0, second.hits());
+
+  let url = redirect(&first, 302, &first.url("/echo"));
+  let response = client.get().url(&url).emit().expect("REQUEST FAIL");
+  assert_eq!(200, response.code());
+}
+
+#[test]
+fn test_redirect_method() {
+  let server = server();
+  let client = client(RedirectPolicy::limited(5));
+  let send = |method: &str, code: u32| {
+    let url = redirect(&server, code, &server.url("/echo"));
+    client.request().method(method).url(&url).raw("body").emit()
+      .expect("REQUEST FAIL")
+      .body().string().unwrap()
+  };
+  assert!(send("POST", 301).starts_with("GET /echo  "));
+  assert!(send("POST", 302).starts_with("GET /echo  "));
+  assert!(send("POST", 303).starts_with("GET /echo  "));
+  assert!(send("PUT", 303).starts_with("GET /echo  "));
+  assert!(send("POST", 307).starts_with("POST /echo body "));
+  assert!(send("POST", 308).starts_with("POST /echo body "));
+  assert!(send("PUT", 302).starts_with("PUT /echo body "));
+}
+
+#[test]
+fn test_redirect_strip_credentials() {
+  let first = server();
+  let second = server();
+  let client = client(RedirectPolicy::limited(5));
+  let send = |url: String| {
+    client.get()
+      .url(&url)
+      .header(("Authorization", "Bearer token"))
+      .header(("Cookie", "name=value"))
+      .emit()
+      .expect("REQUEST FAIL")
+      .body().string().unwrap()
+  };
+
+  let text = send(redirect(&first, 302, &first.url("/echo")));
+  assert!(text.ends_with("auth=Bearer token cookie=name=value"));
+
+  let text = send(redirect(&first, 302, &second.url("/echo")));
+  assert!(text.ends_with("auth= cookie="));
+}
+
+#[test]
+fn test_redirect_relative_location() {
+  let server = server();
+  let client = client(RedirectPolicy::limited(5));
+
+  let response = client.get().url(server.url("/relative/a/b")).emit().expect("REQUEST FAIL");
+  assert_eq!(200, response.code());
+  assert_eq!("/relative/next", response.url().path());
+  assert_eq!(Some("q=a%20b"), response.url().query());
+
+  let response = client.get().url(server.url("/relative/root")).emit().expect("REQUEST FAIL");
+  assert_eq!(server.url("/echo/root"), response.url().as_str());
+
+  let response = client.get().url(server.url("/relative/scheme")).emit().expect("REQUEST FAIL");
+  assert_eq!(server.url("/echo/scheme"), response.url().as_str());
+  assert_eq!(response.url(), response.history().last().unwrap());
+}
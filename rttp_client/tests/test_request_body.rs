@@ -0,0 +1,396 @@
+use std::fs;
+use std::io::{self, Cursor, Read};
+use std::sync::{Arc, Mutex};
+
+use rttp_client::{Client, Config, HttpClient};
+use rttp_client::types::{FormData, Header, RedirectPolicy};
+
+mod support;
+
+fn pattern(len: usize) -> Vec<u8> {
+  (0..len).map(|i| (i % 251) as u8).collect()
+}
+
+/// A server keeps the requests received.
+fn echo_server() -> (support::Server, Arc<Mutex<Vec<support::Incoming>>>) {
+  let requests = Arc::new(Mutex::new(vec![]));
+  let received = requests.clone();
+  let server = support::serve(move |req| {
+    received.lock().unwrap().push(req.clone());
+    support::response("200 OK", &[], b"ok")
+  });
+  (server, requests)
+}
+
+/// A reader returns data in small pieces, like a pipe.
+struct Trickle(Cursor<Vec<u8>>);
+
+impl Read for Trickle {
+  fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+    let len = buf.len().min(1000);
+    self.0.read(&mut buf[..len])
+  }
+}
+
+#[test]
+fn test_request_body_reader_length() {
+  let (server, requests) = echo_server();
+  let body = pattern(512 * 1024);
+  let response = HttpClient::new()
+    .post()
+    .url(server.url("/upload"))
+    .reader(Trickle(Cursor::new(body.clone())), Some(body.len() as u64))
+    .emit()
+    .expect("REQUEST FAIL");
+  assert_eq!(200, response.code());
+
+  let requests = requests.lock().unwrap();
+  let request = &requests[0];
+  assert_eq!(Some(&body.len().to_string()), request.header("content-length"));
+  assert!(request.header("transfer-encoding").is_none());
+  assert_eq!(Some(&"application/octet-stream".to_string()), request.header("content-type"));
+  assert!(body == request.body);
+}
+
+#[test]
+fn test_request_body_reader_chunked() {
+  let (server, requests) = echo_server();
+  let body = pattern(300 * 1024);
+  let response = HttpClient::new()
+    .put()
+    .url(server.url("/upload"))
+    .content_type("text/plain")
+    .reader(Trickle(Cursor::new(body.clone())), None)
+    .emit()
+    .expect("REQUEST FAIL");
+  assert_eq!(200, response.code());
+
+  let requests = requests.lock().unwrap();
+  let request = &requests[0];
+  assert_eq!(vec!["chunked"], request.headers_of("transfer-encoding"));
+  assert!(request.header("content-length").is_none());
+  assert_eq!(Some(&"text/plain".to_string()), request.header("content-type"));
+  assert!(body == request.body);
+}
+
+#[test]
+fn test_request_body_reader_short() {
+  let (server, requests) = echo_server();
+  let err = HttpClient::new()
+    .post()
+    .url(server.url("/upload"))
+    .reader(Cursor::new(b"short".to_vec()), Some(10))
+    .emit()
+    .expect_err("SHORT BODY");
+  assert!(err.to_string().contains("10 bytes"), "{}", err);
+  assert!(requests.lock().unwrap().is_empty());
+}
+
+#[test]
+fn test_request_body_reader_once() {
+  let (server, requests) = echo_server();
+  let client = Client::builder().build();
+  let mut request = client.post();
+  request.url(server.url("/upload")).reader(Cursor::new(b"once".to_vec()), Some(4));
+  let mut again = request.clone();
+  assert!(request.emit().is_ok());
+  let err = again.emit().expect_err("SENT AGAIN");
+  assert!(err.is_builder(), "{}", err);
+  assert_eq!(1, requests.lock().unwrap().len());
+
+  // the reader body is sent by a new connection, it can't be retried on a stale idle one
+  assert_eq!(1, client.idle_connections());
+  client.post().url(server.url("/upload")).reader(Cursor::new(b"new".to_vec()), None).emit().expect("REQUEST FAIL");
+  assert_eq!(2, server.connections());
+}
+
+#[test]
+fn test_request_body_reader_redirect() {
+  let server = support::serve(|req| match req.uri_path() {
+    "/see-other" => support::response("303 See Other", &[("Location", "/result")], b""),
+    "/temporary" => support::response("307 Temporary Redirect", &[("Location", "/result")], b""),
+    _ => support::response("200 OK", &[], format!("{} {}", req.method, req.body.len()).as_bytes()),
+  });
+  let client = Client::builder()
+    .config(Config::builder().redirect(RedirectPolicy::limited(5)))
+    .build();
+
+  let response = client.post()
+    .url(server.url("/see-other"))
+    .reader(Cursor::new(pattern(100)), None)
+    .emit()
+    .expect("REQUEST FAIL");
+  assert_eq!("GET 0", response.body().string().unwrap());
+
+  // the body can't be sent again to the location
+  let err = client.post()
+    .url(server.url("/temporary"))
+    .reader(Cursor::new(pattern(100)), None)
+    .emit()
+    .expect_err("REDIRECT WITH BODY");
+  assert!(err.is_builder(), "{}", err);
+}
+
+#[test]
+fn test_request_body_multipart_file() {
+  let path = std::env::temp_dir().join(format!("rttp_multipart_{}.bin", std::process::id()));
+  let file = pattern(2 * 1024 * 1024);
+  fs::write(&path, &file).unwrap();
+
+  let (server, requests) = echo_server();
+  let response = HttpClient::new()
+    .post()
+    .url(server.url("/upload"))
+    .form(FormData::with_text("name", "rttp"))
+    .form(FormData::with_file_and_name("file", &path, "data.bin"))
+    .form(FormData::with_binary("binary", b"raw".to_vec()))
+    .emit()
+    .expect("REQUEST FAIL");
+  assert_eq!(200, response.code());
+  fs::remove_file(&path).unwrap();
+
+  let requests = requests.lock().unwrap();
+  let request = &requests[0];
+  let content_type = request.header("content-type").unwrap();
+  let boundary = content_type.split("boundary=").nth(1).unwrap();
+  assert_eq!(Some(&request.body.len().to_string()), request.header("content-length"));
+
+  let delimiter = format!("--{}", boundary);
+  let body = &request.body;
+  let head = format!("{}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"data.bin\"\r\n\
+    Content-Type: application/octet-stream\r\n\r\n", delimiter);
+  let start = body.windows(head.len()).position(|w| w == head.as_bytes()).expect("FILE PART") + head.len();
+  assert!(file[..] == body[start..start + file.len()]);
+  let end = format!("\r\n{}--\r\n", delimiter);
+  assert!(body[start + file.len()..] == *end.as_bytes());
+  let text = String::from_utf8_lossy(&body[..start]);
+  assert!(text.contains("name=\"name\"\r\n\r\nrttp\r\n"));
+  assert!(text.contains("name=\"binary\"; filename=\"\"\r\nContent-Type: application/octet-stream\r\n\r\nraw\r\n"));
+}
+
+#[test]
+#[cfg(feature = "async")]
+fn test_request_body_async_reader() {
+  let (server, requests) = echo_server();
+  let body = pattern(200 * 1024);
+  let expected = body.clone();
+  async_std::task::block_on(async {
+    let response = HttpClient::new()
+      .post()
+      .url(server.url("/upload"))
+      .async_reader(async_std::io::Cursor::new(body), None)
+      .rasync()
+      .await
+      .expect("REQUEST FAIL");
+    assert_eq!(200, response.code());
+  });
+
+  let requests = requests.lock().unwrap();
+  assert_eq!(Some(&"chunked".to_string()), requests[0].header("transfer-encoding"));
+  assert!(expected == requests[0].body);
+
+  // the blocking reader of known length is sent by async client, and the async reader by blocking client
+  drop(requests);
+  let (server, requests) = echo_server();
+  async_std::task::block_on(async {
+    HttpClient::new()
+      .post()
+      .url(server.url("/upload"))
+      .reader(Cursor::new(b"blocking".to_vec()), Some(8))
+      .rasync()
+      .await
+      .expect("REQUEST FAIL");
+  });
+  HttpClient::new()
+    .post()
+    .url(server.url("/upload"))
+    .async_reader(async_std::io::Cursor::new(b"async".to_vec()), Some(5))
+    .emit()
+    .expect("REQUEST FAIL");
+  let requests = requests.lock().unwrap();
+  assert_eq!(b"blocking".to_vec(), requests[0].body);
+  assert_eq!(b"async".to_vec(), requests[1].body);
+  assert_eq!(Some(&"5".to_string()), requests[1].header("content-length"));
+}
+
+/// A reader keeps the threads it's read by.
+#[cfg(feature = "async")]
+struct ThreadReader(Cursor<Vec<u8>>, Arc<Mutex<Vec<std::thread::ThreadId>>>);
+
+#[cfg(feature = "async")]
+impl Read for ThreadReader {
+  fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+    self.1.lock().unwrap().push(std::thread::current().id());
+    self.0.read(buf)
+  }
+}
+
+#[test]
+#[cfg(feature = "async")]
+fn test_request_body_async_blocking_reader() {
+  let (server, requests) = echo_server();
+  let body = pattern(200 * 1024);
+  let threads = Arc::new(Mutex::new(vec![]));
+  let reader = ThreadReader(Cursor::new(body.clone()), threads.clone());
+  async_std::task::block_on(async {
+    HttpClient::new()
+      .post()
+      .url(server.url("/upload"))
+      .reader(reader, None)
+      .rasync()
+      .await
+      .expect("REQUEST FAIL");
+  });
+
+  // the blocking reader is not read by the thread run the future
+  let threads = threads.lock().unwrap();
+  assert!(!threads.is_empty());
+  assert!(threads.iter().all(|id| *id != std::thread::current().id()));
+  assert!(body == requests.lock().unwrap()[0].body);
+}
+
+#[test]
+fn test_request_body_chunked() {
+  let (server, requests) = echo_server();
+  let body = pattern(100 * 1024);
+  HttpClient::new()
+    .post()
+    .url(server.url("/upload"))
+    .chunked(true)
+    .binary(body.clone())
+    .emit()
+    .expect("REQUEST FAIL");
+
+  // the chunks are written as the reader yields them
+  let data = pattern(10 * 1024 + 24);
+  HttpClient::new()
+    .post()
+    .url(server.url("/upload"))
+    .chunked(true)
+    .reader(Trickle(Cursor::new(data.clone())), Some(data.len() as u64))
+    .emit()
+    .expect("REQUEST FAIL");
+
+  let requests = requests.lock().unwrap();
+  assert_eq!(Some(&"chunked".to_string()), requests[0].header("transfer-encoding"));
+  assert!(requests[0].header("content-length").is_none());
+  assert!(requests[0].header("trailer").is_none());
+  assert!(body == requests[0].body);
+  assert_eq!(vec![body.len()], requests[0].chunks);
+
+  assert_eq!(Some(&"chunked".to_string()), requests[1].header("transfer-encoding"));
+  assert!(data == requests[1].body);
+  let mut chunks = vec![1000; 10];
+  chunks.push(264);
+  assert_eq!(chunks, requests[1].chunks);
+}
+
+#[test]
+fn test_request_body_trailers() {
+  let (server, requests) = echo_server();
+  HttpClient::new()
+    .put()
+    .url(server.url("/upload"))
+    .header(("Trailer", "X-Other"))
+    .reader(Trickle(Cursor::new(pattern(4000))), None)
+    .trailer(("X-Checksum", "abc"))
+    .trailer(("X-Count", "4000"))
+    .emit()
+    .expect("REQUEST FAIL");
+
+  let requests = requests.lock().unwrap();
+  let request = &requests[0];
+  assert_eq!(vec!["X-Checksum, X-Count"], request.headers_of("trailer"));
+  assert_eq!(Some(&"abc".to_string()), request.header("x-checksum"));
+  assert_eq!(Some(&"4000".to_string()), request.header("x-count"));
+  assert_eq!(4000, request.body.len());
+  // the trailers follow the header lines
+  let names: Vec<&str> = request.headers.iter().map(|(name, _)| name.as_str()).collect();
+  assert_eq!(["X-Checksum", "X-Count"], names[names.len() - 2..]);
+}
+
+#[test]
+fn test_request_body_content_length() {
+  let (server, requests) = echo_server();
+  let err = HttpClient::new()
+    .post()
+    .url(server.url("/upload"))
+    .header(("Content-Length", "1"))
+    .binary(b"abc".to_vec())
+    .emit()
+    .expect_err("CONTENT LENGTH");
+  assert!(err.is_builder(), "{}", err);
+  assert!(err.to_string().contains("Content-Length 1 is not the body length 3"), "{}", err);
+
+  let response = HttpClient::new()
+    .post()
+    .url(server.url("/upload"))
+    .header(("Content-Length", "3"))
+    .binary(b"abc".to_vec())
+    .emit()
+    .expect("REQUEST FAIL");
+  assert_eq!(200, response.code());
+  let requests = requests.lock().unwrap();
+  assert_eq!(1, requests.len());
+  assert_eq!(vec!["3"], requests[0].headers_of("content-length"));
+  assert_eq!(b"abc".to_vec(), requests[0].body);
+}
+
+#[test]
+fn test_request_body_chunked_refused() {
+  let (server, requests) = echo_server();
+  let err = HttpClient::new()
+    .post()
+    .url(server.url("/upload"))
+    .chunked(true)
+    .header(("Content-Length", "3"))
+    .binary(b"abc".to_vec())
+    .emit()
+    .expect_err("CONTENT LENGTH");
+  assert!(err.is_builder(), "{}", err);
+  assert!(err.to_string().contains("Content-Length"), "{}", err);
+
+  // the trailers can't be sent with content length
+  let err = HttpClient::new()
+    .post()
+    .url(server.url("/upload"))
+    .binary(b"abc".to_vec())
+    .trailer(("X-Checksum", "abc"))
+    .emit()
+    .expect_err("TRAILER");
+  assert!(err.is_builder(), "{}", err);
+
+  let err = HttpClient::new()
+    .post()
+    .url(server.url("/upload"))
+    .chunked(true)
+    .binary(b"abc".to_vec())
+    .trailer(Header::new("X-Checksum", "a\r\nb"))
+    .emit()
+    .expect_err("INVALID TRAILER");
+  assert!(err.is_builder(), "{}", err);
+  assert!(requests.lock().unwrap().is_empty());
+}
+
+#[test]
+#[cfg(feature = "async")]
+fn test_request_body_async_chunked() {
+  let (server, requests) = echo_server();
+  let body = pattern(3000);
+  async_std::task::block_on(async {
+    HttpClient::new()
+      .post()
+      .url(server.url("/upload"))
+      .chunked(true)
+      .async_reader(async_std::io::Cursor::new(body.clone()), Some(3000))
+      .trailer(("X-Checksum", "abc"))
+      .rasync()
+      .await
+      .expect("REQUEST FAIL");
+  });
+
+  let requests = requests.lock().unwrap();
+  assert_eq!(Some(&"chunked".to_string()), requests[0].header("transfer-encoding"));
+  assert_eq!(Some(&"abc".to_string()), requests[0].header("x-checksum"));
+  assert!(body == requests[0].body);
+}
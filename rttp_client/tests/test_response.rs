@@ -1,5 +1,5 @@
 use rttp_client::response::Response;
-use rttp_client::types::RoUrl;
+use rttp_client::types::{RoUrl, StatusCode};
 
 #[test]
 fn test_parse_response() {
@@ -74,3 +74,83 @@ fn test_parse_response_1() {
   let response = response.unwrap();
   println!("{}", response);
 }
+
+#[test]
+fn test_parse_chunked_response() {
+  let s = "HTTP/1.1 200 OK\r\n\
+  Transfer-Encoding: chunked\r\n\
+  Trailer: Expires\r\n\
+  \r\n\
+  5;name=value\r\n\
+  hello\r\n\
+  7\r\n\
+  , rttp!\r\n\
+  0\r\n\
+  Expires: Wed, 21 Oct 2015 07:28:00 GMT\r\n\
+  \r\n";
+  let response = Response::new(RoUrl::with("http://example.com"), s.as_bytes().to_vec()).unwrap();
+  assert_eq!("hello, rttp!", response.body().string().unwrap());
+  assert_eq!(1, response.trailers().len());
+  assert_eq!(Some(&"Wed, 21 Oct 2015 07:28:00 GMT".to_string()), response.trailer_value("expires"));
+  assert!(response.header("expires").is_none());
+}
+
+#[test]
+fn test_parse_bad_chunked_response() {
+  let s = "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\nzz\r\nhello\r\n0\r\n\r\n";
+  assert!(Response::new(RoUrl::with("http://example.com"), s.as_bytes().to_vec()).is_err());
+  let s = "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n10\r\nhello\r\n0\r\n\r\n";
+  assert!(Response::new(RoUrl::with("http://example.com"), s.as_bytes().to_vec()).is_err());
+  let s = "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n3\r\nhello\r\n0\r\n\r\n";
+  assert!(Response::new(RoUrl::with("http://example.com"), s.as_bytes().to_vec()).is_err());
+  // the size overflows
+  let s = "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\nffffffffffffffff\r\nhello\r\n0\r\n\r\n";
+  assert!(Response::new(RoUrl::with("http://example.com"), s.as_bytes().to_vec()).is_err());
+}
+
+fn response_of(status_line: &str) -> rttp_client::error::Result<Response> {
+  let text = format!("{}\r\nContent-Length: 0\r\n\r\n", status_line);
+  Response::new(RoUrl::with("http://example.com/status"), text.into_bytes())
+}
+
+#[test]
+fn test_response_status() {
+  let response = response_of("HTTP/1.1 201 Created").unwrap();
+  assert_eq!(StatusCode::CREATED, response.status());
+  assert!(response.is_success() && response.ok());
+  assert!(!response.is_informational() && !response.is_client_error() && !response.is_server_error());
+
+  assert!(response_of("HTTP/1.1 101 Switching Protocols").unwrap().is_informational());
+  assert!(response_of("HTTP/1.1 404 Not Found").unwrap().is_client_error());
+  assert!(response_of("HTTP/1.1 503 Service Unavailable").unwrap().is_server_error());
+  assert!(!response_of("HTTP/1.1 302 Found").unwrap().ok());
+
+  // the code not known is kept, it's not in any class
+  let response = response_of("HTTP/1.1 999 Custom").unwrap();
+  assert_eq!(999, response.code());
+  assert_eq!(999, response.status());
+  assert_eq!(None, response.status().canonical_reason());
+  assert_eq!("999", response.status().as_str());
+  assert!(!response.is_success() && !response.is_client_error() && !response.is_server_error());
+  assert!(response.error_for_status_ref().is_ok());
+  assert!(response_of("HTTP/1.1 600 Custom").unwrap().error_for_status().is_ok());
+
+  assert!(response_of("HTTP/1.1 99 Bad").is_err());
+  assert!(response_of("HTTP/1.1 099 Bad").is_err());
+  assert!(response_of("HTTP/1.1 65736 Bad").is_err());
+  assert!(response_of("HTTP/1.1 2x0 Bad").is_err());
+}
+
+#[test]
+fn test_response_error_for_status() {
+  let response = response_of("HTTP/1.1 200 OK").unwrap();
+  assert!(response.error_for_status_ref().is_ok());
+  assert!(response.error_for_status().is_ok());
+
+  for (status_line, code) in [("HTTP/1.1 404 Not Found", 404), ("HTTP/1.1 503 Service Unavailable", 503)].iter() {
+    let error = response_of(status_line).unwrap().error_for_status().unwrap_err();
+    assert!(error.is_status());
+    assert_eq!(Some(*code), error.status().map(|s| s.as_u16()));
+    assert_eq!("http://example.com/status", error.url().unwrap().as_str());
+  }
+}
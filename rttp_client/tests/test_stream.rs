@@ -0,0 +1,280 @@
+use std::io::{self, Read, Write};
+use std::net::TcpListener;
+use std::sync::mpsc;
+use std::thread;
+
+use rttp_client::{Client, Config, HttpClient};
+use rttp_client::types::{CookieStore, Proxy, RedirectPolicy};
+
+mod support;
+
+fn client() -> Client {
+  Client::builder()
+    .config(Config::builder().read_timeout(3000))
+    .build()
+}
+
+fn pattern(len: usize) -> Vec<u8> {
+  (0..len).map(|i| (i % 251) as u8).collect()
+}
+
+#[test]
+fn test_stream_before_body() {
+  let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+  let url = format!("http://{}/", listener.local_addr().unwrap());
+  let (send_body, body_wanted) = mpsc::channel::<()>();
+  thread::spawn(move || {
+    let (mut stream, _) = listener.accept().unwrap();
+    let mut buf = [0u8; 1024];
+    let _ = stream.read(&mut buf);
+    stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\n").unwrap();
+    // the body is sent only after the client got the head
+    body_wanted.recv().unwrap();
+    stream.write_all(b"hello").unwrap();
+  });
+
+  let mut response = HttpClient::new().get().url(url).emit_stream().expect("REQUEST FAIL");
+  assert_eq!(200, response.code());
+  assert_eq!(Some(5), response.headers().content_length());
+  send_body.send(()).unwrap();
+  let mut text = String::new();
+  response.read_to_string(&mut text).unwrap();
+  assert_eq!("hello", text);
+}
+
+#[test]
+#[cfg(feature = "deflate")]
+fn test_stream_deflate_before_body() {
+  let mut encoder = flate2::write::ZlibEncoder::new(vec![], flate2::Compression::default());
+  encoder.write_all(b"hello").unwrap();
+  let body = encoder.finish().unwrap();
+  let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+  let url = format!("http://{}/", listener.local_addr().unwrap());
+  let (send_body, body_wanted) = mpsc::channel::<()>();
+  thread::spawn(move || {
+    let (mut stream, _) = listener.accept().unwrap();
+    let mut buf = [0u8; 1024];
+    let _ = stream.read(&mut buf);
+    let head = format!("HTTP/1.1 200 OK\r\nContent-Encoding: deflate\r\nContent-Length: {}\r\n\r\n", body.len());
+    stream.write_all(head.as_bytes()).unwrap();
+    // the format of deflate data is checked when the body is read
+    body_wanted.recv().unwrap();
+    stream.write_all(&body).unwrap();
+  });
+
+  let mut response = HttpClient::new().get().url(url).emit_stream().expect("REQUEST FAIL");
+  assert_eq!(200, response.code());
+  send_body.send(()).unwrap();
+  let mut text = String::new();
+  response.read_to_string(&mut text).unwrap();
+  assert_eq!("hello", text);
+}
+
+#[test]
+fn test_stream_large_body() {
+  let body = pattern(4 * 1024 * 1024);
+  let expected = body.clone();
+  let server = support::serve(move |_| support::response("200 OK", &[], &body));
+
+  let mut response = client().get().url(server.url("/")).emit_stream().expect("REQUEST FAIL");
+  let mut received = vec![];
+  let mut buf = [0u8; 64 * 1024];
+  loop {
+    let size = response.read(&mut buf).unwrap();
+    if size == 0 {
+      break;
+    }
+    received.extend_from_slice(&buf[..size]);
+  }
+  assert_eq!(expected.len(), received.len());
+  assert!(expected == received);
+}
+
+#[test]
+fn test_stream_chunked() {
+  let server = support::serve_text("HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n\
+    5;ext=1\r\nhello\r\n6\r\n, rttp\r\n0\r\nX-Checksum: 42\r\n\r\n");
+  let mut response = client().get().url(server.url("/")).emit_stream().expect("REQUEST FAIL");
+  assert!(response.trailers().is_empty());
+  let mut text = String::new();
+  response.read_to_string(&mut text).unwrap();
+  assert_eq!("hello, rttp", text);
+  assert_eq!(Some(&"42".to_string()), response.trailers().get("x-checksum"));
+
+  let server = support::serve_text("HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\nConnection: close\r\n\r\n5\r\nhel");
+  let mut response = client().get().url(server.url("/")).emit_stream().expect("REQUEST FAIL");
+  let err = response.read_to_end(&mut vec![]).expect_err("INCOMPLETE BODY READ");
+  assert_eq!(io::ErrorKind::UnexpectedEof, err.kind());
+}
+
+#[test]
+#[cfg(feature = "gzip")]
+fn test_stream_gzip() {
+  let body = pattern(256 * 1024);
+  let mut encoder = flate2::write::GzEncoder::new(vec![], flate2::Compression::default());
+  encoder.write_all(&body).unwrap();
+  let gzip = encoder.finish().unwrap();
+  let server = support::serve(move |_| {
+    let mut binary = b"HTTP/1.1 200 OK\r\nContent-Encoding: gzip\r\nTransfer-Encoding: chunked\r\n\r\n".to_vec();
+    for chunk in gzip.chunks(1000) {
+      binary.extend(format!("{:x}\r\n", chunk.len()).into_bytes());
+      binary.extend_from_slice(chunk);
+      binary.extend_from_slice(b"\r\n");
+    }
+    binary.extend_from_slice(b"0\r\n\r\n");
+    binary
+  });
+
+  let mut response = client().get().url(server.url("/")).emit_stream().expect("REQUEST FAIL");
+  let mut received = vec![];
+  response.read_to_end(&mut received).unwrap();
+  assert!(body == received);
+}
+
+#[test]
+fn test_stream_keep_alive() {
+  let server = support::serve(|_| support::response("200 OK", &[], b"hello"));
+  let client = client();
+  for _ in 0..3 {
+    let mut response = client.get().url(server.url("/")).emit_stream().expect("REQUEST FAIL");
+    io::copy(&mut response, &mut io::sink()).unwrap();
+  }
+  assert_eq!(3, server.hits());
+  assert_eq!(1, server.connections());
+
+  // the connection of the unfinished body is not reused
+  let mut response = client.get().url(server.url("/")).emit_stream().expect("REQUEST FAIL");
+  response.read_exact(&mut [0u8; 2]).unwrap();
+  drop(response);
+  assert_eq!(0, client.idle_connections());
+  assert!(client.get().url(server.url("/")).emit().is_ok());
+  assert_eq!(2, server.connections());
+}
+
+#[test]
+fn test_stream_redirect() {
+  let server = support::serve(|req| match req.uri_path() {
+    "/old" => support::response("302 Found", &[("Location", "/new"), ("Set-Cookie", "token=1")], b"moved"),
+    _ => {
+      let cookie = req.header("cookie").cloned().unwrap_or_default();
+      support::response("200 OK", &[], cookie.as_bytes())
+    }
+  });
+  let client = Client::builder()
+    .config(Config::builder().redirect(RedirectPolicy::limited(5)))
+    .cookie_store(CookieStore::new())
+    .build();
+  let mut response = client.get().url(server.url("/old")).emit_stream().expect("REQUEST FAIL");
+  assert_eq!(200, response.code());
+  assert_eq!("/new", response.url().path());
+  assert_eq!(2, response.response().history().len());
+  let mut text = String::new();
+  response.read_to_string(&mut text).unwrap();
+  assert_eq!("token=1", text);
+}
+
+#[test]
+fn test_stream_proxy() {
+  let server = support::serve(|_| support::response("200 OK", &[], b"tunneled"));
+  let proxy = support::serve_proxy();
+  let (host, port) = proxy.addr().split_once(':').unwrap();
+  let mut response = client().get()
+    .url(server.url("/"))
+    .proxy(Proxy::socks5(host, port.parse().unwrap()))
+    .emit_stream()
+    .expect("REQUEST FAIL");
+  let mut text = String::new();
+  response.read_to_string(&mut text).unwrap();
+  assert_eq!("tunneled", text);
+  assert_eq!(1, proxy.hits());
+}
+
+#[test]
+#[cfg(feature = "async")]
+fn test_stream_async() {
+  use async_std::prelude::*;
+
+  let body = pattern(512 * 1024);
+  let expected = body.clone();
+  let server = support::serve(move |_| support::response("200 OK", &[], &body));
+  async_std::task::block_on(async {
+    let mut response = client().get().url(server.url("/")).rasync_stream().await.expect("REQUEST FAIL");
+    assert_eq!(200, response.code());
+    let mut received = vec![];
+    let mut parts = 0;
+    while let Some(part) = response.next().await {
+      received.extend(part.unwrap());
+      parts += 1;
+    }
+    assert!(parts > 1);
+    assert!(expected == received);
+  });
+}
+
+#[test]
+#[cfg(all(feature = "async", feature = "gzip"))]
+fn test_stream_async_gzip() {
+  use async_std::io::ReadExt;
+
+  let mut encoder = flate2::write::GzEncoder::new(vec![], flate2::Compression::default());
+  encoder.write_all(b"hello, async stream").unwrap();
+  let gzip = encoder.finish().unwrap();
+  let server = support::serve(move |_| {
+    let mut binary = b"HTTP/1.1 200 OK\r\nContent-Encoding: gzip\r\nTransfer-Encoding: chunked\r\n\r\n".to_vec();
+    for chunk in gzip.chunks(7) {
+      binary.extend(format!("{:x}\r\n", chunk.len()).into_bytes());
+      binary.extend_from_slice(chunk);
+      binary.extend_from_slice(b"\r\n");
+    }
+    binary.extend_from_slice(b"0\r\nX-Checksum: 42\r\n\r\n");
+    binary
+  });
+  async_std::task::block_on(async {
+    let mut response = client().get().url(server.url("/")).rasync_stream().await.expect("REQUEST FAIL");
+    let mut text = String::new();
+    response.read_to_string(&mut text).await.unwrap();
+    assert_eq!("hello, async stream", text);
+    assert_eq!(Some(&"42".to_string()), response.trailers().get("x-checksum"));
+  });
+}
+
+#[test]
+#[cfg(all(feature = "async", feature = "deflate"))]
+fn test_stream_async_truncated() {
+  use async_std::io::ReadExt;
+
+  let text = "hello, truncated stream ".repeat(100);
+  let mut bodies = vec![];
+  let mut zlib = flate2::write::ZlibEncoder::new(vec![], flate2::Compression::default());
+  zlib.write_all(text.as_bytes()).unwrap();
+  bodies.push(("deflate", zlib.finish().unwrap()));
+  let mut raw = flate2::write::DeflateEncoder::new(vec![], flate2::Compression::default());
+  raw.write_all(text.as_bytes()).unwrap();
+  bodies.push(("deflate", raw.finish().unwrap()));
+  #[cfg(feature = "gzip")]
+  {
+    let mut gzip = flate2::write::GzEncoder::new(vec![], flate2::Compression::default());
+    gzip.write_all(text.as_bytes()).unwrap();
+    bodies.push(("gzip", gzip.finish().unwrap()));
+  }
+  #[cfg(feature = "zstd")]
+  bodies.push(("zstd", zstd::encode_all(text.as_bytes(), 3).unwrap()));
+
+  for (encoding, body) in bodies {
+    for len in [body.len(), body.len() / 2, body.len() - 1] {
+      let binary = body[..len].to_vec();
+      let server = support::serve(move |_| support::response("200 OK", &[("Content-Encoding", encoding)], &binary));
+      async_std::task::block_on(async {
+        let mut response = client().get().url(server.url("/")).rasync_stream().await.expect("REQUEST FAIL");
+        let mut received = String::new();
+        let result = response.read_to_string(&mut received).await;
+        if len == body.len() {
+          assert_eq!(text.len(), result.expect("DECODE FAIL"));
+          assert_eq!(text, received);
+        } else {
+          assert!(result.is_err(), "{} of {} bytes {} decoded", len, body.len(), encoding);
+        }
+      });
+    }
+  }
+}
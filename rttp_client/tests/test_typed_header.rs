@@ -0,0 +1,185 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use rttp_client::HttpClient;
+use rttp_client::types::{Accept, CacheControl, ContentRange, ETag, Header, HeaderMap, Link, RetryAfter};
+
+mod support;
+
+fn headers(lines: &[(&str, &str)]) -> HeaderMap {
+  let mut headers = HeaderMap::new();
+  for (name, value) in lines {
+    headers.append(name, value);
+  }
+  headers
+}
+
+#[test]
+fn test_typed_header_content() {
+  let map = headers(&[("Content-Type", "application/json; charset=utf-8"), ("Content-Length", " 42")]);
+  let mime = map.content_type().unwrap();
+  assert_eq!(mime::APPLICATION_JSON.essence_str(), mime.essence_str());
+  assert_eq!(Some(mime::UTF_8), mime.get_param(mime::CHARSET));
+  assert_eq!(Some(42), map.content_length());
+
+  assert!(headers(&[("Content-Type", "bad")]).content_type().is_none());
+  assert!(headers(&[("Content-Length", "-1")]).content_length().is_none());
+  assert_eq!("text/plain; charset=utf-8", Header::content_type(&mime::TEXT_PLAIN_UTF_8).value());
+  assert_eq!("7", Header::content_length(7).value());
+}
+
+#[test]
+fn test_typed_header_cache_control() {
+  let map = headers(&[("Cache-Control", "public, Max-Age=3600"), ("cache-control", "no-cache=\"Set-Cookie, Vary\"")]);
+  let cache_control = map.cache_control().unwrap();
+  assert!(cache_control.public());
+  assert!(cache_control.no_cache());
+  assert!(!cache_control.no_store());
+  assert_eq!(Some(Duration::from_secs(3600)), cache_control.max_age());
+  assert_eq!(Some(&"Set-Cookie, Vary".to_string()), cache_control.value("no-cache"));
+  assert_eq!(None, cache_control.s_maxage());
+  assert!(HeaderMap::new().cache_control().is_none());
+
+  let cache_control = CacheControl::new()
+    .directive("private")
+    .directive_value("max-age", "0")
+    .directive_value("community", "UCI, x")
+    .clone();
+  assert_eq!("private, max-age=0, community=\"UCI, x\"", Header::cache_control(&cache_control).value());
+  assert_eq!(cache_control, CacheControl::parse(cache_control.to_string()));
+}
+
+#[test]
+fn test_typed_header_etag() {
+  let map = headers(&[("ETag", "\"xyzzy\""), ("If-None-Match", "\"a\", W/\"b\""), ("If-None-Match", "*, \"c,d\"")]);
+  assert_eq!(Some(ETag::strong("xyzzy")), map.etag());
+  assert_eq!(vec![ETag::strong("a"), ETag::weak("b"), ETag::strong("c,d")], map.if_none_match());
+
+  assert!(ETag::parse("W/\"\"").is_none());
+  assert!(ETag::parse("\"a\"b\"").is_none());
+  assert!(ETag::weak("1").weak_eq(&ETag::weak("1")));
+  assert!(!ETag::weak("1").strong_eq(&ETag::weak("1")));
+  assert!(ETag::strong("1").strong_eq(&ETag::strong("1")));
+
+  assert_eq!("W/\"v1\"", Header::etag(ETag::weak("v1")).value());
+  assert_eq!("\"a\", W/\"b\"", Header::if_none_match(&[ETag::strong("a"), ETag::weak("b")]).value());
+  assert_eq!("*", Header::if_none_match(&[]).value());
+}
+
+#[test]
+fn test_typed_header_date() {
+  let time = UNIX_EPOCH + Duration::from_secs(784111777);
+  let map = headers(&[("Last-Modified", "Sun, 06 Nov 1994 08:49:37 GMT"), ("If-Modified-Since", "Sunday, 06-Nov-94 08:49:37 GMT")]);
+  assert_eq!(Some(time), map.last_modified());
+  assert_eq!(Some(time), map.if_modified_since());
+  assert!(headers(&[("Last-Modified", "yesterday")]).last_modified().is_none());
+
+  assert_eq!("Sun, 06 Nov 1994 08:49:37 GMT", Header::last_modified(time).value());
+  assert_eq!("If-Modified-Since", Header::if_modified_since(time).name());
+}
+
+#[test]
+fn test_typed_header_retry_after() {
+  assert_eq!(Some(RetryAfter::Delay(Duration::from_secs(120))), headers(&[("Retry-After", "120")]).retry_after());
+  let time = UNIX_EPOCH + Duration::from_secs(784111777);
+  let retry_after = headers(&[("Retry-After", "Sun, 06 Nov 1994 08:49:37 GMT")]).retry_after().unwrap();
+  assert_eq!(RetryAfter::Date(time), retry_after);
+  assert_eq!(Duration::from_secs(0), retry_after.delay());
+  assert!(RetryAfter::parse("-1").is_none());
+
+  let later = SystemTime::now() + Duration::from_secs(600);
+  let delay = RetryAfter::parse(RetryAfter::Date(later).to_string()).unwrap().delay();
+  assert!(delay > Duration::from_secs(590) && delay <= Duration::from_secs(600));
+  assert_eq!("30", Header::retry_after(RetryAfter::Delay(Duration::from_secs(30))).value());
+}
+
+#[test]
+fn test_typed_header_range() {
+  let map = headers(&[("Content-Range", "bytes 0-499/1234"), ("Accept-Ranges", "none, Bytes")]);
+  let content_range = map.content_range().unwrap();
+  assert_eq!(Some((0, 499)), content_range.range());
+  assert_eq!(Some(500), content_range.range_len());
+  assert_eq!(Some(1234), content_range.complete_length());
+  assert!(map.accept_ranges());
+  assert!(!headers(&[("Accept-Ranges", "none")]).accept_ranges());
+
+  assert_eq!(Some(ContentRange::new(10, 19, None)), ContentRange::parse("bytes 10-19/*"));
+  assert_eq!(Some(ContentRange::unsatisfied(42)), ContentRange::parse("bytes */42"));
+  assert!(ContentRange::parse("bytes */*").is_none());
+  assert!(ContentRange::parse("bytes 20-10/100").is_none());
+  assert!(ContentRange::parse("bytes 0-100/100").is_none());
+  assert!(ContentRange::parse("items 0-1/2").is_none());
+
+  assert_eq!("bytes=100-", Header::range(100, None).value());
+  assert_eq!("bytes=0-99", Header::range(0, Some(99)).value());
+  assert_eq!("bytes 0-99/*", Header::content_range(ContentRange::new(0, 99, None)).value());
+}
+
+#[test]
+fn test_typed_header_link() {
+  let map = headers(&[
+    ("Link", r#"<https://example.com/?page=2>; rel="next"; title="a, b; c", <https://example.com/?page=9>; REL="last""#),
+    ("Link", "</a>; rel=\"prev first\"; rel=ignored"),
+  ]);
+  let links = map.links();
+  assert_eq!(3, links.len());
+  assert_eq!("https://example.com/?page=2", map.link("next").unwrap().uri());
+  assert_eq!(Some(&"a, b; c".to_string()), links[0].param_value("title"));
+  assert_eq!("https://example.com/?page=9", map.link("Last").unwrap().uri());
+  assert_eq!(vec!["prev", "first"], links[2].rels());
+  assert_eq!("/a", map.link("first").unwrap().uri());
+  assert!(map.link("ignored").is_none());
+  assert!(Link::parse("https://example.com; rel=next").is_none());
+
+  let header = Header::link(&[
+    Link::new("https://example.com/?page=3").param("rel", "next").clone(),
+    Link::new("/b").param("rel", "prev first").clone(),
+  ]);
+  assert_eq!(r#"<https://example.com/?page=3>; rel=next, </b>; rel="prev first""#, header.value());
+}
+
+#[test]
+fn test_typed_header_accept() {
+  let map = headers(&[("Accept", "text/html;level=1, text/html;q=0.7"), ("Accept", "text/*;q=0.3, */*;q=0.5, image/png;q=0")]);
+  let accept = map.accept().unwrap();
+  assert_eq!(5, accept.items().len());
+  assert_eq!("text/html;level=1", accept.preferred().unwrap().to_string());
+  assert_eq!(1.0, accept.quality(&"text/html;level=1".parse().unwrap()));
+  assert_eq!(0.7, accept.quality(&mime::TEXT_HTML));
+  assert_eq!(0.3, accept.quality(&mime::TEXT_PLAIN));
+  assert_eq!(0.5, accept.quality(&mime::IMAGE_JPEG));
+  assert_eq!(0.0, accept.quality(&mime::IMAGE_PNG));
+  let sorted: Vec<f32> = accept.sorted().iter().map(|(_, q)| *q).collect();
+  assert_eq!(vec![1.0, 0.7, 0.5, 0.3], sorted);
+
+  assert_eq!(0.0, Accept::parse("application/json").quality(&mime::TEXT_HTML));
+  assert_eq!(1, Accept::parse("bad, text/html;q=2, text/plain").items().len());
+
+  let accept = Accept::new()
+    .media(mime::APPLICATION_JSON)
+    .media_quality(mime::TEXT_STAR, 0.25)
+    .clone();
+  assert_eq!("application/json, text/*;q=0.25", Header::accept(&accept).value());
+}
+
+#[test]
+fn test_typed_header_conditional_request() {
+  let server = support::serve(|req| {
+    let matched = req.header("if-none-match").is_some_and(|v| v.contains("\"v2\""));
+    if matched {
+      return support::response("304 Not Modified", &[("ETag", "\"v2\"")], b"");
+    }
+    support::response("200 OK", &[("ETag", "\"v2\""), ("Cache-Control", "max-age=60")], b"body")
+  });
+
+  let response = HttpClient::new().get().url(server.url("/")).emit().expect("REQUEST FAIL");
+  let etag = response.headers().etag().unwrap();
+  assert_eq!(Some(Duration::from_secs(60)), response.headers().cache_control().unwrap().max_age());
+
+  let response = HttpClient::new()
+    .get()
+    .url(server.url("/"))
+    .header(Header::if_none_match(&[etag]))
+    .emit()
+    .expect("REQUEST FAIL");
+  assert_eq!(304, response.code());
+}